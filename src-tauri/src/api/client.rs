@@ -1,5 +1,5 @@
 use anyhow::Result;
-use reqwest::{Client, header};
+use reqwest::{Client, StatusCode, header};
 use std::time::{Duration, Instant};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -9,6 +9,27 @@ const BASE_URL: &str = "https://a.4cdn.org";
 const USER_AGENT: &str = "ChanDesk/0.1.0";
 const RATE_LIMIT_DURATION: Duration = Duration::from_secs(1);
 
+/// Typed API errors callers may want to match on
+#[derive(Debug, thiserror::Error)]
+pub enum ApiError {
+    #[error("Request failed with status: {0}")]
+    Status(StatusCode),
+}
+
+impl ApiError {
+    /// Whether `err` is a 404 from the upstream site (thread pruned or deleted)
+    pub fn is_not_found(err: &anyhow::Error) -> bool {
+        matches!(err.downcast_ref::<ApiError>(), Some(ApiError::Status(StatusCode::NOT_FOUND)))
+    }
+}
+
+/// Outcome of a conditional (If-Modified-Since) request
+pub enum Conditional<T> {
+    Modified(T),
+    NotModified,
+}
+
+#[derive(Clone)]
 pub struct ChanClient {
     client: Client,
     // Rate limiter: endpoint -> last request time
@@ -60,12 +81,54 @@ impl ChanClient {
         let response = self.client.get(&url).send().await?;
 
         if !response.status().is_success() {
-            anyhow::bail!("Request failed with status: {}", response.status());
+            return Err(ApiError::Status(response.status()).into());
         }
 
         let data = response.json::<T>().await?;
         Ok(data)
     }
+
+    /// Conditional GET against a caller-tracked `Last-Modified` value.
+    /// Returns the parsed body together with the new `Last-Modified` header.
+    pub async fn get_json_since<T>(
+        &self,
+        endpoint: &str,
+        since: Option<&str>,
+    ) -> Result<Conditional<(T, Option<String>)>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.wait_for_rate_limit(endpoint).await;
+
+        let url = format!("{}{}", BASE_URL, endpoint);
+        let mut request = self.client.get(&url);
+
+        if let Some(since) = since {
+            request = request.header(header::IF_MODIFIED_SINCE, since);
+        }
+
+        let response = request.send().await?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+
+        if !response.status().is_success() {
+            return Err(ApiError::Status(response.status()).into());
+        }
+
+        let last_modified = response
+            .headers()
+            .get(header::LAST_MODIFIED)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+
+        // Parse before handing back the timestamp, so a bad response can't
+        // cause later requests to be answered with a 304
+        let data = response.json::<T>().await?;
+
+        Ok(Conditional::Modified((data, last_modified)))
+    }
 }
 
 // Endpoint builders
@@ -1,6 +1,10 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::comment::CommentNode;
 
 // Board types
+/// A board record from `boards.json`. Optional features are flagged with `1`
+/// upstream and omitted when off; they are read as booleans.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Board {
     pub board: String,
@@ -10,6 +14,53 @@ pub struct Board {
     pub pages: u16,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta_description: Option<String>,
+    pub max_filesize: u64,
+    pub max_webm_filesize: u64,
+    pub max_comment_chars: u32,
+    pub max_webm_duration: u32,
+    pub bump_limit: u32,
+    pub image_limit: u32,
+    pub cooldowns: BoardCooldowns,
+    #[serde(default)]
+    pub min_image_width: u32,
+    #[serde(default)]
+    pub min_image_height: u32,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub spoilers: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_spoilers: Option<u32>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub is_archived: bool,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub country_flags: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board_flags: Option<std::collections::BTreeMap<String, String>>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub user_ids: bool,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub oekaki: bool,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub sjis_tags: bool,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub code_tags: bool,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub math_tags: bool,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub text_only: bool,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub forced_anon: bool,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub webm_audio: bool,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    pub require_subject: bool,
+}
+
+/// Seconds to wait between posts of each kind
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BoardCooldowns {
+    pub threads: u32,
+    pub replies: u32,
+    pub images: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,11 +68,50 @@ pub struct BoardList {
     pub boards: Vec<Board>,
 }
 
-// Post type
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Feature flags are `1` in the API; accept booleans too so cached copies
+/// of our own serialized records read back
+fn deserialize_flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Flag {
+        Num(u64),
+        Bool(bool),
+    }
+
+    Ok(match Flag::deserialize(deserializer)? {
+        Flag::Num(n) => n != 0,
+        Flag::Bool(b) => b,
+    })
+}
+
+/// Media ids are numeric on 4chan but strings on the archives and 22chan,
+/// so accept either and normalize to a string.
+fn deserialize_tim<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tim {
+        Num(u64),
+        Str(String),
+    }
+
+    Ok(Option::<Tim>::deserialize(deserializer)?.map(|tim| match tim {
+        Tim::Num(n) => n.to_string(),
+        Tim::Str(s) => s,
+    }))
+}
+
+// Post type - normalized across all sources
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Post {
     pub no: u64,
     pub resto: u64,
+    #[serde(default)]
     pub now: String,
     pub time: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -40,8 +130,8 @@ pub struct Post {
     pub sub: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub com: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub tim: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_tim", skip_serializing_if = "Option::is_none")]
+    pub tim: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub filename: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -67,6 +157,10 @@ pub struct Post {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub closed: Option<u8>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_on: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub replies: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub images: Option<u32>,
@@ -80,34 +174,71 @@ pub struct Post {
     pub unique_ips: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_modified: Option<u64>,
+    /// Set when a highlight filter rule matched
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub highlighted: bool,
+    /// `com` parsed into a tree, see `comment::parse`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub com_ast: Option<Vec<CommentNode>>,
+}
+
+impl Post {
+    /// Post numbers quoted (`>>123`) in the comment, raw or HTML-escaped
+    pub fn quoted_posts(&self) -> Vec<u64> {
+        let Some(com) = &self.com else {
+            return Vec::new();
+        };
+        let text = com.replace("&gt;", ">");
+        text.match_indices(">>")
+            .filter_map(|(i, _)| {
+                let digits: String = text[i + 2..].chars().take_while(|c| c.is_ascii_digit()).collect();
+                digits.parse().ok()
+            })
+            .collect()
+    }
 }
 
 // Thread type
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Thread {
     pub posts: Vec<Post>,
 }
 
-// Catalog types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Catalog types - normalized across all sources
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CatalogThread {
     pub no: u64,
+    #[serde(default)]
+    pub time: u64,
+    #[serde(default)]
     pub last_modified: u64,
+    #[serde(default)]
     pub replies: u32,
+    #[serde(default)]
     pub images: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub page: Option<u8>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sub: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub com: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub tim: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_tim", skip_serializing_if = "Option::is_none")]
+    pub tim: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub filename: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ext: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub w: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub h: Option<u32>,
@@ -127,6 +258,17 @@ pub struct CatalogThread {
     pub semantic_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub unique_ips: Option<u32>,
+    /// Newest replies listed with the thread (4chan), numbers only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub last_replies: Vec<ReplyRef>,
+    /// Set when a highlight filter rule matched
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub highlighted: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplyRef {
+    pub no: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
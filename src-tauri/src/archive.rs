@@ -0,0 +1,367 @@
+/**
+ * Thread Archiver
+ *
+ * Writes a self-contained offline copy of a thread to disk:
+ *
+ *   {dest_dir}/{site}-{board}-{thread_id}/
+ *     thread.json    all posts ever seen, OP first
+ *     index.html     static page with relative links
+ *     thumbs/        thumbnails
+ *     media/         full-size files
+ *
+ * Re-running on the same directory merges in new posts and only downloads
+ * missing files, so an interrupted or outdated archive can be resumed.
+ * Posts deleted upstream stay in the archive.
+ */
+
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+use tokio::fs;
+
+use crate::api::{Post, Thread};
+use crate::comment::{self, CommentFormat, CommentNode, TextStyle};
+use crate::downloads::part_path;
+use crate::media_cache;
+use crate::media_store;
+use crate::sources::{ImageboardSource, Site};
+
+/// Progress event emitted while archiving
+pub const ARCHIVE_PROGRESS_EVENT: &str = "archive-progress";
+
+#[derive(Debug, Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveStage {
+    Thread,
+    Media,
+    Done,
+}
+
+/// Payload of `archive-progress`
+#[derive(Debug, Serialize, Clone)]
+pub struct ArchiveProgress {
+    pub site: Site,
+    pub board: String,
+    pub thread_id: u64,
+    pub stage: ArchiveStage,
+    /// Files processed / total files in the media stage
+    pub done: usize,
+    pub total: usize,
+    pub current: Option<String>,
+}
+
+/// Result of an archive run
+#[derive(Debug, Serialize)]
+pub struct ArchiveSummary {
+    pub path: String,
+    pub posts: usize,
+    pub new_posts: usize,
+    pub files_downloaded: usize,
+    pub files_failed: usize,
+}
+
+/// A file to fetch: (url, path relative to the archive dir, expected MD5)
+type MediaJob = (String, String, Option<String>);
+
+pub async fn archive_thread(
+    app: &AppHandle,
+    client: &Client,
+    source: &dyn ImageboardSource,
+    site: Site,
+    board: &str,
+    thread_id: u64,
+    dest_dir: &Path,
+) -> Result<ArchiveSummary> {
+    let dir = dest_dir.join(format!("{}-{}-{}", site.id(), board, thread_id));
+    fs::create_dir_all(dir.join("thumbs")).await?;
+    fs::create_dir_all(dir.join("media")).await?;
+
+    let progress = |stage, done, total, current: Option<String>| {
+        let event = ArchiveProgress {
+            site,
+            board: board.to_string(),
+            thread_id,
+            stage,
+            done,
+            total,
+            current,
+        };
+        if let Err(e) = app.emit(ARCHIVE_PROGRESS_EVENT, event) {
+            eprintln!("Failed to emit archive progress: {}", e);
+        }
+    };
+
+    progress(ArchiveStage::Thread, 0, 0, None);
+
+    let json_path = dir.join("thread.json");
+    let existing: Option<Thread> = match fs::read(&json_path).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).ok(),
+        Err(_) => None,
+    };
+
+    // A dead thread can still be re-rendered and have its missing files retried
+    let thread = match (source.thread(board, thread_id).await, existing.as_ref()) {
+        (Ok(live), _) => live,
+        (Err(e), Some(archived)) => {
+            eprintln!("Archiving /{}/{} from disk only: {}", board, thread_id, e);
+            archived.clone()
+        }
+        (Err(e), None) => return Err(e),
+    };
+
+    let (thread, new_posts) = merge(existing, thread);
+
+    write_atomic(&json_path, &serde_json::to_vec_pretty(&thread)?).await?;
+    write_atomic(&dir.join("index.html"), render_html(site, board, thread_id, &thread).as_bytes()).await?;
+
+    // Thumbnails first so the page is usable early
+    let mut jobs: Vec<MediaJob> = Vec::new();
+    for post in &thread.posts {
+        if let (Some(tim), Some(ext)) = (&post.tim, &post.ext) {
+            jobs.push((source.thumbnail_url(board, tim), thumb_path(tim), None));
+            jobs.push((source.media_url(board, tim, ext), media_path(tim, ext), post.md5.clone()));
+        }
+    }
+    jobs.sort_by_key(|(_, path, _)| !path.starts_with("thumbs/"));
+
+    let total = jobs.len();
+    let mut files_downloaded = 0;
+    let mut files_failed = 0;
+
+    for (done, (url, rel_path, md5)) in jobs.into_iter().enumerate() {
+        let path = dir.join(&rel_path);
+        if fs::try_exists(&path).await.unwrap_or(false) {
+            continue;
+        }
+
+        progress(ArchiveStage::Media, done, total, Some(rel_path.clone()));
+        match download(client, &url, &path, md5.as_deref()).await {
+            Ok(()) => files_downloaded += 1,
+            Err(e) => {
+                eprintln!("Failed to archive {}: {}", url, e);
+                files_failed += 1;
+            }
+        }
+    }
+
+    // Keep whatever the app cached for this thread alongside the archive
+    if let Err(e) = media_cache::pin_thread(board, thread_id, "archived") {
+        eprintln!("Failed to pin archived thread: {}", e);
+    }
+
+    progress(ArchiveStage::Done, total, total, None);
+
+    Ok(ArchiveSummary {
+        path: dir.to_string_lossy().to_string(),
+        posts: thread.posts.len(),
+        new_posts,
+        files_downloaded,
+        files_failed,
+    })
+}
+
+/// Merge a fresh fetch into the archived copy, keeping posts deleted upstream.
+/// Returns the merged thread and how many posts were new.
+fn merge(existing: Option<Thread>, live: Thread) -> (Thread, usize) {
+    let Some(existing) = existing else {
+        let count = live.posts.len();
+        return (live, count);
+    };
+
+    let mut posts: BTreeMap<u64, Post> = existing.posts.into_iter().map(|p| (p.no, p)).collect();
+    let before = posts.len();
+    for post in live.posts {
+        // Live data wins so OP status (archived, closed) stays current
+        posts.insert(post.no, post);
+    }
+    let new_posts = posts.len() - before;
+
+    // Post numbers increase, so BTreeMap order keeps the OP first
+    (Thread { posts: posts.into_values().collect() }, new_posts)
+}
+
+fn thumb_path(tim: &str) -> String {
+    format!("thumbs/{}s.jpg", tim)
+}
+
+fn media_path(tim: &str, ext: &str) -> String {
+    format!("media/{}{}", tim, ext)
+}
+
+/// Write via a temp file so an interrupted run never leaves a truncated file
+async fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = part_path(path);
+    fs::write(&tmp, bytes).await?;
+    fs::rename(&tmp, path).await?;
+    Ok(())
+}
+
+async fn download(client: &Client, url: &str, path: &Path, md5: Option<&str>) -> Result<()> {
+    let response = client.get(url).send().await?.error_for_status()?;
+    let bytes = response.bytes().await?;
+    media_store::verify(&bytes, md5)?;
+    write_atomic(path, &bytes)
+        .await
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const ARCHIVE_CSS: &str = r#"
+body { background: #eef2ff; color: #000; font: 13px arial, helvetica, sans-serif; margin: 0 1em; }
+h1 { color: #af0a0f; font-size: 20px; text-align: center; }
+.post { background: #d6daf0; border: 1px solid #b7c5d9; display: table; margin: 4px 0; padding: 4px 8px; }
+.post.op { background: none; border: none; }
+.info { margin-bottom: 4px; }
+.subject { color: #0f0c5d; font-weight: bold; }
+.name { color: #117743; font-weight: bold; }
+.file { font-size: 11px; }
+.file img { float: left; margin: 4px 16px 4px 0; }
+blockquote { margin: 0; overflow-wrap: anywhere; }
+.quote { color: #789922; }
+a.quotelink { color: #d00; }
+s { background: #000; color: #000; text-decoration: none; }
+s:hover { color: #fff; }
+.deadlink { color: #789922; text-decoration: line-through; }
+"#;
+
+/// Static HTML page for the archive. 4chan-style comments keep the site's own
+/// markup; plain-text comments are rendered from their parsed tree. Either
+/// way `#p123` quote links resolve to the post anchors below.
+fn render_html(site: Site, board: &str, thread_id: u64, thread: &Thread) -> String {
+    let title = thread
+        .posts
+        .first()
+        .and_then(|op| op.sub.clone())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| format!("/{}/ - {}", board, thread_id));
+    let title = escape_html(&title);
+    let board = escape_html(board);
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n", title, ARCHIVE_CSS));
+    html.push_str(&format!("<h1>/{}/ - {}</h1>\n<p>Archived from {} on {}</p>\n<hr>\n",
+        board,
+        title,
+        site.id(),
+        chrono::Utc::now().format("%Y-%m-%d %H:%M UTC"),
+    ));
+
+    for (i, post) in thread.posts.iter().enumerate() {
+        let class = if i == 0 { "post op" } else { "post" };
+        html.push_str(&format!("<div class=\"{}\" id=\"p{}\">\n<div class=\"info\">", class, post.no));
+        if let Some(sub) = post.sub.as_deref().filter(|s| !s.is_empty()) {
+            html.push_str(&format!("<span class=\"subject\">{}</span> ", escape_html(sub)));
+        }
+        html.push_str(&format!(
+            "<span class=\"name\">{}</span>{} {} <a href=\"#p{}\">No.{}</a></div>\n",
+            escape_html(post.name.as_deref().unwrap_or("Anonymous")),
+            escape_html(post.trip.as_deref().unwrap_or("")),
+            escape_html(&post.now),
+            post.no,
+            post.no,
+        ));
+
+        if let (Some(tim), Some(ext)) = (&post.tim, &post.ext) {
+            let media = media_path(tim, ext);
+            let label = format!("{}{}", post.filename.as_deref().unwrap_or(tim), ext);
+            html.push_str(&format!(
+                "<div class=\"file\">File: <a href=\"{0}\">{1}</a><br><a href=\"{0}\"><img src=\"{2}\" loading=\"lazy\" alt=\"{1}\"{3}></a></div>\n",
+                escape_html(&media),
+                escape_html(&label),
+                escape_html(&thumb_path(tim)),
+                match (post.tn_w, post.tn_h) {
+                    (Some(w), Some(h)) => format!(" width=\"{}\" height=\"{}\"", w, h),
+                    _ => String::new(),
+                },
+            ));
+        }
+
+        html.push_str(&format!("<blockquote>{}</blockquote>\n</div>\n", render_comment(site, post)));
+        if i == 0 {
+            html.push_str("<hr>\n");
+        }
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_comment(site: Site, post: &Post) -> String {
+    let Some(com) = post.com.as_deref() else {
+        return String::new();
+    };
+    match site.comment_format() {
+        CommentFormat::Html => com.to_string(),
+        CommentFormat::Text => {
+            let parsed;
+            let nodes = match &post.com_ast {
+                Some(nodes) => nodes,
+                None => {
+                    parsed = comment::parse(com, CommentFormat::Text);
+                    &parsed
+                }
+            };
+            let mut html = String::new();
+            render_nodes(nodes, &mut html);
+            html
+        }
+    }
+}
+
+fn render_nodes(nodes: &[CommentNode], html: &mut String) {
+    for node in nodes {
+        match node {
+            CommentNode::Text { text } => html.push_str(&escape_html(text)),
+            CommentNode::LineBreak => html.push_str("<br>"),
+            CommentNode::Quote { no, thread: None } => {
+                html.push_str(&format!("<a class=\"quotelink\" href=\"#p{0}\">&gt;&gt;{0}</a>", no))
+            }
+            CommentNode::Quote { no, thread: Some(_) } => html.push_str(&format!("&gt;&gt;{}", no)),
+            CommentNode::CrossQuote { board, no, .. } => {
+                html.push_str(&format!("&gt;&gt;&gt;/{}/", escape_html(board)));
+                if let Some(no) = no {
+                    html.push_str(&no.to_string());
+                }
+            }
+            CommentNode::DeadLink { board, no } => {
+                let board = board.as_deref().map(|b| format!("&gt;/{}/", escape_html(b))).unwrap_or_default();
+                html.push_str(&format!("<span class=\"deadlink\">&gt;&gt;{}{}</span>", board, no));
+            }
+            CommentNode::Greentext { children } => wrap("<span class=\"quote\">", "</span>", children, html),
+            CommentNode::Spoiler { children } => wrap("<s>", "</s>", children, html),
+            CommentNode::Styled { style, children } => {
+                let tag = match style {
+                    TextStyle::Bold => "b",
+                    TextStyle::Italic => "i",
+                    TextStyle::Underline => "u",
+                };
+                wrap(&format!("<{}>", tag), &format!("</{}>", tag), children, html);
+            }
+            CommentNode::Code { text } => html.push_str(&format!("<pre>{}</pre>", escape_html(text))),
+            CommentNode::Math { text, display } => {
+                let tag = if *display { "eqn" } else { "math" };
+                html.push_str(&format!("[{0}]{1}[/{0}]", tag, escape_html(text)));
+            }
+            CommentNode::Url { url } => {
+                let url = escape_html(url);
+                html.push_str(&format!("<a href=\"{0}\" rel=\"noreferrer\">{0}</a>", url));
+            }
+        }
+    }
+}
+
+fn wrap(open: &str, close: &str, children: &[CommentNode], html: &mut String) {
+    html.push_str(open);
+    render_nodes(children, html);
+    html.push_str(close);
+}
@@ -0,0 +1,398 @@
+/**
+ * Board Activity
+ *
+ * Background sampler that records catalog snapshots of the tracked boards
+ * so activity can be charted over time and "popular" can mean fast rather
+ * than merely big.
+ *
+ * Each sample stores, per board: thread count, total replies and images,
+ * the highest post number seen (its growth gives board-wide posts per
+ * minute), how many threads appeared since the previous sample, and the
+ * fastest threads. Per thread, the latest reply count and its velocity
+ * (replies per minute since the previous sample) are kept separately.
+ *
+ * Posts per minute needs the reply numbers 4chan lists with each catalog
+ * thread; catalogs without them (FoolFuuka, 22chan) only reveal OP numbers,
+ * so it is left unset there. Samples requested within `MIN_SAMPLE_SECS` of
+ * the previous one return that one instead, since velocities over a few
+ * seconds are mostly noise.
+ */
+
+use anyhow::Result;
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::api::CatalogThread;
+use crate::cache;
+use crate::sources::{Site, Sources};
+
+const META_KEY: &str = "activity_boards";
+
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Samples closer together than this are not taken
+const MIN_SAMPLE_SECS: i64 = 2 * 60;
+/// Snapshots older than this are deleted
+const RETENTION_SECS: i64 = 30 * 24 * 60 * 60;
+/// Threads missing from the catalog for this long are forgotten
+const THREAD_RETENTION_SECS: i64 = 24 * 60 * 60;
+/// Fastest threads kept in each snapshot
+const TOP_THREADS: usize = 5;
+
+/// Boards sampled until the user picks their own
+const DEFAULT_BOARDS: [&str; 8] = ["g", "v", "a", "tv", "sp", "fit", "int", "sci"];
+
+/// A board the sampler follows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackedBoard {
+    pub site: Site,
+    pub board: String,
+}
+
+/// A thread and how fast it is moving
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadVelocity {
+    pub site: Site,
+    pub board: String,
+    pub thread_id: u64,
+    pub subject: Option<String>,
+    pub replies: u32,
+    pub images: u32,
+    /// Thread creation time
+    pub created_at: i64,
+    /// Replies per minute since the previous sample, or over the thread's
+    /// lifetime when first seen
+    pub velocity: f64,
+}
+
+/// One sample of a board
+#[derive(Debug, Clone, Serialize)]
+pub struct BoardSnapshot {
+    pub site: Site,
+    pub board: String,
+    pub taken_at: i64,
+    pub threads: u32,
+    pub replies: u64,
+    pub images: u64,
+    pub max_post_no: u64,
+    /// Seconds since the previous sample; unset for the first one
+    pub interval_secs: Option<i64>,
+    /// Board-wide posts per minute since the previous sample; unset for
+    /// catalogs that don't list reply numbers
+    pub posts_per_minute: Option<f64>,
+    /// Threads created since the previous sample
+    pub new_threads: Option<u32>,
+    pub top_threads: Vec<ThreadVelocity>,
+}
+
+/// New threads per hour over a window
+#[derive(Debug, Clone, Serialize)]
+pub struct NewThreadRate {
+    pub site: Site,
+    pub board: String,
+    pub hours: u32,
+    pub new_threads: u32,
+    /// Seconds of the window actually covered by samples
+    pub covered_secs: i64,
+    pub per_hour: f64,
+}
+
+/// Boards being sampled
+pub fn tracked_boards() -> Result<Vec<TrackedBoard>> {
+    match cache::get_meta(META_KEY)? {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Ok(DEFAULT_BOARDS
+            .iter()
+            .map(|b| TrackedBoard { site: Site::FourChan, board: b.to_string() })
+            .collect()),
+    }
+}
+
+pub fn set_tracked_boards(boards: &[TrackedBoard]) -> Result<()> {
+    cache::set_meta(META_KEY, &serde_json::to_string(boards)?)?;
+    Ok(())
+}
+
+/// Sample the tracked boards in the background
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        loop {
+            let sources = app.state::<Sources>();
+            match tracked_boards() {
+                Ok(boards) => {
+                    for tracked in boards {
+                        if let Err(e) = sample(&sources, tracked.site, &tracked.board).await {
+                            eprintln!("Failed to sample {} /{}/: {}", tracked.site.id(), tracked.board, e);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Failed to read tracked boards: {}", e),
+            }
+            if let Err(e) = prune() {
+                eprintln!("Failed to prune board activity: {}", e);
+            }
+            tokio::time::sleep(SAMPLE_INTERVAL).await;
+        }
+    });
+}
+
+/// Take a catalog snapshot of one board, or return the latest one if it is
+/// less than `MIN_SAMPLE_SECS` old
+pub async fn sample(sources: &Sources, site: Site, board: &str) -> Result<BoardSnapshot> {
+    let previous = latest(site, board)?;
+    if let Some(recent) = previous.as_ref().filter(|p| Utc::now().timestamp() - p.taken_at < MIN_SAMPLE_SECS) {
+        return Ok(recent.clone());
+    }
+
+    let catalog = sources.get(site).catalog(board).await?;
+    let now = Utc::now().timestamp();
+    let lists_replies = catalog.iter().any(|t| !t.last_replies.is_empty());
+
+    let max_post_no = catalog
+        .iter()
+        .flat_map(|t| std::iter::once(t.no).chain(t.last_replies.iter().map(|r| r.no)))
+        .max()
+        .unwrap_or(0);
+
+    let interval_secs = previous.as_ref().map(|p| now - p.taken_at).filter(|&secs| secs > 0);
+    let (posts_per_minute, new_threads) = match (&previous, interval_secs) {
+        (Some(previous), Some(secs)) => (
+            lists_replies.then(|| max_post_no.saturating_sub(previous.max_post_no) as f64 * 60.0 / secs as f64),
+            Some(catalog.iter().filter(|t| t.no > previous.max_post_no).count() as u32),
+        ),
+        _ => (None, None),
+    };
+
+    let mut velocities = record_threads(site, board, &catalog, now)?;
+    velocities.sort_by(|a, b| b.velocity.total_cmp(&a.velocity));
+    velocities.truncate(TOP_THREADS);
+
+    let snapshot = BoardSnapshot {
+        site,
+        board: board.to_string(),
+        taken_at: now,
+        threads: catalog.len() as u32,
+        replies: catalog.iter().map(|t| t.replies as u64).sum(),
+        images: catalog.iter().map(|t| t.images as u64).sum(),
+        max_post_no,
+        interval_secs,
+        posts_per_minute,
+        new_threads,
+        top_threads: velocities,
+    };
+    save_snapshot(&snapshot)?;
+    Ok(snapshot)
+}
+
+/// Update the per-thread reply counts and return every thread's velocity
+fn record_threads(site: Site, board: &str, catalog: &[CatalogThread], now: i64) -> Result<Vec<ThreadVelocity>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    // One commit for the whole catalog rather than one per thread
+    let tx = conn.unchecked_transaction()?;
+
+    let mut velocities = Vec::with_capacity(catalog.len());
+    for thread in catalog {
+        let previous: Option<(u32, i64)> = tx
+            .query_row(
+                "SELECT replies, last_seen FROM thread_activity WHERE site = ?1 AND board = ?2 AND thread_id = ?3",
+                params![site.id(), board, thread.no as i64],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()?;
+
+        let velocity = match previous {
+            Some((replies, last_seen)) if now > last_seen => {
+                thread.replies.saturating_sub(replies) as f64 * 60.0 / (now - last_seen) as f64
+            }
+            // Previous sample just now; keep the lifetime average
+            _ => thread.replies as f64 * 60.0 / (now - thread.time as i64).max(60) as f64,
+        };
+
+        tx.execute(
+            r#"INSERT OR REPLACE INTO thread_activity
+               (site, board, thread_id, subject, replies, images, created_at, last_seen, velocity)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"#,
+            params![
+                site.id(),
+                board,
+                thread.no as i64,
+                thread.sub,
+                thread.replies,
+                thread.images,
+                thread.time as i64,
+                now,
+                velocity
+            ],
+        )?;
+
+        velocities.push(ThreadVelocity {
+            site,
+            board: board.to_string(),
+            thread_id: thread.no,
+            subject: thread.sub.clone(),
+            replies: thread.replies,
+            images: thread.images,
+            created_at: thread.time as i64,
+            velocity,
+        });
+    }
+    tx.commit()?;
+    Ok(velocities)
+}
+
+fn save_snapshot(snapshot: &BoardSnapshot) -> Result<()> {
+    let top_threads = serde_json::to_string(&snapshot.top_threads)?;
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute(
+        r#"INSERT INTO board_snapshots
+           (site, board, taken_at, threads, replies, images, max_post_no, interval_secs,
+            posts_per_minute, new_threads, top_threads)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"#,
+        params![
+            snapshot.site.id(),
+            snapshot.board,
+            snapshot.taken_at,
+            snapshot.threads,
+            snapshot.replies as i64,
+            snapshot.images as i64,
+            snapshot.max_post_no as i64,
+            snapshot.interval_secs,
+            snapshot.posts_per_minute,
+            snapshot.new_threads,
+            top_threads
+        ],
+    )?;
+    Ok(())
+}
+
+const SNAPSHOT_COLUMNS: &str = "site, board, taken_at, threads, replies, images, max_post_no, interval_secs, \
+    posts_per_minute, new_threads, top_threads";
+
+fn row_to_snapshot(row: &rusqlite::Row) -> rusqlite::Result<BoardSnapshot> {
+    let site: String = row.get(0)?;
+    let top_threads: String = row.get(10)?;
+    Ok(BoardSnapshot {
+        site: Site::from_id(&site).unwrap_or(Site::FourChan),
+        board: row.get(1)?,
+        taken_at: row.get(2)?,
+        threads: row.get(3)?,
+        replies: row.get::<_, i64>(4)? as u64,
+        images: row.get::<_, i64>(5)? as u64,
+        max_post_no: row.get::<_, i64>(6)? as u64,
+        interval_secs: row.get(7)?,
+        posts_per_minute: row.get(8)?,
+        new_threads: row.get(9)?,
+        top_threads: serde_json::from_str(&top_threads).unwrap_or_default(),
+    })
+}
+
+fn latest(site: Site, board: &str) -> Result<Option<BoardSnapshot>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let snapshot = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM board_snapshots WHERE site = ?1 AND board = ?2 ORDER BY taken_at DESC LIMIT 1",
+                SNAPSHOT_COLUMNS
+            ),
+            params![site.id(), board],
+            row_to_snapshot,
+        )
+        .optional()?;
+    Ok(snapshot)
+}
+
+/// Snapshots of a board taken at or after `since`, oldest first
+pub fn history(site: Site, board: &str, since: i64) -> Result<Vec<BoardSnapshot>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM board_snapshots WHERE site = ?1 AND board = ?2 AND taken_at >= ?3 ORDER BY taken_at ASC",
+        SNAPSHOT_COLUMNS
+    ))?;
+    let snapshots = stmt
+        .query_map(params![site.id(), board, since], row_to_snapshot)?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(snapshots)
+}
+
+/// Fastest threads still in their catalog as of the last sample, optionally
+/// limited to one site or board
+pub fn fastest_threads(site: Option<Site>, board: Option<&str>, limit: u32) -> Result<Vec<ThreadVelocity>> {
+    // Threads missing from the last couple of samples have fallen off the board
+    let alive_since = Utc::now().timestamp() - 2 * SAMPLE_INTERVAL.as_secs() as i64;
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let mut stmt = conn.prepare(
+        r#"SELECT site, board, thread_id, subject, replies, images, created_at, velocity
+           FROM thread_activity
+           WHERE last_seen >= ?1 AND (?2 IS NULL OR site = ?2) AND (?3 IS NULL OR board = ?3)
+           ORDER BY velocity DESC LIMIT ?4"#,
+    )?;
+    let threads = stmt
+        .query_map(params![alive_since, site.map(|s| s.id()), board, limit], |row| {
+            let site: String = row.get(0)?;
+            Ok(ThreadVelocity {
+                site: Site::from_id(&site).unwrap_or(Site::FourChan),
+                board: row.get(1)?,
+                thread_id: row.get::<_, i64>(2)? as u64,
+                subject: row.get(3)?,
+                replies: row.get(4)?,
+                images: row.get(5)?,
+                created_at: row.get(6)?,
+                velocity: row.get(7)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(threads)
+}
+
+/// Last sampled velocity of each thread on a board
+pub fn velocities(site: Site, board: &str) -> Result<HashMap<u64, f64>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let mut stmt = conn.prepare("SELECT thread_id, velocity FROM thread_activity WHERE site = ?1 AND board = ?2")?;
+    let velocities = stmt
+        .query_map(params![site.id(), board], |r| Ok((r.get::<_, i64>(0)? as u64, r.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(velocities)
+}
+
+/// Threads created per hour on a board over the last `hours`
+pub fn new_thread_rate(site: Site, board: &str, hours: u32) -> Result<NewThreadRate> {
+    let since = Utc::now().timestamp() - hours as i64 * 3600;
+    let snapshots = history(site, board, since)?;
+
+    let (new_threads, covered_secs) = snapshots
+        .iter()
+        .filter_map(|s| Some((s.new_threads?, s.interval_secs?)))
+        .fold((0, 0), |(threads, secs), (n, interval)| (threads + n, secs + interval));
+
+    Ok(NewThreadRate {
+        site,
+        board: board.to_string(),
+        hours,
+        new_threads,
+        covered_secs,
+        per_hour: if covered_secs > 0 { new_threads as f64 * 3600.0 / covered_secs as f64 } else { 0.0 },
+    })
+}
+
+fn prune() -> Result<()> {
+    let now = Utc::now().timestamp();
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute("DELETE FROM board_snapshots WHERE taken_at < ?1", params![now - RETENTION_SECS])?;
+    conn.execute("DELETE FROM thread_activity WHERE last_seen < ?1", params![now - THREAD_RETENTION_SECS])?;
+    Ok(())
+}
@@ -0,0 +1,159 @@
+/**
+ * Board Capabilities
+ *
+ * Full 4chan board records from `boards.json` (upload limits, cooldowns,
+ * bump/image limits and feature flags) merged with the static categories in
+ * `boards_metadata`. Records are fetched once per session (and again
+ * whenever the board list is refreshed) and kept in `cache_meta`, so posting
+ * still knows the limits when 4chan is unreachable.
+ */
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::api::{boards_endpoint, Board, BoardCooldowns, BoardList, ChanClient};
+use crate::boards_metadata::{categorize_board, BoardCategory};
+use crate::cache;
+
+const META_KEY: &str = "boards_json";
+
+lazy_static! {
+    static ref BOARDS: Mutex<Option<HashMap<String, Board>>> = Mutex::new(None);
+}
+
+/// Everything known about a board, for posting and the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct BoardCapabilities {
+    pub id: String,
+    pub name: String,
+    pub category: BoardCategory,
+    pub category_name: String,
+    pub nsfw: bool,
+    pub description: Option<String>,
+    pub pages: u16,
+    pub per_page: u16,
+    pub max_filesize: u64,
+    pub max_webm_filesize: u64,
+    pub max_comment_chars: u32,
+    pub max_webm_duration: u32,
+    pub min_image_width: u32,
+    pub min_image_height: u32,
+    pub bump_limit: u32,
+    pub image_limit: u32,
+    pub cooldowns: BoardCooldowns,
+    pub spoilers: bool,
+    pub custom_spoilers: Option<u32>,
+    pub is_archived: bool,
+    pub country_flags: bool,
+    pub board_flags: Option<std::collections::BTreeMap<String, String>>,
+    pub user_ids: bool,
+    pub oekaki: bool,
+    pub sjis_tags: bool,
+    pub code_tags: bool,
+    pub math_tags: bool,
+    pub text_only: bool,
+    pub forced_anon: bool,
+    pub webm_audio: bool,
+    pub require_subject: bool,
+}
+
+impl From<Board> for BoardCapabilities {
+    fn from(board: Board) -> Self {
+        let category = categorize_board(&board.board);
+        Self {
+            category_name: category.display_name().to_string(),
+            category,
+            nsfw: board.ws_board == 0,
+            id: board.board,
+            name: board.title,
+            description: board.meta_description,
+            pages: board.pages,
+            per_page: board.per_page,
+            max_filesize: board.max_filesize,
+            max_webm_filesize: board.max_webm_filesize,
+            max_comment_chars: board.max_comment_chars,
+            max_webm_duration: board.max_webm_duration,
+            min_image_width: board.min_image_width,
+            min_image_height: board.min_image_height,
+            bump_limit: board.bump_limit,
+            image_limit: board.image_limit,
+            cooldowns: board.cooldowns,
+            spoilers: board.spoilers,
+            custom_spoilers: board.custom_spoilers,
+            is_archived: board.is_archived,
+            country_flags: board.country_flags,
+            board_flags: board.board_flags,
+            user_ids: board.user_ids,
+            oekaki: board.oekaki,
+            sjis_tags: board.sjis_tags,
+            code_tags: board.code_tags,
+            math_tags: board.math_tags,
+            text_only: board.text_only,
+            forced_anon: board.forced_anon,
+            webm_audio: board.webm_audio,
+            require_subject: board.require_subject,
+        }
+    }
+}
+
+/// Replace the known board records with a freshly fetched list
+pub fn remember(list: &BoardList) {
+    if let Err(e) = save(list) {
+        eprintln!("Failed to save board records: {}", e);
+    }
+    keep(list);
+}
+
+fn save(list: &BoardList) -> Result<()> {
+    cache::set_meta(META_KEY, &serde_json::to_string(list)?)?;
+    Ok(())
+}
+
+fn keep(list: &BoardList) {
+    let boards = list.boards.iter().map(|b| (b.board.clone(), b.clone())).collect();
+    if let Ok(mut cached) = BOARDS.lock() {
+        *cached = Some(boards);
+    }
+}
+
+/// Board records, fetched on first use. Falls back to the copy saved by an
+/// earlier session when the fetch fails.
+async fn records(client: &ChanClient) -> Result<HashMap<String, Board>> {
+    if let Some(boards) = BOARDS.lock().map_err(|e| anyhow!("Lock error: {}", e))?.as_ref() {
+        return Ok(boards.clone());
+    }
+
+    let list = match client.get_json::<BoardList>(&boards_endpoint()).await {
+        Ok(list) => {
+            remember(&list);
+            list
+        }
+        Err(e) => {
+            let saved = cache::get_meta(META_KEY)?.ok_or(e)?;
+            let list = serde_json::from_str::<BoardList>(&saved)?;
+            keep(&list);
+            list
+        }
+    };
+
+    Ok(list.boards.into_iter().map(|b| (b.board.clone(), b)).collect())
+}
+
+/// Capabilities of one board
+pub async fn get(client: &ChanClient, board: &str) -> Result<BoardCapabilities> {
+    records(client)
+        .await?
+        .remove(board)
+        .map(BoardCapabilities::from)
+        .ok_or_else(|| anyhow!("Unknown board /{}/", board))
+}
+
+/// Capabilities of every board, sorted by id
+pub async fn all(client: &ChanClient) -> Result<Vec<BoardCapabilities>> {
+    let mut boards: Vec<BoardCapabilities> = records(client).await?.into_values().map(BoardCapabilities::from).collect();
+    boards.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(boards)
+}
@@ -0,0 +1,140 @@
+/**
+ * Board Lists
+ *
+ * Live board lists for every site (4chan `boards.json`, the FoolFuuka
+ * archives API, the 22chan front page), cached in SQLite for `TTL`.
+ * When a refresh fails the last cached list is used, however old, and
+ * without one the source's static table. Refreshes that add or remove boards
+ * emit `board-list-changed` so the UI can update its menus.
+ */
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::cache;
+use crate::sources::{Site, SiteBoard, Sources};
+
+/// Event emitted when a refresh finds new or removed boards
+pub const BOARD_LIST_EVENT: &str = "board-list-changed";
+
+/// How long a fetched list is served without refetching
+const TTL_SECS: i64 = 24 * 60 * 60;
+
+/// Background refresh: first pass shortly after startup, then periodically
+const STARTUP_DELAY: Duration = Duration::from_secs(30);
+const REFRESH_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Boards added to or removed from a site since the previous list
+#[derive(Debug, Clone, Serialize)]
+pub struct BoardListDiff {
+    pub site: Site,
+    pub added: Vec<SiteBoard>,
+    pub removed: Vec<SiteBoard>,
+}
+
+fn load(site: Site) -> Result<Option<(Vec<SiteBoard>, i64)>> {
+    let row = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+        conn.query_row(
+            "SELECT boards, fetched_at FROM board_lists WHERE site = ?1",
+            params![site.id()],
+            |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)),
+        )
+        .optional()?
+    };
+
+    match row {
+        Some((json, fetched_at)) => Ok(Some((serde_json::from_str(&json)?, fetched_at))),
+        None => Ok(None),
+    }
+}
+
+fn save(site: Site, boards: &[SiteBoard]) -> Result<()> {
+    let json = serde_json::to_string(boards)?;
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute(
+        "INSERT OR REPLACE INTO board_lists (site, boards, fetched_at) VALUES (?1, ?2, ?3)",
+        params![site.id(), json, Utc::now().timestamp()],
+    )?;
+    Ok(())
+}
+
+/// Board list of `site`: cached while fresh, otherwise refetched, falling back
+/// to stale or static data when the site can't be reached
+pub async fn boards(app: &AppHandle, sources: &Sources, site: Site) -> Vec<SiteBoard> {
+    let cached = load(site).unwrap_or_else(|e| {
+        eprintln!("Failed to read {} board list: {}", site.id(), e);
+        None
+    });
+    if let Some((boards, fetched_at)) = &cached {
+        if Utc::now().timestamp() - fetched_at < TTL_SECS {
+            return boards.clone();
+        }
+    }
+
+    match refresh(app, sources, site).await {
+        Ok(boards) => boards,
+        Err(e) => {
+            eprintln!("Failed to refresh {} board list: {}", site.id(), e);
+            cached
+                .map(|(boards, _)| boards)
+                .unwrap_or_else(|| sources.get(site).static_boards())
+        }
+    }
+}
+
+/// Fetch the live list of `site`, store it and report changes since the
+/// previous list
+pub async fn refresh(app: &AppHandle, sources: &Sources, site: Site) -> Result<Vec<SiteBoard>> {
+    let boards = sources.get(site).boards().await?;
+    if boards.is_empty() {
+        return Err(anyhow!("{} returned an empty board list", site.id()));
+    }
+
+    let previous = load(site)?.map(|(boards, _)| boards);
+    save(site, &boards)?;
+
+    // The first fetch has nothing to compare against
+    if let Some(previous) = previous {
+        let diff = BoardListDiff {
+            site,
+            added: boards.iter().filter(|b| !previous.iter().any(|p| p.id == b.id)).cloned().collect(),
+            removed: previous.iter().filter(|p| !boards.iter().any(|b| b.id == p.id)).cloned().collect(),
+        };
+        if !diff.added.is_empty() || !diff.removed.is_empty() {
+            if let Err(e) = app.emit(BOARD_LIST_EVENT, diff) {
+                eprintln!("Failed to emit board list change: {}", e);
+            }
+        }
+    }
+
+    Ok(boards)
+}
+
+/// Refresh stale board lists in the background
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        loop {
+            let sources = app.state::<Sources>();
+            for site in Site::ALL {
+                let stale = match load(site) {
+                    Ok(Some((_, fetched_at))) => Utc::now().timestamp() - fetched_at >= TTL_SECS,
+                    _ => true,
+                };
+                if stale {
+                    if let Err(e) = refresh(&app, &sources, site).await {
+                        eprintln!("Failed to refresh {} board list: {}", site.id(), e);
+                    }
+                }
+            }
+            tokio::time::sleep(REFRESH_INTERVAL).await;
+        }
+    });
+}
@@ -8,15 +8,20 @@
  * - Automatic cleanup by age (default: 7 days)
  * - Size-based cleanup (default: 100MB limit)
  * - Cache statistics
+ * - Full-text search over cached posts (FTS5)
  */
 
-use rusqlite::{Connection, Result, params};
+use rusqlite::{Connection, OptionalExtension, Result, params};
+use rusqlite::functions::FunctionFlags;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Mutex;
 use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
 
+use crate::catalog_search::strip_html;
+use crate::sources::Site;
+
 // Cache configuration
 const DEFAULT_MAX_AGE_DAYS: i64 = 7;
 const DEFAULT_MAX_SIZE_MB: u64 = 100;
@@ -41,6 +46,14 @@ pub fn init_cache_db() -> Result<()> {
     let path = get_cache_db_path();
     let conn = Connection::open(&path)?;
     
+    // Used by the FTS triggers to index comments as plain text
+    conn.create_scalar_function(
+        "strip_html",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| Ok(ctx.get::<Option<String>>(0)?.map(|html| strip_html(&html))),
+    )?;
+    
     // Create tables
     conn.execute_batch(r#"
         -- Cached threads metadata
@@ -75,10 +88,174 @@ pub fn init_cache_db() -> Result<()> {
             fsize INTEGER,
             w INTEGER,
             h INTEGER,
+            md5 TEXT,
             cached_at INTEGER NOT NULL,
             UNIQUE(board, post_no)
         );
         
+        -- Threads polled by the background watcher
+        CREATE TABLE IF NOT EXISTS watched_threads (
+            id INTEGER PRIMARY KEY,
+            site TEXT NOT NULL,
+            board TEXT NOT NULL,
+            thread_id INTEGER NOT NULL,
+            subject TEXT,
+            last_post_no INTEGER DEFAULT 0,
+            reply_count INTEGER DEFAULT 0,
+            unread_count INTEGER DEFAULT 0,
+            you_count INTEGER DEFAULT 0,
+            status TEXT NOT NULL DEFAULT 'active',
+            last_modified TEXT,
+            interval_secs INTEGER NOT NULL,
+            next_check_at INTEGER NOT NULL,
+            last_checked_at INTEGER,
+            added_at INTEGER NOT NULL,
+            UNIQUE(site, board, thread_id)
+        );
+        
+        -- Posts we made ("(You)")
+        CREATE TABLE IF NOT EXISTS own_posts (
+            id INTEGER PRIMARY KEY,
+            site TEXT NOT NULL,
+            board TEXT NOT NULL,
+            thread_id INTEGER NOT NULL,
+            post_no INTEGER NOT NULL,
+            posted_at INTEGER NOT NULL,
+            UNIQUE(site, board, post_no)
+        );
+        
+        -- Source URL -> content-addressed media file
+        CREATE TABLE IF NOT EXISTS media_refs (
+            url TEXT PRIMARY KEY,
+            board TEXT,
+            thread_id INTEGER,
+            filename TEXT NOT NULL,
+            md5 TEXT NOT NULL,
+            ext TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        );
+        
+        -- Stored media files, for LRU eviction and storage reports
+        CREATE TABLE IF NOT EXISTS media_files (
+            md5 TEXT NOT NULL,
+            ext TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            last_access INTEGER NOT NULL,
+            PRIMARY KEY (md5, ext)
+        );
+        
+        -- Thumbnails generated locally, one per source file and size
+        CREATE TABLE IF NOT EXISTS media_thumbnails (
+            source_md5 TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            thumb_md5 TEXT NOT NULL,
+            width INTEGER NOT NULL,
+            height INTEGER NOT NULL,
+            source_width INTEGER NOT NULL,
+            source_height INTEGER NOT NULL,
+            PRIMARY KEY (source_md5, size)
+        );
+        
+        -- Image dimensions probed for sites whose API doesn't report them
+        CREATE TABLE IF NOT EXISTS media_dimensions (
+            url TEXT PRIMARY KEY,
+            width INTEGER NOT NULL,
+            height INTEGER NOT NULL
+        );
+        
+        -- Threads whose media is never evicted (watched threads are pinned implicitly)
+        CREATE TABLE IF NOT EXISTS pinned_threads (
+            board TEXT NOT NULL,
+            thread_id INTEGER NOT NULL,
+            reason TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (board, thread_id)
+        );
+        
+        -- 4chan X style filter rules
+        CREATE TABLE IF NOT EXISTS filter_rules (
+            id INTEGER PRIMARY KEY,
+            field TEXT NOT NULL,
+            pattern TEXT NOT NULL,
+            boards TEXT NOT NULL DEFAULT '',
+            exclude_boards TEXT NOT NULL DEFAULT '',
+            posts TEXT NOT NULL DEFAULT 'all',
+            action TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL
+        );
+        
+        -- Threads added to the watcher by an auto-watch rule, so a thread
+        -- the user unwatches is not watched again on the next refresh
+        CREATE TABLE IF NOT EXISTS filter_auto_watched (
+            site TEXT NOT NULL,
+            board TEXT NOT NULL,
+            thread_id INTEGER NOT NULL,
+            watched_at INTEGER NOT NULL,
+            PRIMARY KEY (site, board, thread_id)
+        );
+        
+        -- End of each post cooldown (unix seconds) per board and post type
+        CREATE TABLE IF NOT EXISTS post_cooldowns (
+            board TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            until INTEGER NOT NULL,
+            PRIMARY KEY (board, kind)
+        );
+        
+        -- Live board lists per site, refreshed when older than their TTL
+        CREATE TABLE IF NOT EXISTS board_lists (
+            site TEXT PRIMARY KEY,
+            boards TEXT NOT NULL,
+            fetched_at INTEGER NOT NULL
+        );
+        
+        -- Drafts waiting to be posted by the post queue
+        CREATE TABLE IF NOT EXISTS post_queue (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            request TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'queued',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at INTEGER NOT NULL,
+            last_error TEXT,
+            thread_id INTEGER,
+            post_id INTEGER,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        
+        -- Catalog snapshots taken by the board activity sampler
+        CREATE TABLE IF NOT EXISTS board_snapshots (
+            id INTEGER PRIMARY KEY,
+            site TEXT NOT NULL,
+            board TEXT NOT NULL,
+            taken_at INTEGER NOT NULL,
+            threads INTEGER NOT NULL,
+            replies INTEGER NOT NULL,
+            images INTEGER NOT NULL,
+            max_post_no INTEGER NOT NULL,
+            interval_secs INTEGER,
+            posts_per_minute REAL,
+            new_threads INTEGER,
+            top_threads TEXT NOT NULL
+        );
+        
+        -- Latest reply count and velocity of each sampled thread
+        CREATE TABLE IF NOT EXISTS thread_activity (
+            site TEXT NOT NULL,
+            board TEXT NOT NULL,
+            thread_id INTEGER NOT NULL,
+            subject TEXT,
+            replies INTEGER NOT NULL,
+            images INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            last_seen INTEGER NOT NULL,
+            velocity REAL NOT NULL,
+            PRIMARY KEY (site, board, thread_id)
+        );
+        
         -- Cache metadata for tracking size
         CREATE TABLE IF NOT EXISTS cache_meta (
             key TEXT PRIMARY KEY,
@@ -91,16 +268,81 @@ pub fn init_cache_db() -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_threads_accessed ON cached_threads(accessed_at);
         CREATE INDEX IF NOT EXISTS idx_posts_thread ON cached_posts(board, thread_id);
         CREATE INDEX IF NOT EXISTS idx_posts_cached ON cached_posts(cached_at);
+        CREATE INDEX IF NOT EXISTS idx_watched_next ON watched_threads(status, next_check_at);
+        CREATE INDEX IF NOT EXISTS idx_post_queue_next ON post_queue(status, next_attempt_at);
+        CREATE INDEX IF NOT EXISTS idx_board_snapshots ON board_snapshots(site, board, taken_at);
+        CREATE INDEX IF NOT EXISTS idx_thread_activity_velocity ON thread_activity(last_seen, velocity);
+        CREATE INDEX IF NOT EXISTS idx_media_refs_md5 ON media_refs(md5);
+        CREATE INDEX IF NOT EXISTS idx_media_refs_thread ON media_refs(board, thread_id);
+        CREATE INDEX IF NOT EXISTS idx_media_files_access ON media_files(last_access);
+        CREATE INDEX IF NOT EXISTS idx_own_posts_thread ON own_posts(site, board, thread_id);
     "#)?;
     
+    // Databases created before posts carried an MD5
+    let has_md5: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('cached_posts') WHERE name = 'md5'",
+        [],
+        |r| r.get(0),
+    )?;
+    if !has_md5 {
+        conn.execute("ALTER TABLE cached_posts ADD COLUMN md5 TEXT", [])?;
+    }
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_posts_md5 ON cached_posts(md5)", [])?;
+    
+    init_post_search(&conn)?;
+    
     let mut guard = DB_CONNECTION.lock().unwrap();
     *guard = Some(conn);
     
     Ok(())
 }
 
+/// Create the FTS5 index over `cached_posts` and the triggers keeping it in sync.
+/// Posts cached before the index existed are indexed once on creation.
+fn init_post_search(conn: &Connection) -> Result<()> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'cached_posts_fts'",
+        [],
+        |r| r.get(0),
+    )?;
+    
+    // INSERT OR REPLACE only fires the delete trigger with recursive triggers on
+    conn.execute_batch(r#"
+        PRAGMA recursive_triggers = ON;
+        
+        CREATE VIRTUAL TABLE IF NOT EXISTS cached_posts_fts USING fts5(
+            subject,
+            comment,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+        
+        CREATE TRIGGER IF NOT EXISTS cached_posts_fts_insert AFTER INSERT ON cached_posts BEGIN
+            INSERT INTO cached_posts_fts(rowid, subject, comment)
+            VALUES (new.id, new.subject, strip_html(new.comment));
+        END;
+        
+        CREATE TRIGGER IF NOT EXISTS cached_posts_fts_delete AFTER DELETE ON cached_posts BEGIN
+            DELETE FROM cached_posts_fts WHERE rowid = old.id;
+        END;
+        
+        CREATE TRIGGER IF NOT EXISTS cached_posts_fts_update AFTER UPDATE OF subject, comment ON cached_posts BEGIN
+            UPDATE cached_posts_fts SET subject = new.subject, comment = strip_html(new.comment)
+            WHERE rowid = old.id;
+        END;
+    "#)?;
+    
+    if !exists {
+        conn.execute(
+            "INSERT INTO cached_posts_fts(rowid, subject, comment) SELECT id, subject, strip_html(comment) FROM cached_posts",
+            [],
+        )?;
+    }
+    
+    Ok(())
+}
+
 /// Get a database connection
-fn get_connection() -> Result<std::sync::MutexGuard<'static, Option<Connection>>> {
+pub(crate) fn get_connection() -> Result<std::sync::MutexGuard<'static, Option<Connection>>> {
     let guard = DB_CONNECTION.lock().unwrap();
     if guard.is_none() {
         drop(guard);
@@ -110,6 +352,27 @@ fn get_connection() -> Result<std::sync::MutexGuard<'static, Option<Connection>>
     Ok(guard)
 }
 
+/// Read a value from `cache_meta`
+pub fn get_meta(key: &str) -> Result<Option<String>> {
+    let guard = get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    
+    conn.query_row("SELECT value FROM cache_meta WHERE key = ?1", params![key], |r| r.get(0))
+        .optional()
+}
+
+/// Write a value to `cache_meta`
+pub fn set_meta(key: &str, value: &str) -> Result<()> {
+    let guard = get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    
+    conn.execute(
+        "INSERT OR REPLACE INTO cache_meta (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
 /// Cached thread data structure
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CachedThread {
@@ -141,6 +404,8 @@ pub struct CachedPost {
     pub fsize: Option<i64>,
     pub w: Option<i32>,
     pub h: Option<i32>,
+    #[serde(default)]
+    pub md5: Option<String>,
 }
 
 /// Cache a thread with its posts
@@ -169,8 +434,8 @@ pub fn cache_thread(board: &str, thread_id: i64, subject: Option<&str>, posts: &
     for post in posts {
         conn.execute(
             r#"INSERT OR REPLACE INTO cached_posts 
-               (board, thread_id, post_no, resto, time, name, trip, subject, comment, tim, ext, filename, fsize, w, h, cached_at)
-               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)"#,
+               (board, thread_id, post_no, resto, time, name, trip, subject, comment, tim, ext, filename, fsize, w, h, md5, cached_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)"#,
             params![
                 post.board,
                 post.thread_id,
@@ -187,6 +452,7 @@ pub fn cache_thread(board: &str, thread_id: i64, subject: Option<&str>, posts: &
                 post.fsize,
                 post.w,
                 post.h,
+                post.md5,
                 now
             ],
         )?;
@@ -229,34 +495,57 @@ pub fn get_cached_thread(board: &str, thread_id: i64) -> Result<Option<(CachedTh
     }
     
     // Get posts
-    let mut stmt = conn.prepare(
-        "SELECT board, thread_id, post_no, resto, time, name, trip, subject, comment, tim, ext, filename, fsize, w, h
-         FROM cached_posts WHERE board = ?1 AND thread_id = ?2 ORDER BY post_no ASC"
-    )?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM cached_posts WHERE board = ?1 AND thread_id = ?2 ORDER BY post_no ASC",
+        POST_COLUMNS
+    ))?;
     
-    let posts: Vec<CachedPost> = stmt.query_map(params![board, thread_id], |row| {
-        Ok(CachedPost {
-            board: row.get(0)?,
-            thread_id: row.get(1)?,
-            post_no: row.get(2)?,
-            resto: row.get(3)?,
-            time: row.get(4)?,
-            name: row.get(5)?,
-            trip: row.get(6)?,
-            subject: row.get(7)?,
-            comment: row.get(8)?,
-            tim: row.get(9)?,
-            ext: row.get(10)?,
-            filename: row.get(11)?,
-            fsize: row.get(12)?,
-            w: row.get(13)?,
-            h: row.get(14)?,
-        })
-    })?.filter_map(|r| r.ok()).collect();
+    let posts: Vec<CachedPost> = stmt.query_map(params![board, thread_id], row_to_post)?
+        .filter_map(|r| r.ok())
+        .collect();
     
     Ok(Some((thread.unwrap(), posts)))
 }
 
+const POST_COLUMNS: &str = "board, thread_id, post_no, resto, time, name, trip, subject, comment, tim, ext, filename, fsize, w, h, md5";
+
+fn row_to_post(row: &rusqlite::Row) -> Result<CachedPost> {
+    Ok(CachedPost {
+        board: row.get(0)?,
+        thread_id: row.get(1)?,
+        post_no: row.get(2)?,
+        resto: row.get(3)?,
+        time: row.get(4)?,
+        name: row.get(5)?,
+        trip: row.get(6)?,
+        subject: row.get(7)?,
+        comment: row.get(8)?,
+        tim: row.get(9)?,
+        ext: row.get(10)?,
+        filename: row.get(11)?,
+        fsize: row.get(12)?,
+        w: row.get(13)?,
+        h: row.get(14)?,
+        md5: row.get(15)?,
+    })
+}
+
+/// Cached posts on any board carrying the file with this MD5, oldest first
+pub fn find_posts_by_md5(md5: &str) -> Result<Vec<CachedPost>> {
+    let guard = get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM cached_posts WHERE md5 = ?1 ORDER BY time ASC",
+        POST_COLUMNS
+    ))?;
+    let posts = stmt.query_map(params![md5], row_to_post)?
+        .filter_map(|r| r.ok())
+        .collect();
+    
+    Ok(posts)
+}
+
 /// Check if a thread is cached and fresh (within max_age_days)
 pub fn is_thread_cached(board: &str, thread_id: i64, max_age_secs: i64) -> Result<bool> {
     let guard = get_connection()?;
@@ -304,6 +593,161 @@ pub fn get_cache_stats() -> Result<CacheStats> {
     })
 }
 
+/// Remember a post we made
+pub fn add_own_post(site: Site, board: &str, thread_id: i64, post_no: i64) -> Result<()> {
+    let guard = get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    
+    conn.execute(
+        "INSERT OR IGNORE INTO own_posts (site, board, thread_id, post_no, posted_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![site.id(), board, thread_id, post_no, Utc::now().timestamp()],
+    )?;
+    
+    Ok(())
+}
+
+/// Post numbers we made in a thread
+pub fn get_own_post_numbers(site: Site, board: &str, thread_id: i64) -> Result<Vec<i64>> {
+    let guard = get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    
+    let mut stmt = conn.prepare(
+        "SELECT post_no FROM own_posts WHERE site = ?1 AND board = ?2 AND thread_id = ?3 ORDER BY post_no ASC"
+    )?;
+    let posts = stmt.query_map(params![site.id(), board, thread_id], |row| row.get(0))?
+        .filter_map(|r| r.ok())
+        .collect();
+    
+    Ok(posts)
+}
+
+/// A post we made
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OwnPost {
+    pub site: Site,
+    pub board: String,
+    pub thread_id: i64,
+    pub post_no: i64,
+    pub posted_at: i64,
+}
+
+/// Posts we made in a thread, oldest first
+pub fn get_own_posts(site: Site, board: &str, thread_id: i64) -> Result<Vec<OwnPost>> {
+    let guard = get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    
+    let mut stmt = conn.prepare(
+        "SELECT board, thread_id, post_no, posted_at FROM own_posts WHERE site = ?1 AND board = ?2 AND thread_id = ?3 ORDER BY post_no ASC"
+    )?;
+    let posts = stmt.query_map(params![site.id(), board, thread_id], |row| {
+        Ok(OwnPost {
+            site,
+            board: row.get(0)?,
+            thread_id: row.get(1)?,
+            post_no: row.get(2)?,
+            posted_at: row.get(3)?,
+        })
+    })?
+    .filter_map(|r| r.ok())
+    .collect();
+    
+    Ok(posts)
+}
+
+// Snippet match markers, replaced by <mark> tags after HTML-escaping the snippet
+const MATCH_START: &str = "\u{2}";
+const MATCH_END: &str = "\u{3}";
+
+/// Full-text search hit
+#[derive(Debug, Serialize, Clone)]
+pub struct PostSearchHit {
+    pub board: String,
+    pub thread_id: i64,
+    pub post_no: i64,
+    pub time: i64,
+    pub subject: Option<String>,
+    /// HTML-escaped excerpt with matches wrapped in `<mark>`
+    pub snippet: String,
+    /// BM25 score, lower is better
+    pub rank: f64,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Search cached post subjects and comments using FTS5 query syntax
+/// (`word`, `"exact phrase"`, `pre*`, `AND`/`OR`/`NOT`).
+/// Queries that aren't valid FTS5 syntax (e.g. `don't`, `c++`, `what?`) are
+/// searched again as plain words.
+/// `since` limits results to posts made at or after that Unix time.
+pub fn search_cached_posts(
+    query: &str,
+    board: Option<&str>,
+    since: Option<i64>,
+    limit: i64,
+) -> Result<Vec<PostSearchHit>> {
+    let guard = get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    
+    match run_post_search(conn, query, board, since, limit) {
+        Err(rusqlite::Error::SqliteFailure(_, _)) => {
+            run_post_search(conn, &quote_search_terms(query), board, since, limit)
+        }
+        result => result,
+    }
+}
+
+/// Quote every whitespace-separated term so FTS5 reads it literally
+fn quote_search_terms(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn run_post_search(
+    conn: &Connection,
+    query: &str,
+    board: Option<&str>,
+    since: Option<i64>,
+    limit: i64,
+) -> Result<Vec<PostSearchHit>> {
+    let mut stmt = conn.prepare(
+        r#"SELECT p.board, p.thread_id, p.post_no, p.time, p.subject,
+                  snippet(cached_posts_fts, -1, ?4, ?5, '…', 24), bm25(cached_posts_fts)
+           FROM cached_posts_fts
+           JOIN cached_posts p ON p.id = cached_posts_fts.rowid
+           WHERE cached_posts_fts MATCH ?1
+             AND (?2 IS NULL OR p.board = ?2)
+             AND (?3 IS NULL OR p.time >= ?3)
+           ORDER BY bm25(cached_posts_fts)
+           LIMIT ?6"#
+    )?;
+    
+    let hits = stmt.query_map(params![query, board, since, MATCH_START, MATCH_END, limit], |row| {
+        let snippet: String = row.get(5)?;
+        Ok(PostSearchHit {
+            board: row.get(0)?,
+            thread_id: row.get(1)?,
+            post_no: row.get(2)?,
+            time: row.get(3)?,
+            subject: row.get(4)?,
+            snippet: escape_html(&snippet)
+                .replace(MATCH_START, "<mark>")
+                .replace(MATCH_END, "</mark>"),
+            rank: row.get(6)?,
+        })
+    })?
+    .collect::<Result<Vec<_>>>()?;
+    
+    Ok(hits)
+}
+
 /// Clean up old cache entries by age
 pub fn cleanup_old_cache(max_age_days: i64) -> Result<i64> {
     let guard = get_connection()?;
@@ -333,22 +777,22 @@ pub fn cleanup_old_cache(max_age_days: i64) -> Result<i64> {
 
 /// Clean up cache to stay under size limit
 pub fn cleanup_by_size(max_size_mb: u64) -> Result<i64> {
-    let stats = get_cache_stats()?;
     let max_size_bytes = max_size_mb * 1024 * 1024;
     
-    if stats.db_size_bytes <= max_size_bytes {
-        return Ok(0);
-    }
-    
     let guard = get_connection()?;
     let conn = guard.as_ref().unwrap();
     
-    // Delete least recently accessed threads until under limit
+    if db_used_bytes(conn)? <= max_size_bytes {
+        return Ok(0);
+    }
+    
+    // Delete least recently accessed threads until under limit. Pages freed
+    // by a delete only leave the file on VACUUM, so measure used pages.
     let mut deleted = 0i64;
-    while get_cache_stats()?.db_size_bytes > max_size_bytes {
+    while db_used_bytes(conn)? > max_size_bytes {
         let result = conn.execute(
-            "DELETE FROM cached_threads WHERE thread_id = (
-                SELECT thread_id FROM cached_threads ORDER BY accessed_at ASC LIMIT 1
+            "DELETE FROM cached_threads WHERE id = (
+                SELECT id FROM cached_threads ORDER BY accessed_at ASC LIMIT 1
             )",
             [],
         )?;
@@ -357,23 +801,31 @@ pub fn cleanup_by_size(max_size_mb: u64) -> Result<i64> {
             break; // No more threads to delete
         }
         deleted += result as i64;
+        
+        // Delete orphaned posts
+        conn.execute(
+            "DELETE FROM cached_posts WHERE NOT EXISTS (
+                SELECT 1 FROM cached_threads WHERE cached_threads.board = cached_posts.board 
+                AND cached_threads.thread_id = cached_posts.thread_id
+            )",
+            [],
+        )?;
     }
     
-    // Delete orphaned posts
-    conn.execute(
-        "DELETE FROM cached_posts WHERE NOT EXISTS (
-            SELECT 1 FROM cached_threads WHERE cached_threads.board = cached_posts.board 
-            AND cached_threads.thread_id = cached_posts.thread_id
-        )",
-        [],
-    )?;
-    
     // Vacuum to reclaim space
     conn.execute("VACUUM", [])?;
     
     Ok(deleted)
 }
 
+/// Bytes of the database file in use (excluding free pages)
+fn db_used_bytes(conn: &Connection) -> Result<u64> {
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |r| r.get(0))?;
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |r| r.get(0))?;
+    let free_pages: i64 = conn.query_row("PRAGMA freelist_count", [], |r| r.get(0))?;
+    Ok(((page_count - free_pages) * page_size) as u64)
+}
+
 /// Clear all cache
 pub fn clear_all_cache() -> Result<()> {
     let guard = get_connection()?;
@@ -0,0 +1,513 @@
+/**
+ * Catalog Search
+ *
+ * Query language for filtering a board catalog on the Rust side.
+ *
+ * Syntax:
+ * - `word` / `"a phrase"`     subject, comment, filename or name contains (case-insensitive)
+ * - `/regex/i`                regular expression (`i` flag = case-insensitive)
+ * - `sub:` `com:` `file:` `name:` `ext:` restrict a text match to one field
+ * - `replies:>100` `images:<=5` `replies:10..50`   numeric comparisons
+ * - `age:<2h` (s, m, h, d, w)  thread age
+ * - `is:sticky` `is:closed` `is:bumplimit` `is:imagelimit` `has:file` `has:subject`
+ * - `AND` (implicit), `OR` / `|`, `NOT` / `-`, and parentheses
+ */
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::api::CatalogThread;
+
+/// Catalog sort orders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatalogSort {
+    /// Order as served by the site
+    #[default]
+    Bump,
+    Replies,
+    Images,
+    Created,
+    LastReply,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Any,
+    Subject,
+    Comment,
+    Filename,
+    Name,
+    Ext,
+}
+
+#[derive(Debug)]
+enum TextMatcher {
+    Contains(String),
+    Regex(Regex),
+}
+
+impl TextMatcher {
+    fn is_match(&self, haystack: &str) -> bool {
+        match self {
+            Self::Contains(needle) => haystack.to_lowercase().contains(needle),
+            Self::Regex(re) => re.is_match(haystack),
+        }
+    }
+}
+
+/// Inclusive numeric range
+#[derive(Debug, Clone, Copy)]
+struct Range {
+    min: Option<i64>,
+    max: Option<i64>,
+}
+
+impl Range {
+    fn contains(&self, value: i64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Flag {
+    Sticky,
+    Closed,
+    BumpLimit,
+    ImageLimit,
+    File,
+    Subject,
+}
+
+#[derive(Debug)]
+enum Condition {
+    Text(Field, TextMatcher),
+    Replies(Range),
+    Images(Range),
+    Age(Range),
+    Flag(Flag),
+}
+
+#[derive(Debug)]
+enum Expr {
+    All,
+    Cond(Condition),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+/// A parsed catalog query
+#[derive(Debug)]
+pub struct CatalogQuery {
+    expr: Expr,
+}
+
+impl CatalogQuery {
+    /// Parse a query string. An empty query matches every thread.
+    pub fn parse(query: &str) -> Result<Self> {
+        let tokens = tokenize(query)?;
+        if tokens.is_empty() {
+            return Ok(Self { expr: Expr::All });
+        }
+
+        let mut parser = Parser { tokens, pos: 0, depth: 0 };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            bail!("Unexpected {:?} in query", token);
+        }
+
+        Ok(Self { expr })
+    }
+
+    /// Filter and sort a catalog
+    pub fn apply(&self, threads: Vec<CatalogThread>, sort: CatalogSort) -> Vec<CatalogThread> {
+        let now = Utc::now().timestamp();
+        let mut results: Vec<CatalogThread> = threads
+            .into_iter()
+            .filter(|t| eval(&self.expr, &Haystack::new(t), t, now))
+            .collect();
+
+        sort_catalog(&mut results, sort);
+        results
+    }
+}
+
+/// Sort threads in place, highest first. `Bump` keeps the site's order.
+pub fn sort_catalog(threads: &mut [CatalogThread], sort: CatalogSort) {
+    match sort {
+        CatalogSort::Bump => {}
+        CatalogSort::Replies => threads.sort_by_key(|t| std::cmp::Reverse(t.replies)),
+        CatalogSort::Images => threads.sort_by_key(|t| std::cmp::Reverse(t.images)),
+        CatalogSort::Created => threads.sort_by_key(|t| std::cmp::Reverse(t.time)),
+        CatalogSort::LastReply => threads.sort_by_key(|t| std::cmp::Reverse(t.last_modified)),
+    }
+}
+
+/// Plain text of a comment: tags removed, `<br>` as newlines, entities decoded
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        if tag.eq_ignore_ascii_case("br") || tag.eq_ignore_ascii_case("br/") {
+            text.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+
+    decode_entities(&text)
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&gt;", ">")
+        .replace("&lt;", "<")
+        .replace("&quot;", "\"")
+        .replace("&#039;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Searchable text of one thread
+struct Haystack {
+    subject: String,
+    comment: String,
+    filename: String,
+    name: String,
+    ext: String,
+}
+
+impl Haystack {
+    fn new(thread: &CatalogThread) -> Self {
+        Self {
+            subject: thread.sub.as_deref().map(decode_entities).unwrap_or_default(),
+            comment: thread.com.as_deref().map(strip_html).unwrap_or_default(),
+            filename: format!(
+                "{}{}",
+                thread.filename.as_deref().unwrap_or(""),
+                thread.ext.as_deref().unwrap_or("")
+            ),
+            name: format!(
+                "{}{}",
+                thread.name.as_deref().unwrap_or(""),
+                thread.trip.as_deref().unwrap_or("")
+            ),
+            ext: thread.ext.as_deref().unwrap_or("").trim_start_matches('.').to_string(),
+        }
+    }
+
+    fn field(&self, field: Field) -> &str {
+        match field {
+            Field::Subject => &self.subject,
+            Field::Comment => &self.comment,
+            Field::Filename => &self.filename,
+            Field::Name => &self.name,
+            Field::Ext => &self.ext,
+            Field::Any => unreachable!(),
+        }
+    }
+}
+
+fn eval(expr: &Expr, hay: &Haystack, thread: &CatalogThread, now: i64) -> bool {
+    match expr {
+        Expr::All => true,
+        Expr::Not(inner) => !eval(inner, hay, thread, now),
+        Expr::And(exprs) => exprs.iter().all(|e| eval(e, hay, thread, now)),
+        Expr::Or(exprs) => exprs.iter().any(|e| eval(e, hay, thread, now)),
+        Expr::Cond(cond) => match cond {
+            Condition::Text(Field::Any, m) => {
+                [Field::Subject, Field::Comment, Field::Filename, Field::Name]
+                    .iter()
+                    .any(|f| m.is_match(hay.field(*f)))
+            }
+            Condition::Text(field, m) => m.is_match(hay.field(*field)),
+            Condition::Replies(range) => range.contains(thread.replies as i64),
+            Condition::Images(range) => range.contains(thread.images as i64),
+            Condition::Age(range) => range.contains(now - thread.time as i64),
+            Condition::Flag(flag) => match flag {
+                Flag::Sticky => thread.sticky == Some(1),
+                Flag::Closed => thread.closed == Some(1),
+                Flag::BumpLimit => thread.bumplimit == Some(1),
+                Flag::ImageLimit => thread.imagelimit == Some(1),
+                Flag::File => thread.tim.is_some(),
+                Flag::Subject => thread.sub.as_deref().is_some_and(|s| !s.is_empty()),
+            },
+        },
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Term(String),
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        match c {
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+                continue;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+                continue;
+            }
+            '-' | '!' if chars.get(i + 1).is_some_and(|n| !n.is_whitespace()) => {
+                tokens.push(Token::Not);
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        // A term runs until whitespace or ')', except inside quotes or /regex/
+        let start = i;
+        let mut value_start = true;
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_whitespace() || c == ')' || c == '(' {
+                break;
+            }
+            if c == '"' {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += 1;
+                }
+                if i == chars.len() {
+                    bail!("Unterminated quote in query");
+                }
+            } else if c == '/' && value_start {
+                i += 1;
+                while i < chars.len() && chars[i] != '/' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    bail!("Unterminated regular expression in query");
+                }
+            }
+            value_start = c == ':';
+            i += 1;
+        }
+
+        let word: String = chars[start..i].iter().collect();
+        tokens.push(match word.as_str() {
+            "AND" | "&&" | "&" => Token::And,
+            "OR" | "||" | "|" => Token::Or,
+            "NOT" => Token::Not,
+            _ => Token::Term(word),
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Deepest nesting of parentheses and negations a query may use; the parser
+/// and evaluator recurse once per level
+const MAX_NESTING: usize = 64;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// Parse one nested level with `parse`
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> Result<Expr>) -> Result<Expr> {
+        if self.depth >= MAX_NESTING {
+            bail!("Query is nested too deeply");
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut exprs = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            exprs.push(self.parse_and()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.pop().unwrap() } else { Expr::Or(exprs) })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut exprs = vec![self.parse_not()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.pos += 1,
+                Some(Token::Not | Token::LParen | Token::Term(_)) => {}
+                _ => break,
+            }
+            exprs.push(self.parse_not()?);
+        }
+        Ok(if exprs.len() == 1 { exprs.pop().unwrap() } else { Expr::And(exprs) })
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.nested(Self::parse_not)?)));
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr> {
+        match self.tokens.get(self.pos) {
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let expr = self.nested(Self::parse_or)?;
+                if self.peek() != Some(&Token::RParen) {
+                    bail!("Missing closing parenthesis in query");
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Some(Token::Term(term)) => {
+                let cond = parse_term(term)?;
+                self.pos += 1;
+                Ok(Expr::Cond(cond))
+            }
+            Some(token) => bail!("Unexpected {:?} in query", token),
+            None => bail!("Query ends unexpectedly"),
+        }
+    }
+}
+
+fn parse_term(term: &str) -> Result<Condition> {
+    if let Some((key, value)) = term.split_once(':') {
+        let field = match key.to_lowercase().as_str() {
+            "sub" | "subject" => Some(Field::Subject),
+            "com" | "comment" => Some(Field::Comment),
+            "file" | "filename" => Some(Field::Filename),
+            "name" => Some(Field::Name),
+            "ext" => Some(Field::Ext),
+            "text" => Some(Field::Any),
+            _ => None,
+        };
+        if let Some(field) = field {
+            return Ok(Condition::Text(field, parse_text(value)?));
+        }
+
+        match key.to_lowercase().as_str() {
+            "replies" => return Ok(Condition::Replies(parse_range(value, 1)?)),
+            "images" => return Ok(Condition::Images(parse_range(value, 1)?)),
+            "age" => return Ok(Condition::Age(parse_age(value)?)),
+            "is" | "has" => {
+                let flag = match value.to_lowercase().as_str() {
+                    "sticky" => Flag::Sticky,
+                    "closed" | "locked" => Flag::Closed,
+                    "bumplimit" => Flag::BumpLimit,
+                    "imagelimit" => Flag::ImageLimit,
+                    "file" | "image" | "media" => Flag::File,
+                    "sub" | "subject" => Flag::Subject,
+                    _ => bail!("Unknown flag '{}'", value),
+                };
+                return Ok(Condition::Flag(flag));
+            }
+            // Not a known field (e.g. "http://"), treat the whole term as text
+            _ => {}
+        }
+    }
+
+    Ok(Condition::Text(Field::Any, parse_text(term)?))
+}
+
+fn parse_text(value: &str) -> Result<TextMatcher> {
+    if let Some(body) = value.strip_prefix('/') {
+        let end = body.rfind('/').ok_or_else(|| anyhow!("Unterminated regular expression"))?;
+        let flags = &body[end + 1..];
+        let re = RegexBuilder::new(&body[..end])
+            .case_insensitive(flags.contains('i'))
+            .multi_line(flags.contains('m'))
+            .build()
+            .map_err(|e| anyhow!("Invalid regular expression: {}", e))?;
+        return Ok(TextMatcher::Regex(re));
+    }
+
+    let value = value.trim_matches('"');
+    if value.is_empty() {
+        bail!("Empty search term");
+    }
+    Ok(TextMatcher::Contains(value.to_lowercase()))
+}
+
+/// `>N`, `>=N`, `<N`, `<=N`, `N`, `A..B`; values are multiplied by `unit`
+fn parse_range(value: &str, unit: i64) -> Result<Range> {
+    let invalid = || anyhow!("Invalid number '{}'", value);
+    let num = |s: &str| -> Result<i64> {
+        s.trim()
+            .parse::<i64>()
+            .ok()
+            .and_then(|n| n.checked_mul(unit))
+            .ok_or_else(|| anyhow!("Invalid number '{}'", s))
+    };
+
+    let range = if let Some((lo, hi)) = value.split_once("..") {
+        Range {
+            min: if lo.is_empty() { None } else { Some(num(lo)?) },
+            max: if hi.is_empty() { None } else { Some(num(hi)?) },
+        }
+    } else if let Some(v) = value.strip_prefix(">=") {
+        Range { min: Some(num(v)?), max: None }
+    } else if let Some(v) = value.strip_prefix("<=") {
+        Range { min: None, max: Some(num(v)?) }
+    } else if let Some(v) = value.strip_prefix('>') {
+        Range { min: Some(num(v)?.checked_add(1).ok_or_else(invalid)?), max: None }
+    } else if let Some(v) = value.strip_prefix('<') {
+        Range { min: None, max: Some(num(v)?.checked_sub(1).ok_or_else(invalid)?) }
+    } else {
+        let n = num(value)?;
+        let max = n.checked_add(unit - 1).ok_or_else(invalid)?;
+        Range { min: Some(n), max: Some(max) }
+    };
+
+    Ok(range)
+}
+
+/// Age comparisons with a unit suffix, e.g. `<2h`
+fn parse_age(value: &str) -> Result<Range> {
+    let unit = match value.chars().last() {
+        Some('s') => 1,
+        Some('m') => 60,
+        Some('h') => 3600,
+        Some('d') => 86400,
+        Some('w') => 604800,
+        _ => bail!("Age needs a unit (s, m, h, d, w): '{}'", value),
+    };
+    parse_range(&value[..value.len() - 1], unit)
+}
+
@@ -0,0 +1,57 @@
+/**
+ * Board Activity Commands
+ *
+ * Tauri commands for the board activity sampler: which boards it follows,
+ * their history, the fastest threads and new-thread rates.
+ */
+
+use chrono::Utc;
+use tauri::State;
+
+use crate::board_activity::{self, BoardSnapshot, NewThreadRate, ThreadVelocity, TrackedBoard};
+use crate::sources::{Site, Sources};
+
+/// Boards the sampler follows
+#[tauri::command]
+pub async fn get_activity_boards() -> Result<Vec<TrackedBoard>, String> {
+    board_activity::tracked_boards().map_err(|e| e.to_string())
+}
+
+/// Replace the boards the sampler follows
+#[tauri::command]
+pub async fn set_activity_boards(boards: Vec<TrackedBoard>) -> Result<(), String> {
+    board_activity::set_tracked_boards(&boards).map_err(|e| e.to_string())
+}
+
+/// Take a snapshot of a board now instead of waiting for the sampler
+#[tauri::command]
+pub async fn sample_board_activity(
+    site: Site,
+    board: String,
+    sources: State<'_, Sources>,
+) -> Result<BoardSnapshot, String> {
+    board_activity::sample(&sources, site, &board).await.map_err(|e| e.to_string())
+}
+
+/// Snapshots of a board over the last `hours` (a day by default), oldest first
+#[tauri::command]
+pub async fn get_board_activity(site: Site, board: String, hours: Option<u32>) -> Result<Vec<BoardSnapshot>, String> {
+    let since = Utc::now().timestamp() - hours.unwrap_or(24) as i64 * 3600;
+    board_activity::history(site, &board, since).map_err(|e| e.to_string())
+}
+
+/// Fastest-moving threads across the sampled boards, or one site or board
+#[tauri::command]
+pub async fn get_fastest_threads(
+    site: Option<Site>,
+    board: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<ThreadVelocity>, String> {
+    board_activity::fastest_threads(site, board.as_deref(), limit.unwrap_or(20)).map_err(|e| e.to_string())
+}
+
+/// New threads per hour on a board over the last `hours` (a day by default)
+#[tauri::command]
+pub async fn get_new_thread_rate(site: Site, board: String, hours: Option<u32>) -> Result<NewThreadRate, String> {
+    board_activity::new_thread_rate(site, &board, hours.unwrap_or(24)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+use crate::archive::{self, ArchiveSummary};
+use crate::sources::{self, Site, Sources};
+
+/// Save a thread (JSON, static HTML, thumbnails and media) under `dest_dir`.
+/// Running it again on the same directory appends new posts and missing files.
+/// Progress is reported through `archive-progress` events.
+#[tauri::command]
+pub async fn archive_thread(
+    site: Site,
+    board: String,
+    thread_id: u64,
+    dest_dir: String,
+    app: AppHandle,
+    sources: State<'_, Sources>,
+) -> Result<ArchiveSummary, String> {
+    let client = sources::browser_client().map_err(|e| e.to_string())?;
+
+    archive::archive_thread(
+        &app,
+        &client,
+        sources.get(site),
+        site,
+        &board,
+        thread_id,
+        &PathBuf::from(dest_dir),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
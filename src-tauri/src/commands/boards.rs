@@ -1,49 +1,66 @@
-use crate::boards_metadata::{get_all_boards_info, BoardInfo};
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use crate::api::ChanClient;
+use crate::board_capabilities::{self, BoardCapabilities};
+use crate::board_lists;
+use crate::boards_metadata::{categorize_board, BoardInfo};
+use crate::home_feed::{self, FeedConfig, FeedPage, FeedRanking};
+use crate::sources::{Site, SiteBoard, Sources};
+use tauri::{AppHandle, State};
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PopularThread {
-    pub no: u32,
-    pub board: String,
-    pub title: Option<String>,
-    pub com: Option<String>,
-    pub tim: u64,
-    pub ext: String,
-    pub replies: u32,
-    pub images: u32,
+/// 4chan boards with their categories, from the live board list
+async fn fourchan_boards_info(app: &AppHandle, sources: &Sources) -> Vec<BoardInfo> {
+    board_lists::boards(app, sources, Site::FourChan)
+        .await
+        .into_iter()
+        .map(|board| BoardInfo {
+            category: categorize_board(&board.id),
+            id: board.id,
+            name: board.name,
+            nsfw: board.nsfw,
+            description: board.description,
+        })
+        .collect()
 }
 
-#[derive(Debug, Deserialize)]
-struct CatalogPage {
-    threads: Vec<CatalogThread>,
+#[tauri::command]
+pub async fn fetch_all_boards_with_metadata(
+    app: AppHandle,
+    sources: State<'_, Sources>,
+) -> Result<Vec<BoardInfo>, String> {
+    Ok(fourchan_boards_info(&app, &sources).await)
 }
 
-#[derive(Debug, Deserialize)]
-struct CatalogThread {
-    no: u32,
-    #[serde(default)]
-    sub: Option<String>,
-    #[serde(default)]
-    com: Option<String>,
-    #[serde(default)]
-    tim: u64,
-    #[serde(default)]
-    ext: String,
-    #[serde(default)]
-    replies: u32,
-    #[serde(default)]
-    images: u32,
+/// List the boards of any supported site
+#[tauri::command]
+pub async fn fetch_boards(
+    site: Site,
+    app: AppHandle,
+    sources: State<'_, Sources>,
+) -> Result<Vec<SiteBoard>, String> {
+    Ok(board_lists::boards(&app, &sources, site).await)
 }
 
+/// Posting limits, cooldowns and feature flags of a 4chan board
 #[tauri::command]
-pub fn fetch_all_boards_with_metadata() -> Result<Vec<BoardInfo>, String> {
-    Ok(get_all_boards_info())
+pub async fn get_board_capabilities(
+    board: String,
+    client: State<'_, ChanClient>,
+) -> Result<BoardCapabilities, String> {
+    board_capabilities::get(&client, &board).await.map_err(|e| e.to_string())
 }
 
+/// Capabilities of every 4chan board
 #[tauri::command]
-pub fn search_boards(query: String) -> Result<Vec<BoardInfo>, String> {
-    let all_boards = get_all_boards_info();
+pub async fn list_board_capabilities(client: State<'_, ChanClient>) -> Result<Vec<BoardCapabilities>, String> {
+    board_capabilities::all(&client).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn search_boards(
+    query: String,
+    app: AppHandle,
+    sources: State<'_, Sources>,
+) -> Result<Vec<BoardInfo>, String> {
+    let all_boards = fourchan_boards_info(&app, &sources).await;
     let query_lower = query.to_lowercase();
     
     let filtered: Vec<BoardInfo> = all_boards
@@ -57,86 +74,46 @@ pub fn search_boards(query: String) -> Result<Vec<BoardInfo>, String> {
     Ok(filtered)
 }
 
+/// Refetch a site's board list now, ignoring the cache
 #[tauri::command]
-pub async fn fetch_popular_threads() -> Result<Vec<PopularThread>, String> {
-    // Boards to check for popular threads
-    let boards = vec!["g", "v", "a", "tv", "sp", "fit", "int", "sci"];
-    let client = Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-        .build()
-        .map_err(|e| e.to_string())?;
-        
-    let mut tasks = Vec::new();
-
-    println!("Fetching popular threads from boards: {:?}", boards);
-
-    for board in boards {
-        let client = client.clone();
-        tasks.push(tokio::spawn(async move {
-            let url = format!("https://a.4cdn.org/{}/catalog.json", board);
-            println!("Fetching {}", url);
-            match client.get(&url).send().await {
-                Ok(resp) => {
-                    if !resp.status().is_success() {
-                        println!("Failed to fetch {}: Status {}", board, resp.status());
-                        return vec![];
-                    }
-                    match resp.json::<Vec<CatalogPage>>().await {
-                        Ok(catalog) => {
-                            // Get top 2 threads from first page of each board
-                            let mut top_threads = Vec::new();
-                            if let Some(page) = catalog.first() {
-                                // Filter for threads with images
-                                let mut valid_threads: Vec<&CatalogThread> = page.threads.iter()
-                                    .filter(|t| t.tim > 0)
-                                    .collect();
-                                    
-                                // Sort by replies
-                                valid_threads.sort_by(|a, b| b.replies.cmp(&a.replies));
-                                
-                                println!("Board {} has {} valid threads", board, valid_threads.len());
-
-                                // Take top 2
-                                for t in valid_threads.into_iter().take(2) {
-                                    top_threads.push(PopularThread {
-                                        no: t.no,
-                                        board: board.to_string(),
-                                        title: t.sub.clone(),
-                                        com: t.com.clone(),
-                                        tim: t.tim,
-                                        ext: t.ext.clone(),
-                                        replies: t.replies,
-                                        images: t.images,
-                                    });
-                                }
-                            }
-                            top_threads
-                        },
-                        Err(e) => {
-                            println!("Failed to parse JSON for {}: {}", board, e);
-                            vec![]
-                        }
-                    }
-                },
-                Err(e) => {
-                    println!("Failed to send request for {}: {}", board, e);
-                    vec![]
-                }
-            }
-        }));
-    }
+pub async fn refresh_board_list(
+    site: Site,
+    app: AppHandle,
+    sources: State<'_, Sources>,
+) -> Result<Vec<SiteBoard>, String> {
+    board_lists::refresh(&app, &sources, site).await.map_err(|e| e.to_string())
+}
 
-    let mut all_popular = Vec::new();
-    for task in tasks {
-        if let Ok(threads) = task.await {
-            all_popular.extend(threads);
-        }
-    }
+/// A page of the home feed: popular threads from the configured boards,
+/// ranked by `ranking` or the configured mode
+#[tauri::command]
+pub async fn fetch_popular_threads(
+    ranking: Option<FeedRanking>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+    refresh: Option<bool>,
+    app: AppHandle,
+    sources: State<'_, Sources>,
+) -> Result<FeedPage, String> {
+    home_feed::page(
+        &app,
+        &sources,
+        ranking,
+        page.unwrap_or(0),
+        page_size.unwrap_or(24),
+        refresh.unwrap_or(false),
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
 
-    // Sort all collected threads by replies
-    all_popular.sort_by(|a, b| b.replies.cmp(&a.replies));
-    
-    println!("Total popular threads found: {}", all_popular.len());
+/// Boards, ranking and caching of the home feed
+#[tauri::command]
+pub async fn get_home_feed_config() -> Result<FeedConfig, String> {
+    home_feed::config().map_err(|e| e.to_string())
+}
 
-    Ok(all_popular)
+#[tauri::command]
+pub async fn set_home_feed_config(config: FeedConfig) -> Result<(), String> {
+    home_feed::set_config(&config).map_err(|e| e.to_string())
 }
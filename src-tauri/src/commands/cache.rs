@@ -1,10 +1,11 @@
 /**
  * Cache Commands
  * 
- * Tauri commands for thread caching operations.
+ * Tauri commands for thread caching operations and media cache management.
  */
 
-use crate::cache::{self, CacheStats, CachedPost};
+use crate::cache::{self, CacheStats, CachedPost, PostSearchHit};
+use crate::media_cache::{self, CacheLimits, Eviction, PinnedThread, StorageReport};
 use serde::{Deserialize, Serialize};
 
 /// Get cache statistics
@@ -80,3 +81,55 @@ pub struct CleanupResult {
 pub async fn clear_thread_cache() -> Result<(), String> {
     cache::clear_all_cache().map_err(|e| e.to_string())
 }
+
+/// Full-text search over cached posts, best matches first
+#[tauri::command]
+pub async fn search_cached_posts(
+    query: String,
+    board: Option<String>,
+    since: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<PostSearchHit>, String> {
+    cache::search_cached_posts(&query, board.as_deref(), since, limit.unwrap_or(50))
+        .map_err(|e| e.to_string())
+}
+
+/// All cached posts, on any board, carrying the file with this MD5
+#[tauri::command]
+pub async fn find_reposts(md5: String) -> Result<Vec<CachedPost>, String> {
+    cache::find_posts_by_md5(&md5).map_err(|e| e.to_string())
+}
+
+/// Media and thread cache usage, broken down by kind and board
+#[tauri::command]
+pub async fn get_storage_report() -> Result<StorageReport, String> {
+    media_cache::storage_report().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_media_cache_limits() -> Result<CacheLimits, String> {
+    media_cache::get_limits().map_err(|e| e.to_string())
+}
+
+/// Save new limits and evict right away if they are now exceeded
+#[tauri::command]
+pub async fn set_media_cache_limits(limits: CacheLimits) -> Result<Eviction, String> {
+    media_cache::set_limits(&limits).map_err(|e| e.to_string())?;
+    media_cache::enforce_limits().map_err(|e| e.to_string())
+}
+
+/// Keep a thread's media out of LRU eviction
+#[tauri::command]
+pub async fn pin_thread_media(board: String, thread_id: u64) -> Result<(), String> {
+    media_cache::pin_thread(&board, thread_id, "manual").map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unpin_thread_media(board: String, thread_id: u64) -> Result<bool, String> {
+    media_cache::unpin_thread(&board, thread_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_pinned_threads() -> Result<Vec<PinnedThread>, String> {
+    media_cache::list_pins().map_err(|e| e.to_string())
+}
@@ -1,10 +1,43 @@
 use tauri::State;
-use crate::api::{ChanClient, Catalog, catalog_endpoint};
+use crate::api::CatalogThread;
+use crate::catalog_search::{CatalogQuery, CatalogSort};
+use crate::filters;
+use crate::sources::{Site, Sources};
 
 #[tauri::command]
-pub async fn fetch_catalog(board: String, client: State<'_, ChanClient>) -> Result<Catalog, String> {
-    client
-        .get_json(&catalog_endpoint(&board))
+pub async fn fetch_catalog(
+    site: Site,
+    board: String,
+    sources: State<'_, Sources>,
+) -> Result<Vec<CatalogThread>, String> {
+    let mut threads = sources
+        .get(site)
+        .catalog(&board)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    filters::apply_to_catalog(site, &board, &mut threads);
+    Ok(threads)
+}
+
+/// Fetch a catalog and return only the threads matching `query`, sorted by `sort`
+#[tauri::command]
+pub async fn search_catalog(
+    site: Site,
+    board: String,
+    query: String,
+    sort: Option<CatalogSort>,
+    sources: State<'_, Sources>,
+) -> Result<Vec<CatalogThread>, String> {
+    // Reject malformed queries before hitting the network
+    let query = CatalogQuery::parse(&query).map_err(|e| e.to_string())?;
+
+    let mut threads = sources
+        .get(site)
+        .catalog(&board)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    filters::apply_to_catalog(site, &board, &mut threads);
+    Ok(query.apply(threads, sort.unwrap_or_default()))
 }
@@ -0,0 +1,86 @@
+/**
+ * Download Commands
+ *
+ * Tauri commands for the bulk media download queue.
+ * Progress is pushed via `download-progress` and `download-queue` events.
+ */
+
+use std::path::PathBuf;
+use tauri::{AppHandle, State};
+use crate::downloads::{DownloadJob, DownloadManager, MediaFilter, NewDownload};
+use crate::sources::{Site, Sources};
+
+/// Queue every file of a thread that passes `filter`, saved as `{dest_dir}/{tim}{ext}`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn enqueue_thread_media(
+    site: Site,
+    board: String,
+    thread_id: u64,
+    dest_dir: String,
+    filter: Option<MediaFilter>,
+    app: AppHandle,
+    sources: State<'_, Sources>,
+    downloads: State<'_, DownloadManager>,
+) -> Result<Vec<DownloadJob>, String> {
+    let source = sources.get(site);
+    let thread = source
+        .thread(&board, thread_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let filter = filter.unwrap_or_default();
+    let dest_dir = PathBuf::from(dest_dir);
+
+    let queued = thread
+        .posts
+        .iter()
+        .filter(|post| filter.accepts(post))
+        .filter_map(|post| {
+            let (tim, ext) = (post.tim.as_ref()?, post.ext.as_ref()?);
+            Some(NewDownload {
+                board: board.clone(),
+                thread_id,
+                post_no: post.no,
+                url: source.media_url(&board, tim, ext),
+                path: dest_dir.join(format!("{}{}", tim, ext)),
+                md5: post.md5.clone(),
+            })
+        })
+        .collect();
+
+    Ok(downloads.enqueue(&app, queued))
+}
+
+/// All jobs in the download queue
+#[tauri::command]
+pub fn list_downloads(downloads: State<'_, DownloadManager>) -> Vec<DownloadJob> {
+    downloads.list()
+}
+
+#[tauri::command]
+pub fn pause_download(id: u64, app: AppHandle, downloads: State<'_, DownloadManager>) -> Result<(), String> {
+    downloads.pause(&app, id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn resume_download(id: u64, app: AppHandle, downloads: State<'_, DownloadManager>) -> Result<(), String> {
+    downloads.resume(&app, id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn cancel_download(id: u64, app: AppHandle, downloads: State<'_, DownloadManager>) -> Result<(), String> {
+    downloads.cancel(&app, id).map_err(|e| e.to_string())
+}
+
+/// Remove completed and cancelled jobs from the queue
+#[tauri::command]
+pub fn clear_finished_downloads(app: AppHandle, downloads: State<'_, DownloadManager>) {
+    downloads.clear_finished(&app)
+}
+
+/// Maximum number of simultaneous downloads
+#[tauri::command]
+pub fn set_download_concurrency(concurrency: usize, app: AppHandle, downloads: State<'_, DownloadManager>) {
+    downloads.set_concurrency(&app, concurrency)
+}
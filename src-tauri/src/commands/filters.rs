@@ -0,0 +1,38 @@
+/**
+ * Filter Commands
+ *
+ * Tauri commands for managing 4chan X style filter rules.
+ * Rules are applied by the thread and catalog commands.
+ */
+
+use crate::filters::{self, FilterRule, FilterRuleInput};
+
+/// List all filter rules
+#[tauri::command]
+pub async fn list_filter_rules() -> Result<Vec<FilterRule>, String> {
+    filters::list_rules().map_err(|e| e.to_string())
+}
+
+/// Add a filter rule
+#[tauri::command]
+pub async fn add_filter_rule(rule: FilterRuleInput) -> Result<FilterRule, String> {
+    filters::add_rule(&rule).map_err(|e| e.to_string())
+}
+
+/// Replace an existing filter rule
+#[tauri::command]
+pub async fn update_filter_rule(id: i64, rule: FilterRuleInput) -> Result<FilterRule, String> {
+    filters::update_rule(id, &rule).map_err(|e| e.to_string())
+}
+
+/// Delete a filter rule
+#[tauri::command]
+pub async fn delete_filter_rule(id: i64) -> Result<bool, String> {
+    filters::delete_rule(id).map_err(|e| e.to_string())
+}
+
+/// Import filters from a 4chan X settings export, returning how many were added
+#[tauri::command]
+pub async fn import_4chanx_filters(data: String) -> Result<usize, String> {
+    filters::import_4chanx(&data).map_err(|e| e.to_string())
+}
@@ -1,59 +1,38 @@
-use tauri::{AppHandle, Manager};
-use reqwest;
-use std::path::PathBuf;
 use base64::{Engine as _, engine::general_purpose};
-
+use crate::media_protocol;
+use crate::media_store;
+use crate::sources::Sources;
+use tauri::State;
+
+const IMAGE_EXTENSIONS: &[&str] = &[".jpg", ".jpeg", ".png", ".gif", ".webp"];
+
+/// Fetch an image through the backend and return it as a data URL.
+/// Full-size images are verified against `md5` when given; all images are
+/// kept in the content-addressed media store.
+///
+/// Prefer `chanmedia://` URLs, which skip the base64 round trip over IPC.
 #[tauri::command]
 pub async fn fetch_image_with_cors_bypass(
     url: String,
-    app: AppHandle,
+    md5: Option<String>,
+    sources: State<'_, Sources>,
 ) -> Result<String, String> {
-    // Create cache directory
-    let cache_dir = app.path().cache_dir()
-        .map_err(|e| format!("Failed to get cache dir: {}", e))?
-        .join("images");
-    
-    std::fs::create_dir_all(&cache_dir)
-        .map_err(|e| format!("Failed to create cache dir: {}", e))?;
-
-    // Extract filename from URL
-    let filename = url.split('/').last()
-        .ok_or_else(|| "Invalid URL".to_string())?;
-    
-    let cache_path = cache_dir.join(filename);
-
-    // Check if already cached
-    if cache_path.exists() {
-        let bytes = std::fs::read(&cache_path)
-            .map_err(|e| format!("Failed to read cached image: {}", e))?;
-        
-        // Detect MIME type from extension
-        let mime_type = get_mime_type(filename);
-        let base64 = general_purpose::STANDARD.encode(&bytes);
-        return Ok(format!("data:{};base64,{}", mime_type, base64));
+    if !sources.is_media_url(&url) {
+        return Err(format!("Not a known media host: {}", url));
     }
-
-    // Download image
-    let client = reqwest::Client::new();
-    let response = client.get(&url)
-        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .header("Referer", "https://boards.4chan.org/")
-        .send()
+    let stored = media_protocol::resolve(&url, md5.as_deref(), None)
         .await
-        .map_err(|e| format!("Failed to download image: {}", e))?;
+        .map_err(|e| format!("Failed to fetch image: {}", e))?;
+    let bytes = std::fs::read(&stored.path)
+        .map_err(|e| format!("Failed to read cached image: {}", e))?;
 
-    let bytes = response.bytes()
-        .await
-        .map_err(|e| format!("Failed to read image bytes: {}", e))?;
-
-    // Save to cache
-    std::fs::write(&cache_path, &bytes)
-        .map_err(|e| format!("Failed to write image: {}", e))?;
+    Ok(to_data_url(&media_store::url_ext(&url), &bytes))
+}
 
-    // Return as base64 data URL
-    let mime_type = get_mime_type(filename);
-    let base64 = general_purpose::STANDARD.encode(&bytes);
-    Ok(format!("data:{};base64,{}", mime_type, base64))
+fn to_data_url(ext: &str, bytes: &[u8]) -> String {
+    let mime_type = get_mime_type(ext);
+    let base64 = general_purpose::STANDARD.encode(bytes);
+    format!("data:{};base64,{}", mime_type, base64)
 }
 
 fn get_mime_type(filename: &str) -> &str {
@@ -71,15 +50,7 @@ fn get_mime_type(filename: &str) -> &str {
 }
 
 #[tauri::command]
-pub async fn clear_image_cache(app: AppHandle) -> Result<(), String> {
-    let cache_dir = app.path().cache_dir()
-        .map_err(|e| format!("Failed to get cache dir: {}", e))?
-        .join("images");
-    
-    if cache_dir.exists() {
-        std::fs::remove_dir_all(&cache_dir)
-            .map_err(|e| format!("Failed to clear cache: {}", e))?;
-    }
-    
+pub async fn clear_image_cache() -> Result<(), String> {
+    media_store::remove_by_ext(IMAGE_EXTENSIONS).map_err(|e| e.to_string())?;
     Ok(())
 }
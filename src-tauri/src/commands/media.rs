@@ -1,52 +1,51 @@
 use std::path::PathBuf;
 use std::fs;
-use dirs;
+use tauri::State;
+use crate::media_protocol;
+use crate::media_store;
+use crate::thumbnails;
+use crate::sources::{Site, Sources};
 
 fn get_media_cache_dir() -> PathBuf {
-    let cache_dir = dirs::cache_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("chandesk")
-        .join("media");
-    
-    fs::create_dir_all(&cache_dir).ok();
-    cache_dir
+    media_store::media_root()
 }
 
+/// Download a file into the media store, verifying it against `md5` when given.
+/// Files already stored under the same MD5 (reposts) are not downloaded again,
+/// and error responses are never stored.
 #[tauri::command]
 pub async fn download_media(
     url: String,
     board: String,
     thread_id: u64,
+    md5: Option<String>,
+    sources: State<'_, Sources>,
 ) -> Result<String, String> {
-    let cache_dir = get_media_cache_dir();
-    let board_dir = cache_dir.join(&board).join(thread_id.to_string());
-    
-    fs::create_dir_all(&board_dir).map_err(|e| e.to_string())?;
-
-    // Extract filename from URL
-    let filename = url
-        .split('/')
-        .last()
-        .ok_or("Invalid URL")?
-        .to_string();
-    
-    let file_path = board_dir.join(&filename);
-
-    // Check if already cached
-    if file_path.exists() {
-        return Ok(file_path.to_string_lossy().to_string());
+    if !sources.is_media_url(&url) {
+        return Err(format!("Not a known media host: {}", url));
     }
-
-    // Download file
-    let response = reqwest::get(&url)
+    let stored = media_protocol::resolve(&url, md5.as_deref(), Some((board.as_str(), thread_id)))
         .await
         .map_err(|e| e.to_string())?;
+    Ok(stored.path.to_string_lossy().to_string())
+}
 
-    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
-
-    fs::write(&file_path, bytes).map_err(|e| e.to_string())?;
-
-    Ok(file_path.to_string_lossy().to_string())
+/// Resolve the full-size or thumbnail URL of a post's media on any site
+#[tauri::command]
+pub fn get_media_url(
+    site: Site,
+    board: String,
+    tim: String,
+    ext: String,
+    thumbnail: Option<bool>,
+    sources: State<'_, Sources>,
+) -> String {
+    let source = sources.get(site);
+    if thumbnail.unwrap_or(false) {
+        source.thumbnail_url(&board, &tim)
+    } else {
+        source.media_url(&board, &tim, &ext)
+    }
 }
 
 #[tauri::command]
@@ -55,14 +54,8 @@ pub async fn get_cached_media_path(
     thread_id: u64,
     filename: String,
 ) -> Result<Option<String>, String> {
-    let cache_dir = get_media_cache_dir();
-    let file_path = cache_dir.join(&board).join(thread_id.to_string()).join(&filename);
-
-    if file_path.exists() {
-        Ok(Some(file_path.to_string_lossy().to_string()))
-    } else {
-        Ok(None)
-    }
+    let stored = media_store::lookup_file(&board, thread_id, &filename).map_err(|e| e.to_string())?;
+    Ok(stored.map(|s| s.path.to_string_lossy().to_string()))
 }
 
 #[tauri::command]
@@ -70,6 +63,7 @@ pub async fn clear_media_cache() -> Result<(), String> {
     let cache_dir = get_media_cache_dir();
     fs::remove_dir_all(&cache_dir).map_err(|e| e.to_string())?;
     fs::create_dir_all(&cache_dir).map_err(|e| e.to_string())?;
+    media_store::clear_refs().map_err(|e| e.to_string())?;
     Ok(())
 }
 
@@ -95,3 +89,28 @@ pub async fn get_cache_size() -> Result<u64, String> {
 
     dir_size(&cache_dir).map_err(|e| e.to_string())
 }
+
+/// Dimensions, size and a thumbnail of a local file, e.g. one picked for posting
+#[tauri::command]
+pub async fn get_local_media_info(
+    path: String,
+    thumbnail_size: Option<u32>,
+) -> Result<thumbnails::LocalImageInfo, String> {
+    let size = thumbnail_size
+        .map(thumbnails::clamp_size)
+        .unwrap_or_else(thumbnails::thumbnail_size);
+    thumbnails::local_image_info(PathBuf::from(path), size)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_thumbnail_size() -> u32 {
+    thumbnails::thumbnail_size()
+}
+
+/// Set the size of generated thumbnails; returns the value actually stored
+#[tauri::command]
+pub fn set_thumbnail_size(size: u32) -> Result<u32, String> {
+    thumbnails::set_thumbnail_size(size).map_err(|e| e.to_string())
+}
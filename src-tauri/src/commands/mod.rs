@@ -4,12 +4,13 @@ pub mod thread;
 pub mod media;
 pub mod image_proxy;
 pub mod posting;
-pub mod sevenchan;
-pub mod fourplebs;
-pub mod archivedmoe;
-pub mod twentytwochan;
 pub mod video_proxy;
 pub mod cache;
+pub mod watcher;
+pub mod filters;
+pub mod archive;
+pub mod downloads;
+pub mod activity;
 
 pub use boards::*;
 pub use catalog::*;
@@ -17,11 +18,12 @@ pub use thread::*;
 pub use media::*;
 pub use image_proxy::*;
 pub use posting::*;
-pub use sevenchan::*;
-pub use fourplebs::*;
-pub use archivedmoe::*;
-pub use twentytwochan::*;
 pub use video_proxy::*;
 pub use cache::*;
+pub use watcher::*;
+pub use filters::*;
+pub use archive::*;
+pub use downloads::*;
+pub use activity::*;
 
 
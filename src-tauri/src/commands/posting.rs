@@ -1,16 +1,20 @@
 use reqwest::{Client, multipart};
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
-use std::time::{Duration, Instant};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::State;
 
-const POST_URL: &str = "https://sys.4chan.org";
-const RATE_LIMIT_SECONDS: u64 = 60; // 1 minute between posts
+use crate::api::ChanClient;
+use crate::board_capabilities;
+use crate::cache;
+use crate::cooldowns::{self, PostKind, DEFAULT_COOLDOWNS};
+use crate::post_queue::{self, QueuedPost};
+use crate::sources::Site;
+use crate::upload::{self, SanitizeOptions, UploadError, UploadLimits};
 
-lazy_static::lazy_static! {
-    static ref LAST_POST_TIME: Mutex<Option<Instant>> = Mutex::new(None);
-}
+const POST_URL: &str = "https://sys.4chan.org";
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PostRequest {
     pub board: String,
     pub resto: Option<u64>, // 0 or None for new thread, thread number for reply
@@ -22,6 +26,9 @@ pub struct PostRequest {
     pub file_name: Option<String>,
     pub captcha_challenge: Option<String>,
     pub captcha_response: Option<String>,
+    /// Metadata stripping, renaming and noise applied to the file before upload
+    #[serde(default)]
+    pub sanitize: Option<SanitizeOptions>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,33 +37,43 @@ pub struct PostResponse {
     pub thread_id: Option<u64>,
     pub post_id: Option<u64>,
     pub error: Option<String>,
-}
-
-/// Check rate limit and return seconds until next post allowed
-fn check_rate_limit() -> Option<u64> {
-    let last = LAST_POST_TIME.lock().unwrap();
-    if let Some(instant) = *last {
-        let elapsed = instant.elapsed();
-        if elapsed < Duration::from_secs(RATE_LIMIT_SECONDS) {
-            return Some(RATE_LIMIT_SECONDS - elapsed.as_secs());
-        }
-    }
-    None
-}
-
-fn update_last_post_time() {
-    let mut last = LAST_POST_TIME.lock().unwrap();
-    *last = Some(Instant::now());
+    /// Why the attached file was rejected before sending, if it was
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub validation_errors: Vec<UploadError>,
 }
 
 #[tauri::command]
 pub async fn submit_post(
     request: PostRequest,
     pass_token: Option<String>,
+    client: State<'_, ChanClient>,
 ) -> Result<PostResponse, String> {
-    // Check rate limit
-    if let Some(seconds) = check_rate_limit() {
-        return Err(format!("Please wait {} seconds before posting again", seconds));
+    send_post(&request, pass_token.as_deref(), &client).await
+}
+
+/// Post to 4chan. Shared by `submit_post` and the post queue.
+pub async fn send_post(
+    request: &PostRequest,
+    pass_token: Option<&str>,
+    client: &ChanClient,
+) -> Result<PostResponse, String> {
+    // Check this board's cooldown for this kind of post
+    let kind = PostKind::of(request.resto, request.file_path.is_some());
+    let wait = cooldowns::remaining(&request.board, kind).map_err(|e| e.to_string())?;
+    if wait > 0 {
+        return Err(format!("Please wait {} seconds before posting again", wait));
+    }
+
+    let board = board_capabilities::get(client, &request.board).await.ok();
+    let board_cooldowns = board.as_ref().map_or(DEFAULT_COOLDOWNS, |b| b.cooldowns.clone());
+    if let Some(board) = &board {
+        let length = request.comment.chars().count() as u32;
+        if length > board.max_comment_chars {
+            return Ok(rejected_post(format!(
+                "Comment is too long ({} characters, the limit is {})",
+                length, board.max_comment_chars
+            )));
+        }
     }
 
     // Build multipart form
@@ -76,26 +93,38 @@ pub async fn submit_post(
         form = form.text("subject", subject.clone());
     }
 
-    // File attachment
+    // File attachment, sanitized and checked against the board's limits
     if let Some(file_path) = &request.file_path {
-        let file_data = std::fs::read(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let file_path = file_path.clone();
         let filename = request.file_name.clone().unwrap_or_else(|| {
-            std::path::Path::new(file_path)
+            std::path::Path::new(&file_path)
                 .file_name()
                 .and_then(|n| n.to_str())
                 .unwrap_or("file")
                 .to_string()
         });
-        
-        let mime = mime_guess::from_path(file_path)
-            .first_or_octet_stream()
-            .to_string();
-            
-        let part = multipart::Part::bytes(file_data)
-            .file_name(filename)
-            .mime_str(&mime)
+
+        let board_id = request.board.clone();
+        let options = request.sanitize.clone().unwrap_or_default();
+        let limits = board.as_ref().map(UploadLimits::from).unwrap_or_else(|| upload::limits_for(&request.board));
+
+        // Decoding, re-encoding and scanning the file is CPU-bound
+        let prepared = tauri::async_runtime::spawn_blocking(move || {
+            let file_data = std::fs::read(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+            Ok::<_, String>(upload::prepare(&board_id, file_data, &filename, &options, limits))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+        let prepared = match prepared {
+            Ok(prepared) => prepared,
+            Err(errors) => return Ok(rejected_upload(errors)),
+        };
+
+        let part = multipart::Part::bytes(prepared.bytes)
+            .file_name(prepared.filename)
+            .mime_str(&prepared.mime)
             .map_err(|e| e.to_string())?;
-            
+
         form = form.part("upfile", part);
     }
 
@@ -133,26 +162,61 @@ pub async fn submit_post(
         .await
         .map_err(|e| format!("Network error: {}", e))?;
 
-    update_last_post_time();
-
     let status = response.status();
     let text = response.text().await.unwrap_or_default();
 
     // Parse response
-    // 4chan returns HTML, we need to check for success/error patterns
-    if text.contains("Post successful") || text.contains("Thread posted") {
-        // Try to extract post/thread number from response
-        // This is a simplified parser - real implementation would need proper HTML parsing
+    // 4chan returns HTML; the success page carries a `<!-- thread:X,no:Y -->` marker
+    let posted = parse_post_marker(&text).is_some() || text.contains("Post successful") || text.contains("Thread posted");
+    if posted {
+        if let Err(e) = cooldowns::record_post(&request.board, kind, &board_cooldowns) {
+            eprintln!("Failed to record post cooldown: {}", e);
+        }
+    }
+
+    if let Some((thread, no)) = parse_post_marker(&text) {
+        // thread:0 means we started a new thread, which is numbered after its OP
+        let thread_id = if thread == 0 { no } else { thread };
+
+        if let Err(e) = cache::add_own_post(Site::FourChan, &request.board, thread_id as i64, no as i64) {
+            eprintln!("Failed to record own post: {}", e);
+        }
+
+        Ok(PostResponse {
+            success: true,
+            thread_id: Some(thread_id),
+            post_id: Some(no),
+            error: None,
+            validation_errors: Vec::new(),
+        })
+    } else if text.contains("Post successful") || text.contains("Thread posted") {
+        // Success page without a marker; we can't tell which post is ours
         Ok(PostResponse {
             success: true,
-            thread_id: if request.resto.unwrap_or(0) == 0 { Some(0) } else { None }, // Would need to parse
-            post_id: Some(0), // Would need to parse
+            thread_id: request.resto.filter(|&r| r != 0),
+            post_id: None,
             error: None,
+            validation_errors: Vec::new(),
         })
     } else if text.contains("Error") || !status.is_success() {
+        // The server's own cooldown outranks ours
+        let server_error = parse_error_message(&text);
+        let cooldown = cooldowns::record_rejection(
+            &request.board,
+            kind,
+            server_error.as_deref().unwrap_or(&text),
+            &board_cooldowns,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to record post cooldown: {}", e);
+            None
+        });
+
         // Extract error message
         let error = if text.contains("banned") {
             "You are banned from posting".to_string()
+        } else if let Some(secs) = cooldown {
+            format!("You must wait {} seconds before posting again", secs)
         } else if text.contains("flood detected") {
             "Flood detected. Please wait before posting again.".to_string()
         } else if text.contains("CAPTCHA") || text.contains("Verification") {
@@ -170,6 +234,7 @@ pub async fn submit_post(
             thread_id: None,
             post_id: None,
             error: Some(error),
+            validation_errors: Vec::new(),
         })
     } else {
         Ok(PostResponse {
@@ -177,13 +242,155 @@ pub async fn submit_post(
             thread_id: None,
             post_id: None,
             error: Some("Unknown response from server".to_string()),
+            validation_errors: Vec::new(),
         })
     }
 }
 
+fn rejected_post(error: String) -> PostResponse {
+    PostResponse {
+        success: false,
+        thread_id: None,
+        post_id: None,
+        error: Some(error),
+        validation_errors: Vec::new(),
+    }
+}
+
+fn rejected_upload(errors: Vec<UploadError>) -> PostResponse {
+    let message = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("; ");
+    PostResponse {
+        validation_errors: errors,
+        ..rejected_post(message)
+    }
+}
+
+/// Upload limits from the board's record, or the built-in defaults when
+/// `boards.json` can't be loaded
+async fn upload_limits(client: &ChanClient, board: &str) -> UploadLimits {
+    match board_capabilities::get(client, board).await {
+        Ok(capabilities) => UploadLimits::from(&capabilities),
+        Err(_) => upload::limits_for(board),
+    }
+}
+
+/// Text of the `<span id="errmsg">` on 4chan's error page
+fn parse_error_message(html: &str) -> Option<String> {
+    let start = html.find("id=\"errmsg\"")?;
+    let rest = &html[start..];
+    let text = &rest[rest.find('>')? + 1..];
+    let text = text[..text.find('<').unwrap_or(text.len())].trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Extract `(thread, no)` from the `<!-- thread:X,no:Y -->` success marker
+fn parse_post_marker(html: &str) -> Option<(u64, u64)> {
+    let start = html.find("<!-- thread:")? + "<!-- thread:".len();
+    let rest = &html[start..];
+    let marker = &rest[..rest.find("-->")?];
+    let (thread, no) = marker.trim().split_once(",no:")?;
+    Some((thread.trim().parse().ok()?, no.trim().parse().ok()?))
+}
+
+/// Posts we made in a thread
+#[tauri::command]
+pub async fn get_own_posts(site: Site, board: String, thread_id: i64) -> Result<Vec<cache::OwnPost>, String> {
+    cache::get_own_posts(site, &board, thread_id).map_err(|e| e.to_string())
+}
+
+/// Result of checking a file before posting it
+#[derive(Debug, Serialize)]
+pub struct UploadCheck {
+    pub filename: String,
+    pub size: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub errors: Vec<UploadError>,
+}
+
+/// Run the upload pipeline on a file without posting it, so problems can be
+/// shown while the post is being written
+#[tauri::command]
+pub async fn validate_upload(
+    board: String,
+    file_path: String,
+    sanitize: Option<SanitizeOptions>,
+    client: State<'_, ChanClient>,
+) -> Result<UploadCheck, String> {
+    let options = sanitize.unwrap_or_default();
+    let limits = upload_limits(&client, &board).await;
+
+    // Reading, decoding and re-encoding the file is blocking and CPU-bound
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = std::path::Path::new(&file_path);
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("file").to_string();
+        let original_size = bytes.len() as u64;
+
+        Ok::<_, String>(match upload::prepare(&board, bytes, &filename, &options, limits) {
+            Ok(prepared) => UploadCheck {
+                size: prepared.bytes.len() as u64,
+                filename: prepared.filename,
+                width: prepared.width,
+                height: prepared.height,
+                errors: Vec::new(),
+            },
+            Err(errors) => UploadCheck { filename, size: original_size, width: None, height: None, errors },
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Upload limits of a board
+#[tauri::command]
+pub async fn get_upload_limits(board: String, client: State<'_, ChanClient>) -> Result<UploadLimits, String> {
+    Ok(upload_limits(&client, &board).await)
+}
+
+/// Seconds until a post of `kind` (a reply by default) is allowed on `board`,
+/// or the longest active cooldown anywhere when no board is given
+#[tauri::command]
+pub fn get_post_cooldown(board: Option<String>, kind: Option<PostKind>) -> Result<u64, String> {
+    match board {
+        Some(board) => cooldowns::remaining(&board, kind.unwrap_or(PostKind::Reply)).map_err(|e| e.to_string()),
+        None => Ok(cooldowns::all_remaining()
+            .map_err(|e| e.to_string())?
+            .values()
+            .flat_map(|kinds| kinds.values().copied())
+            .max()
+            .unwrap_or(0)),
+    }
+}
+
+/// Every active cooldown: board -> post kind -> seconds left
+#[tauri::command]
+pub fn get_post_cooldowns() -> Result<HashMap<String, HashMap<PostKind, u64>>, String> {
+    cooldowns::all_remaining().map_err(|e| e.to_string())
+}
+
+/// Save a draft to be posted once the board's cooldown allows it
+#[tauri::command]
+pub fn queue_post(request: PostRequest, pass_token: Option<String>) -> Result<QueuedPost, String> {
+    post_queue::enqueue(request, pass_token).map_err(|e| e.to_string())
+}
+
+/// Drafts in the post queue, oldest first
+#[tauri::command]
+pub fn list_queued_posts() -> Result<Vec<QueuedPost>, String> {
+    post_queue::list().map_err(|e| e.to_string())
+}
+
+/// Edit a waiting or failed draft; it is queued again with fresh retries
+#[tauri::command]
+pub fn update_queued_post(id: i64, request: PostRequest, pass_token: Option<String>) -> Result<QueuedPost, String> {
+    post_queue::update(id, request, pass_token).map_err(|e| e.to_string())
+}
+
+/// Remove a draft from the queue
 #[tauri::command]
-pub fn get_post_cooldown() -> u64 {
-    check_rate_limit().unwrap_or(0)
+pub fn cancel_queued_post(id: i64) -> Result<bool, String> {
+    post_queue::cancel(id).map_err(|e| e.to_string())
 }
 
 /// Captcha challenge response from 4chan
@@ -1,14 +1,204 @@
+use std::collections::{BTreeSet, HashSet};
+use serde::Serialize;
 use tauri::State;
-use crate::api::{ChanClient, Thread, thread_endpoint};
+use crate::api::{thread_endpoint, ChanClient, Conditional, Post, Thread};
+use crate::comment::{self, CommentFormat};
+use crate::board_capabilities;
+use crate::cache;
+use crate::filters;
+use crate::reply_graph::{self, ReplyGraph};
+use crate::sources::{Site, Sources};
+use crate::thread_stats::{self, ThreadStats};
+
+/// Incremental thread update
+#[derive(Debug, Serialize)]
+pub struct ThreadUpdate {
+    pub modified: bool,
+    pub new_posts: Vec<Post>,
+    pub deleted_posts: Vec<u64>,
+    /// `Last-Modified` to pass as `since` on the next refresh
+    pub last_modified: Option<String>,
+}
 
 #[tauri::command]
 pub async fn fetch_thread(
+    site: Site,
     board: String,
     thread_id: u64,
-    client: State<'_, ChanClient>
+    sources: State<'_, Sources>,
 ) -> Result<Thread, String> {
-    client
-        .get_json(&thread_endpoint(&board, thread_id))
+    let mut thread = sources
+        .get(site)
+        .thread(&board, thread_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    filters::apply_to_thread(site, &board, &mut thread);
+    comment::annotate(&mut thread.posts, site.comment_format());
+    Ok(thread)
+}
+
+/// Refresh a 4chan thread, returning only posts newer than `after_post`
+/// and which of `known_posts` disappeared. `since` is the `last_modified` of
+/// the caller's previous refresh; each view tracks its own, so views of the
+/// same thread never hide updates from each other.
+#[tauri::command]
+pub async fn refresh_thread(
+    board: String,
+    thread_id: u64,
+    after_post: u64,
+    since: Option<String>,
+    known_posts: Option<Vec<u64>>,
+    client: State<'_, ChanClient>,
+) -> Result<ThreadUpdate, String> {
+    let (mut thread, last_modified): (Thread, _) = match client
+        .get_json_since(&thread_endpoint(&board, thread_id), since.as_deref())
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Conditional::Modified(modified) => modified,
+        Conditional::NotModified => {
+            return Ok(ThreadUpdate {
+                modified: false,
+                new_posts: Vec::new(),
+                deleted_posts: Vec::new(),
+                last_modified: since,
+            })
+        }
+    };
+
+    let current: HashSet<u64> = thread.posts.iter().map(|p| p.no).collect();
+    let mut deleted_posts: Vec<u64> = known_posts
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|no| !current.contains(no))
+        .collect();
+    deleted_posts.sort_unstable();
+    deleted_posts.dedup();
+
+    // Filter after the snapshot so hidden posts aren't reported as deleted
+    filters::apply_to_thread(Site::FourChan, &board, &mut thread);
+
+    let mut new_posts: Vec<Post> = thread
+        .posts
+        .into_iter()
+        .filter(|p| p.no > after_post)
+        .collect();
+    comment::annotate(&mut new_posts, CommentFormat::Html);
+
+    Ok(ThreadUpdate {
+        modified: true,
+        new_posts,
+        deleted_posts,
+        last_modified: last_modified.or(since),
+    })
+}
+
+/// Quote links of every post in a thread
+#[tauri::command]
+pub async fn get_reply_graph(
+    site: Site,
+    board: String,
+    thread_id: u64,
+    sources: State<'_, Sources>,
+) -> Result<ReplyGraph, String> {
+    let thread = sources
+        .get(site)
+        .thread(&board, thread_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(ReplyGraph::build(&thread.posts, site.comment_format()))
+}
+
+/// A post with everything it answers and everything answering it, in thread order
+#[tauri::command]
+pub async fn get_reply_chain(
+    site: Site,
+    board: String,
+    thread_id: u64,
+    post_no: u64,
+    sources: State<'_, Sources>,
+) -> Result<Vec<Post>, String> {
+    let thread = sources
+        .get(site)
+        .thread(&board, thread_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let graph = ReplyGraph::build(&thread.posts, site.comment_format());
+    let chain = graph.reply_chain(post_no).map_err(|e| e.to_string())?;
+    Ok(selected_posts(site, &board, thread, &chain))
+}
+
+/// The back-and-forth between two posts: the posts on the reply paths connecting them
+#[tauri::command]
+pub async fn get_conversation(
+    site: Site,
+    board: String,
+    thread_id: u64,
+    post_a: u64,
+    post_b: u64,
+    sources: State<'_, Sources>,
+) -> Result<Vec<Post>, String> {
+    let thread = sources
+        .get(site)
+        .thread(&board, thread_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    let graph = ReplyGraph::build(&thread.posts, site.comment_format());
+    let conversation = graph.conversation(post_a, post_b).map_err(|e| e.to_string())?;
+    Ok(selected_posts(site, &board, thread, &conversation))
+}
+
+/// Posts of `thread` in `selected` that pass the filters, with parsed comments.
+/// Filters run on the whole thread so hidden reply chains stay hidden.
+fn selected_posts(site: Site, board: &str, mut thread: Thread, selected: &BTreeSet<u64>) -> Vec<Post> {
+    filters::apply_to_thread(site, board, &mut thread);
+    let mut posts = reply_graph::select(thread.posts, selected);
+    comment::annotate(&mut posts, site.comment_format());
+    posts
+}
+
+/// Statistics of a thread. Reads the thread cache when `cached` is set or the
+/// thread can't be fetched (e.g. it 404'd); the cache only holds 4chan threads,
+/// so other sites always fetch.
+#[tauri::command]
+pub async fn get_thread_stats(
+    site: Site,
+    board: String,
+    thread_id: u64,
+    cached: Option<bool>,
+    sources: State<'_, Sources>,
+    client: State<'_, ChanClient>,
+) -> Result<ThreadStats, String> {
+    let use_cache = site == Site::FourChan;
+    let live = if cached.unwrap_or(false) && use_cache {
+        None
+    } else {
+        match sources.get(site).thread(&board, thread_id).await {
+            Ok(thread) => Some(thread.posts),
+            Err(e) if use_cache => {
+                eprintln!("Failed to fetch /{}/{} for stats, using cache: {}", board, thread_id, e);
+                None
+            }
+            Err(e) => return Err(format!("Failed to fetch /{}/{}: {}", board, thread_id, e)),
+        }
+    };
+
+    let posts = match live {
+        Some(posts) => posts,
+        None => match cache::get_cached_thread(&board, thread_id as i64).map_err(|e| e.to_string())? {
+            Some((_, posts)) => thread_stats::posts_from_cache(posts),
+            None => return Err(format!("Thread /{}/{} is not cached", board, thread_id)),
+        },
+    };
+
+    let bump_limit = match site {
+        Site::FourChan => board_capabilities::get(&client, &board).await.ok().map(|b| b.bump_limit),
+        _ => None,
+    };
+
+    Ok(thread_stats::compute(&posts, site.comment_format(), bump_limit))
 }
@@ -2,13 +2,14 @@
  * Video Proxy Commands
  * 
  * Proxies video content from 4chan CDN to bypass CORS restrictions.
- * Downloads videos to local cache, then serves via local HTTP server.
+ * Cache misses are streamed through the local HTTP server while they download.
  */
 
-use tauri::{AppHandle, Manager};
-use std::fs::File;
-use std::io::Write;
+use crate::media_store;
 use crate::video_server::get_video_server_url;
+use crate::video_stream;
+
+const VIDEO_EXTENSIONS: &[&str] = &[".webm", ".mp4", ".swf"];
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct VideoInfo {
@@ -17,80 +18,57 @@ pub struct VideoInfo {
     pub cached: bool,        // Whether video was already cached
 }
 
-/// Download video to cache and return local server URL for playback
+/// Return a local server URL for playback. Cached videos are served from the
+/// media store; others are streamed from the CDN while being saved, verified
+/// against `md5` when given, so reposts are only fetched once.
 #[tauri::command]
-pub async fn proxy_video(url: String, app: AppHandle) -> Result<VideoInfo, String> {
+pub async fn proxy_video(url: String, md5: Option<String>) -> Result<VideoInfo, String> {
     // Get video server URL
     let server_url = get_video_server_url()
         .ok_or_else(|| "Video server not running".to_string())?;
     
-    // Create cache directory
-    let cache_dir = app.path().cache_dir()
-        .map_err(|e| format!("Failed to get cache dir: {}", e))?
-        .join("videos");
-    
-    std::fs::create_dir_all(&cache_dir)
-        .map_err(|e| format!("Failed to create cache dir: {}", e))?;
-
-    // Extract filename from URL
-    let filename = url.split('/').last()
-        .ok_or_else(|| "Invalid URL".to_string())?;
-    
-    let cache_path = cache_dir.join(filename);
-    let video_url = format!("{}/{}", server_url, filename);
+    let ext = media_store::url_ext(&url);
+    let video_url = |md5: &str| -> Result<String, String> {
+        let path = media_store::relative_path(md5, &ext).map_err(|e| e.to_string())?;
+        Ok(format!("{}/{}", server_url, path))
+    };
 
-    // Check if already cached
-    if cache_path.exists() {
-        let content_type = if filename.ends_with(".webm") {
-            "video/webm"
-        } else if filename.ends_with(".mp4") {
-            "video/mp4"
-        } else if filename.ends_with(".swf") {
-            "application/x-shockwave-flash"
-        } else {
-            "video/webm"
-        };
-        
+    // Check if already cached (under this URL or, for reposts, another one)
+    let cached = match media_store::lookup_url(&url).map_err(|e| e.to_string())? {
+        Some(stored) => Some(stored),
+        None => match &md5 {
+            Some(md5) => media_store::lookup_md5(md5).map_err(|e| e.to_string())?,
+            None => None,
+        },
+    };
+    if let Some(stored) = cached {
+        media_store::add_ref(&url, None, None, &stored.md5, &ext).map_err(|e| e.to_string())?;
         return Ok(VideoInfo {
-            url: video_url,
-            content_type: content_type.to_string(),
+            url: video_url(&stored.md5)?,
+            content_type: get_content_type(&ext).to_string(),
             cached: true,
         });
     }
 
-    // Download video
-    let client = reqwest::Client::new();
-    let response = client.get(&url)
-        .header("User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .header("Referer", "https://boards.4chan.org/")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to download video: {}", e))?;
-
-    let content_type = response.headers()
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("video/webm")
-        .to_string();
-
-    let bytes = response.bytes()
-        .await
-        .map_err(|e| format!("Failed to read video bytes: {}", e))?;
-
-    // Save to cache
-    let mut file = File::create(&cache_path)
-        .map_err(|e| format!("Failed to create cache file: {}", e))?;
-    
-    file.write_all(&bytes)
-        .map_err(|e| format!("Failed to write video: {}", e))?;
+    // Stream it: the player starts while the file is fetched and tee'd to disk
+    let path = video_stream::open(&url, md5.as_deref(), &ext).map_err(|e| e.to_string())?;
 
     Ok(VideoInfo {
-        url: video_url,
-        content_type,
+        url: format!("{}/{}", server_url, path),
+        content_type: get_content_type(&ext).to_string(),
         cached: false,
     })
 }
 
+fn get_content_type(ext: &str) -> &'static str {
+    match ext {
+        ".webm" => "video/webm",
+        ".mp4" => "video/mp4",
+        ".swf" => "application/x-shockwave-flash",
+        _ => "video/webm",
+    }
+}
+
 /// Get the video server URL
 #[tauri::command]
 pub fn get_video_server_url_cmd() -> Result<String, String> {
@@ -100,26 +78,6 @@ pub fn get_video_server_url_cmd() -> Result<String, String> {
 
 /// Clear video cache
 #[tauri::command]
-pub async fn clear_video_cache(app: AppHandle) -> Result<u64, String> {
-    let cache_dir = app.path().cache_dir()
-        .map_err(|e| format!("Failed to get cache dir: {}", e))?
-        .join("videos");
-    
-    let mut cleared_bytes: u64 = 0;
-    
-    if cache_dir.exists() {
-        // Count bytes before clearing
-        if let Ok(entries) = std::fs::read_dir(&cache_dir) {
-            for entry in entries.flatten() {
-                if let Ok(meta) = entry.metadata() {
-                    cleared_bytes += meta.len();
-                }
-            }
-        }
-        
-        std::fs::remove_dir_all(&cache_dir)
-            .map_err(|e| format!("Failed to clear cache: {}", e))?;
-    }
-    
-    Ok(cleared_bytes)
+pub async fn clear_video_cache() -> Result<u64, String> {
+    media_store::remove_by_ext(VIDEO_EXTENSIONS).map_err(|e| format!("Failed to clear cache: {}", e))
 }
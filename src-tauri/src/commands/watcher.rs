@@ -0,0 +1,45 @@
+/**
+ * Thread Watcher Commands
+ *
+ * Tauri commands for managing the background thread watcher.
+ * Updates are pushed to the frontend via the `thread-watch-update` event.
+ */
+
+use crate::cache;
+use crate::sources::Site;
+use crate::watcher::{self, WatchedThread};
+
+/// Start watching a thread
+#[tauri::command]
+pub async fn watch_thread(
+    site: Site,
+    board: String,
+    thread_id: u64,
+    subject: Option<String>,
+) -> Result<WatchedThread, String> {
+    watcher::add_watch(site, &board, thread_id, subject.as_deref()).map_err(|e| e.to_string())
+}
+
+/// Stop watching a thread
+#[tauri::command]
+pub async fn unwatch_thread(site: Site, board: String, thread_id: u64) -> Result<bool, String> {
+    watcher::remove_watch(site, &board, thread_id).map_err(|e| e.to_string())
+}
+
+/// List all watched threads
+#[tauri::command]
+pub async fn list_watched_threads() -> Result<Vec<WatchedThread>, String> {
+    watcher::list_watches().map_err(|e| e.to_string())
+}
+
+/// Clear unread and (You) counters of a watched thread
+#[tauri::command]
+pub async fn mark_watched_thread_read(site: Site, board: String, thread_id: u64) -> Result<(), String> {
+    watcher::mark_read(site, &board, thread_id).map_err(|e| e.to_string())
+}
+
+/// Remember a post as ours so replies to it trigger notifications
+#[tauri::command]
+pub async fn mark_own_post(site: Site, board: String, thread_id: i64, post_no: i64) -> Result<(), String> {
+    cache::add_own_post(site, &board, thread_id, post_no).map_err(|e| e.to_string())
+}
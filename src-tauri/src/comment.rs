@@ -0,0 +1,298 @@
+/**
+ * Comment Parser
+ *
+ * Turns post comments into a typed tree the frontend can render without
+ * touching HTML, and the backend can walk for quotes.
+ *
+ * Two input formats:
+ * - `Html`: 4chan-style markup (`quotelink` anchors, `<span class="quote">`
+ *   greentext, `<s>` spoilers, `<pre>` code, `deadlink` spans, `<br>`)
+ * - `Text`: plain text with `>` greentext lines and `[spoiler]`/`[code]`
+ *   BBCode, as served by FoolFuuka archives and scraped from 22chan
+ *
+ * In both, bare `>>123` / `>>>/g/123` quotes, `[math]`/`[eqn]` blocks and
+ * URLs inside text are recognized as well.
+ */
+
+use ego_tree::NodeRef;
+use lazy_static::lazy_static;
+use regex::Regex;
+use scraper::{ElementRef, Html, Node};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+use crate::api::Post;
+
+/// How a site's `com` field is encoded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CommentFormat {
+    Html,
+    Text,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextStyle {
+    Bold,
+    Italic,
+    Underline,
+}
+
+/// One node of a parsed comment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CommentNode {
+    Text { text: String },
+    LineBreak,
+    /// `>>123`; `thread` is set when the post is in another thread
+    Quote {
+        no: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        thread: Option<u64>,
+    },
+    /// `>>>/g/123`, or `>>>/g/` for a link to the board itself
+    CrossQuote {
+        board: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        thread: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        no: Option<u64>,
+    },
+    /// Quote of a post that no longer exists
+    DeadLink {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        board: Option<String>,
+        no: u64,
+    },
+    Greentext { children: Vec<CommentNode> },
+    Spoiler { children: Vec<CommentNode> },
+    Styled { style: TextStyle, children: Vec<CommentNode> },
+    Code { text: String },
+    /// `[math]` (inline) or `[eqn]` (display) TeX
+    Math { text: String, display: bool },
+    Url { url: String },
+}
+
+lazy_static! {
+    static ref INLINE: Regex = Regex::new(
+        r"(?s)>>>/([a-z0-9]+)/(\d+)?|>>(\d+)|\[math\](.*?)\[/math\]|\[eqn\](.*?)\[/eqn\]|https?://[^\s<>\[\]]+"
+    )
+    .unwrap();
+    static ref QUOTE_TEXT: Regex = Regex::new(r"^>>(?:(\d+)|>/([a-z0-9]+)/(\d+)?)$").unwrap();
+    static ref HREF_THREAD: Regex = Regex::new(r"/thread/(\d+)").unwrap();
+    static ref QUOTE_START: Regex = Regex::new(r"^>>(\d|>/)").unwrap();
+    static ref BBCODE: Regex = Regex::new(r"(?i)\[(/?)(spoiler|code)\]").unwrap();
+}
+
+/// Parse a comment
+pub fn parse(com: &str, format: CommentFormat) -> Vec<CommentNode> {
+    let html = match format {
+        CommentFormat::Html => Cow::Borrowed(com),
+        CommentFormat::Text => Cow::Owned(text_to_html(com)),
+    };
+
+    let fragment = Html::parse_fragment(&html);
+    let mut nodes = Vec::new();
+    for child in fragment.root_element().children() {
+        walk(child, &mut nodes);
+    }
+    finish(nodes)
+}
+
+/// Fill `com_ast` for every post with a comment
+pub fn annotate(posts: &mut [Post], format: CommentFormat) {
+    for post in posts {
+        post.com_ast = post.com.as_deref().map(|com| parse(com, format));
+    }
+}
+
+/// Every node in the tree, depth first
+pub fn flatten(nodes: &[CommentNode]) -> Vec<&CommentNode> {
+    let mut all = Vec::new();
+    for node in nodes {
+        all.push(node);
+        match node {
+            CommentNode::Greentext { children }
+            | CommentNode::Spoiler { children }
+            | CommentNode::Styled { children, .. } => all.extend(flatten(children)),
+            _ => {}
+        }
+    }
+    all
+}
+
+/// Escape plain text and turn its markup into the HTML `parse` understands
+fn text_to_html(text: &str) -> String {
+    let lines: Vec<String> = text
+        .replace("\r\n", "\n")
+        .split('\n')
+        .map(|line| {
+            let escaped = line.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+            if line.starts_with('>') && !QUOTE_START.is_match(line) {
+                format!("<span class=\"quote\">{}</span>", escaped)
+            } else {
+                escaped
+            }
+        })
+        .collect();
+
+    BBCODE
+        .replace_all(&lines.join("<br>"), |c: &regex::Captures| {
+            let tag = if c[2].eq_ignore_ascii_case("spoiler") { "s" } else { "pre" };
+            format!("<{}{}>", &c[1], tag)
+        })
+        .into_owned()
+}
+
+fn walk(node: NodeRef<Node>, out: &mut Vec<CommentNode>) {
+    match node.value() {
+        Node::Text(text) => push_text(out, text),
+        Node::Element(el) => {
+            let Some(element) = ElementRef::wrap(node) else {
+                return;
+            };
+            let has_class = |class: &str| el.classes().any(|c| c == class);
+
+            match el.name() {
+                "br" => out.push(CommentNode::LineBreak),
+                "wbr" | "script" | "style" => {}
+                "pre" | "code" => out.push(CommentNode::Code { text: code_text(node) }),
+                "a" => link(element, out),
+                "s" | "del" => out.push(CommentNode::Spoiler { children: children(node) }),
+                "span" if has_class("quote") => out.push(CommentNode::Greentext { children: children(node) }),
+                "span" if has_class("spoiler") => out.push(CommentNode::Spoiler { children: children(node) }),
+                "span" if has_class("deadlink") => dead_link(&element.text().collect::<String>(), out),
+                "b" | "strong" => out.push(styled(TextStyle::Bold, node)),
+                "i" | "em" => out.push(styled(TextStyle::Italic, node)),
+                "u" => out.push(styled(TextStyle::Underline, node)),
+                _ => {
+                    for child in node.children() {
+                        walk(child, out);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn children(node: NodeRef<Node>) -> Vec<CommentNode> {
+    let mut out = Vec::new();
+    for child in node.children() {
+        walk(child, &mut out);
+    }
+    finish(out)
+}
+
+fn styled(style: TextStyle, node: NodeRef<Node>) -> CommentNode {
+    CommentNode::Styled { style, children: children(node) }
+}
+
+/// Text of a code block, `<br>` as newlines
+fn code_text(node: NodeRef<Node>) -> String {
+    let mut text = String::new();
+    for descendant in node.descendants() {
+        match descendant.value() {
+            Node::Text(t) => text.push_str(t),
+            Node::Element(el) if el.name() == "br" => text.push('\n'),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Quote links become quotes, other links URLs
+fn link(element: ElementRef, out: &mut Vec<CommentNode>) {
+    let text: String = element.text().collect();
+    let href = element.value().attr("href").unwrap_or("");
+    let thread = HREF_THREAD.captures(href).and_then(|c| c[1].parse().ok());
+
+    if let Some(quote) = parse_quote(text.trim(), thread) {
+        out.push(quote);
+    } else if href.starts_with("http") || href.starts_with("//") {
+        let url = if href.starts_with("//") { format!("https:{}", href) } else { href.to_string() };
+        out.push(CommentNode::Url { url });
+    } else {
+        push_text(out, &text);
+    }
+}
+
+fn dead_link(text: &str, out: &mut Vec<CommentNode>) {
+    match parse_quote(text.trim(), None) {
+        Some(CommentNode::Quote { no, .. }) => out.push(CommentNode::DeadLink { board: None, no }),
+        Some(CommentNode::CrossQuote { board, no: Some(no), .. }) => {
+            out.push(CommentNode::DeadLink { board: Some(board), no })
+        }
+        _ => push_text(out, text),
+    }
+}
+
+/// `>>123`, `>>>/g/123` or `>>>/g/` as a quote node
+fn parse_quote(text: &str, thread: Option<u64>) -> Option<CommentNode> {
+    let c = QUOTE_TEXT.captures(text)?;
+    if let Some(no) = c.get(1) {
+        return Some(CommentNode::Quote { no: no.as_str().parse().ok()?, thread });
+    }
+    Some(CommentNode::CrossQuote {
+        board: c[2].to_string(),
+        thread,
+        no: c.get(3).and_then(|n| n.as_str().parse().ok()),
+    })
+}
+
+/// Append text, merging it into a preceding text node (`<wbr>` splits
+/// long URLs into several)
+fn push_text(out: &mut Vec<CommentNode>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(CommentNode::Text { text: last }) = out.last_mut() {
+        last.push_str(text);
+    } else {
+        out.push(CommentNode::Text { text: text.to_string() });
+    }
+}
+
+/// Recognize inline markup in the text nodes of one level
+fn finish(nodes: Vec<CommentNode>) -> Vec<CommentNode> {
+    let mut out = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        match node {
+            CommentNode::Text { text } => inline(&text, &mut out),
+            node => out.push(node),
+        }
+    }
+    out
+}
+
+fn inline(text: &str, out: &mut Vec<CommentNode>) {
+    let mut last = 0;
+    for c in INLINE.captures_iter(text) {
+        let m = c.get(0).unwrap();
+        push_text(out, &text[last..m.start()]);
+        last = m.end();
+
+        if let Some(board) = c.get(1) {
+            out.push(CommentNode::CrossQuote {
+                board: board.as_str().to_string(),
+                thread: None,
+                no: c.get(2).and_then(|n| n.as_str().parse().ok()),
+            });
+        } else if let Some(no) = c.get(3) {
+            match no.as_str().parse() {
+                Ok(no) => out.push(CommentNode::Quote { no, thread: None }),
+                Err(_) => push_text(out, m.as_str()),
+            }
+        } else if let Some(math) = c.get(4) {
+            out.push(CommentNode::Math { text: math.as_str().to_string(), display: false });
+        } else if let Some(math) = c.get(5) {
+            out.push(CommentNode::Math { text: math.as_str().to_string(), display: true });
+        } else {
+            // Punctuation right after a URL is almost always the sentence's
+            let url = m.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'', '"']);
+            out.push(CommentNode::Url { url: url.to_string() });
+            last = m.start() + url.len();
+        }
+    }
+    push_text(out, &text[last..]);
+}
@@ -0,0 +1,180 @@
+/**
+ * Post Cooldowns
+ *
+ * 4chan enforces separate cooldowns per board for new threads, replies and
+ * replies with an image (`cooldowns` in `boards.json`). We track when each
+ * one ends, per board and post type, in SQLite so they survive restarts.
+ *
+ * A successful post starts the cooldowns it triggers. When the server still
+ * rejects a post as too soon, the wait it reports (or, failing that, the
+ * board's cooldown) replaces ours, since it knows better.
+ */
+
+use anyhow::Result;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use regex::Regex;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::api::BoardCooldowns;
+use crate::cache;
+
+/// 4chan's usual cooldowns, for boards whose record isn't available
+pub const DEFAULT_COOLDOWNS: BoardCooldowns = BoardCooldowns {
+    threads: 600,
+    replies: 60,
+    images: 60,
+};
+
+/// Kind of post, as far as cooldowns are concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PostKind {
+    Thread,
+    Reply,
+    ReplyWithImage,
+}
+
+lazy_static! {
+    // e.g. "You must wait 42 seconds before posting a duplicate reply."
+    static ref REPORTED_WAIT: Regex = Regex::new(r"(\d+)\s*(second|minute)").unwrap();
+}
+
+impl PostKind {
+    pub const ALL: [PostKind; 3] = [Self::Thread, Self::Reply, Self::ReplyWithImage];
+
+    pub fn of(resto: Option<u64>, has_file: bool) -> Self {
+        match (resto.unwrap_or(0), has_file) {
+            (0, _) => Self::Thread,
+            (_, true) => Self::ReplyWithImage,
+            (_, false) => Self::Reply,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Thread => "thread",
+            Self::Reply => "reply",
+            Self::ReplyWithImage => "reply_with_image",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == s)
+    }
+
+    fn cooldown(&self, cooldowns: &BoardCooldowns) -> u32 {
+        match self {
+            Self::Thread => cooldowns.threads,
+            Self::Reply => cooldowns.replies,
+            Self::ReplyWithImage => cooldowns.images.max(cooldowns.replies),
+        }
+    }
+}
+
+/// Extend the cooldown of `kind` on `board` to at least `secs` from now
+fn extend(board: &str, kind: PostKind, secs: u32) -> Result<()> {
+    let until = Utc::now().timestamp() + secs as i64;
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute(
+        r#"INSERT INTO post_cooldowns (board, kind, until) VALUES (?1, ?2, ?3)
+           ON CONFLICT(board, kind) DO UPDATE SET until = MAX(until, excluded.until)"#,
+        params![board, kind.as_str(), until],
+    )?;
+    Ok(())
+}
+
+/// Set the cooldown of `kind` on `board` to end `secs` from now
+fn replace(board: &str, kind: PostKind, secs: u32) -> Result<()> {
+    let until = Utc::now().timestamp() + secs as i64;
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute(
+        "INSERT OR REPLACE INTO post_cooldowns (board, kind, until) VALUES (?1, ?2, ?3)",
+        params![board, kind.as_str(), until],
+    )?;
+    Ok(())
+}
+
+/// Start the cooldowns a successful post of `kind` triggers: any post holds
+/// back the next reply, a thread also the next thread and an image reply the
+/// next image
+pub fn record_post(board: &str, kind: PostKind, cooldowns: &BoardCooldowns) -> Result<()> {
+    let replies = cooldowns.replies;
+    let started = match kind {
+        PostKind::Thread => vec![
+            (PostKind::Thread, cooldowns.threads),
+            (PostKind::Reply, replies),
+            (PostKind::ReplyWithImage, replies),
+        ],
+        PostKind::Reply => vec![(PostKind::Reply, replies), (PostKind::ReplyWithImage, replies)],
+        PostKind::ReplyWithImage => vec![
+            (PostKind::Reply, replies),
+            (PostKind::ReplyWithImage, PostKind::ReplyWithImage.cooldown(cooldowns)),
+        ],
+    };
+    for (kind, secs) in started {
+        extend(board, kind, secs)?;
+    }
+    Ok(())
+}
+
+/// Apply a server rejection. Returns the wait in seconds if `message` was a
+/// cooldown error.
+pub fn record_rejection(board: &str, kind: PostKind, message: &str, cooldowns: &BoardCooldowns) -> Result<Option<u32>> {
+    let lower = message.to_lowercase();
+    let is_cooldown = lower.contains("flood detected")
+        || lower.contains("must wait")
+        || lower.contains("before posting");
+    if !is_cooldown {
+        return Ok(None);
+    }
+
+    let reported = REPORTED_WAIT.captures(&lower).and_then(|c| {
+        let n = c[1].parse::<u32>().ok()?;
+        Some(if &c[2] == "minute" { n.saturating_mul(60) } else { n })
+    });
+    let secs = reported.unwrap_or_else(|| kind.cooldown(cooldowns));
+
+    replace(board, kind, secs)?;
+    Ok(Some(secs))
+}
+
+/// Seconds left before a post of `kind` is allowed on `board`
+pub fn remaining(board: &str, kind: PostKind) -> Result<u64> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let until: Option<i64> = conn
+        .query_row(
+            "SELECT until FROM post_cooldowns WHERE board = ?1 AND kind = ?2",
+            params![board, kind.as_str()],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(until.map_or(0, |until| (until - Utc::now().timestamp()).max(0) as u64))
+}
+
+/// Active cooldowns: board -> post kind -> seconds left
+pub fn all_remaining() -> Result<HashMap<String, HashMap<PostKind, u64>>> {
+    let now = Utc::now().timestamp();
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    // Expired entries are of no further use
+    conn.execute("DELETE FROM post_cooldowns WHERE until <= ?1", params![now])?;
+
+    let mut stmt = conn.prepare("SELECT board, kind, until FROM post_cooldowns")?;
+    let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, i64>(2)?)))?;
+
+    let mut cooldowns: HashMap<String, HashMap<PostKind, u64>> = HashMap::new();
+    for row in rows {
+        let (board, kind, until) = row?;
+        if let Some(kind) = PostKind::parse(&kind) {
+            cooldowns.entry(board).or_default().insert(kind, (until - now) as u64);
+        }
+    }
+    Ok(cooldowns)
+}
@@ -0,0 +1,574 @@
+/**
+ * Download Manager
+ *
+ * Queue for bulk media downloads.
+ *
+ * Features:
+ * - Bounded concurrency (adjustable at runtime)
+ * - Streaming to `.part` files, resumed with HTTP Range requests
+ * - Retries with backoff on network errors
+ * - MD5 verification against the site-provided hash
+ * - Pause / resume / cancel per job
+ * - `download-progress` (per file) and `download-queue` (aggregate) events
+ */
+
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose, Engine as _};
+use md5::{Digest, Md5};
+use reqwest::{header, Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::watch;
+
+use crate::api::Post;
+
+/// Per-file progress event
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download-progress";
+/// Aggregate queue progress event
+pub const DOWNLOAD_QUEUE_EVENT: &str = "download-queue";
+
+const DEFAULT_CONCURRENCY: usize = 4;
+const MAX_ATTEMPTS: u32 = 3;
+/// Emit a progress event every this many bytes
+const PROGRESS_STEP_BYTES: u64 = 256 * 1024;
+
+// Control signals checked between chunks and watched during retry backoff
+const SIGNAL_RUN: u8 = 0;
+const SIGNAL_PAUSE: u8 = 1;
+const SIGNAL_CANCEL: u8 = 2;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadState {
+    Queued,
+    Downloading,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// Which files of a thread to download
+#[derive(Debug, Default, Deserialize, Clone)]
+pub struct MediaFilter {
+    /// Extensions with or without the dot, e.g. `["webm", ".gif"]`
+    pub extensions: Option<Vec<String>>,
+    pub min_size: Option<u64>,
+    pub max_size: Option<u64>,
+    pub min_width: Option<u32>,
+    pub min_height: Option<u32>,
+}
+
+impl MediaFilter {
+    pub fn accepts(&self, post: &Post) -> bool {
+        let Some(ext) = &post.ext else {
+            return false;
+        };
+        if post.filedeleted == Some(1) {
+            return false;
+        }
+
+        if let Some(extensions) = &self.extensions {
+            let ext = ext.trim_start_matches('.');
+            if !extensions.iter().any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(ext)) {
+                return false;
+            }
+        }
+
+        let size = post.fsize.unwrap_or(0);
+        let width = post.w.unwrap_or(0);
+        let height = post.h.unwrap_or(0);
+
+        self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+            && self.min_width.is_none_or(|min| width >= min)
+            && self.min_height.is_none_or(|min| height >= min)
+    }
+}
+
+/// A file to add to the queue
+#[derive(Debug, Clone)]
+pub struct NewDownload {
+    pub board: String,
+    pub thread_id: u64,
+    pub post_no: u64,
+    pub url: String,
+    pub path: PathBuf,
+    /// Base64 MD5 as reported by the site
+    pub md5: Option<String>,
+}
+
+/// Queue entry as reported to the frontend
+#[derive(Debug, Serialize, Clone)]
+pub struct DownloadJob {
+    pub id: u64,
+    pub board: String,
+    pub thread_id: u64,
+    pub post_no: u64,
+    pub url: String,
+    pub path: String,
+    pub md5: Option<String>,
+    pub state: DownloadState,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    /// True once the file matched the expected MD5
+    pub verified: bool,
+    pub error: Option<String>,
+}
+
+/// Payload of `download-queue`
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct QueueProgress {
+    pub queued: usize,
+    pub active: usize,
+    pub paused: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+struct Entry {
+    job: DownloadJob,
+    signal: Arc<watch::Sender<u8>>,
+}
+
+struct Inner {
+    client: Client,
+    entries: Mutex<BTreeMap<u64, Entry>>,
+    next_id: AtomicU64,
+    concurrency: AtomicUsize,
+}
+
+/// Download queue, managed as Tauri state
+pub struct DownloadManager {
+    inner: Arc<Inner>,
+}
+
+impl DownloadManager {
+    pub fn new(client: Client) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                client,
+                entries: Mutex::new(BTreeMap::new()),
+                next_id: AtomicU64::new(1),
+                concurrency: AtomicUsize::new(DEFAULT_CONCURRENCY),
+            }),
+        }
+    }
+
+    /// Queue files, skipping any path that is already queued or running
+    pub fn enqueue(&self, app: &AppHandle, downloads: Vec<NewDownload>) -> Vec<DownloadJob> {
+        let mut added = Vec::new();
+        {
+            let mut entries = self.inner.entries.lock().unwrap();
+            for download in downloads {
+                let path = download.path.to_string_lossy().to_string();
+                let pending = entries.values().any(|e| {
+                    e.job.path == path
+                        && matches!(e.job.state, DownloadState::Queued | DownloadState::Downloading | DownloadState::Paused)
+                });
+                if pending {
+                    continue;
+                }
+
+                let id = self.inner.next_id.fetch_add(1, Ordering::SeqCst);
+                let job = DownloadJob {
+                    id,
+                    board: download.board,
+                    thread_id: download.thread_id,
+                    post_no: download.post_no,
+                    url: download.url,
+                    path,
+                    md5: download.md5,
+                    state: DownloadState::Queued,
+                    downloaded: 0,
+                    total: None,
+                    verified: false,
+                    error: None,
+                };
+                added.push(job.clone());
+                entries.insert(id, Entry { job, signal: Arc::new(watch::Sender::new(SIGNAL_RUN)) });
+            }
+        }
+
+        emit_queue(app, &self.inner);
+        pump(app, &self.inner);
+        added
+    }
+
+    pub fn list(&self) -> Vec<DownloadJob> {
+        let entries = self.inner.entries.lock().unwrap();
+        entries.values().map(|e| e.job.clone()).collect()
+    }
+
+    pub fn pause(&self, app: &AppHandle, id: u64) -> Result<()> {
+        self.inner.update(id, |entry| match entry.job.state {
+            DownloadState::Queued => {
+                entry.job.state = DownloadState::Paused;
+                Ok(())
+            }
+            // The worker notices between chunks (or during a retry backoff)
+            // and marks the job paused
+            DownloadState::Downloading => {
+                entry.signal.send_replace(SIGNAL_PAUSE);
+                Ok(())
+            }
+            state => bail!("Cannot pause a {:?} download", state),
+        })?;
+        emit_job(app, &self.inner, id);
+        Ok(())
+    }
+
+    /// Resume a paused or failed download (keeps the `.part` file)
+    pub fn resume(&self, app: &AppHandle, id: u64) -> Result<()> {
+        self.inner.update(id, |entry| match entry.job.state {
+            DownloadState::Paused | DownloadState::Failed => {
+                entry.job.state = DownloadState::Queued;
+                entry.job.error = None;
+                entry.signal.send_replace(SIGNAL_RUN);
+                Ok(())
+            }
+            // Paused but the worker hasn't stopped yet; let it carry on
+            DownloadState::Downloading if *entry.signal.borrow() == SIGNAL_PAUSE => {
+                entry.signal.send_replace(SIGNAL_RUN);
+                Ok(())
+            }
+            state => bail!("Cannot resume a {:?} download", state),
+        })?;
+        emit_job(app, &self.inner, id);
+        pump(app, &self.inner);
+        Ok(())
+    }
+
+    pub fn cancel(&self, app: &AppHandle, id: u64) -> Result<()> {
+        let part = self.inner.update(id, |entry| match entry.job.state {
+            DownloadState::Queued | DownloadState::Paused | DownloadState::Failed => {
+                entry.job.state = DownloadState::Cancelled;
+                Ok(Some(part_path(Path::new(&entry.job.path))))
+            }
+            DownloadState::Downloading => {
+                entry.signal.send_replace(SIGNAL_CANCEL);
+                Ok(None)
+            }
+            state => bail!("Cannot cancel a {:?} download", state),
+        })?;
+        if let Some(part) = part {
+            std::fs::remove_file(part).ok();
+        }
+        emit_job(app, &self.inner, id);
+        emit_queue(app, &self.inner);
+        Ok(())
+    }
+
+    /// Drop completed and cancelled jobs from the list
+    pub fn clear_finished(&self, app: &AppHandle) {
+        {
+            let mut entries = self.inner.entries.lock().unwrap();
+            entries.retain(|_, e| !matches!(e.job.state, DownloadState::Completed | DownloadState::Cancelled));
+        }
+        emit_queue(app, &self.inner);
+    }
+
+    pub fn set_concurrency(&self, app: &AppHandle, concurrency: usize) {
+        self.inner.concurrency.store(concurrency.max(1), Ordering::SeqCst);
+        pump(app, &self.inner);
+    }
+}
+
+impl Inner {
+    fn update<T>(&self, id: u64, f: impl FnOnce(&mut Entry) -> Result<T>) -> Result<T> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get_mut(&id).ok_or_else(|| anyhow!("Download {} not found", id))?;
+        f(entry)
+    }
+
+    fn set_progress(&self, id: u64, downloaded: u64, total: Option<u64>) {
+        let _ = self.update(id, |entry| {
+            entry.job.downloaded = downloaded;
+            entry.job.total = total;
+            Ok(())
+        });
+    }
+
+    fn finish(&self, id: u64, state: DownloadState, verified: bool, error: Option<String>) {
+        let _ = self.update(id, |entry| {
+            entry.job.state = state;
+            entry.job.verified = verified;
+            entry.job.error = error;
+            Ok(())
+        });
+    }
+
+    /// Mark a job paused after its worker stopped for a pause signal.
+    /// Returns false if it was resumed in the meantime and should go on.
+    fn settle_pause(&self, id: u64) -> bool {
+        self.update(id, |entry| {
+            if *entry.signal.borrow() != SIGNAL_PAUSE {
+                return Ok(false);
+            }
+            entry.signal.send_replace(SIGNAL_RUN);
+            entry.job.state = DownloadState::Paused;
+            entry.job.error = None;
+            Ok(true)
+        })
+        .unwrap_or(true)
+    }
+
+    fn queue_progress(&self) -> QueueProgress {
+        let entries = self.entries.lock().unwrap();
+        let mut progress = QueueProgress::default();
+        for entry in entries.values() {
+            let job = &entry.job;
+            match job.state {
+                DownloadState::Queued => progress.queued += 1,
+                DownloadState::Downloading => progress.active += 1,
+                DownloadState::Paused => progress.paused += 1,
+                DownloadState::Completed => progress.completed += 1,
+                DownloadState::Failed => progress.failed += 1,
+                DownloadState::Cancelled => continue,
+            }
+            progress.bytes_done += job.downloaded;
+            progress.bytes_total += job.total.unwrap_or(job.downloaded);
+        }
+        progress
+    }
+}
+
+fn emit_job(app: &AppHandle, inner: &Inner, id: u64) {
+    let job = {
+        let entries = inner.entries.lock().unwrap();
+        entries.get(&id).map(|e| e.job.clone())
+    };
+    if let Some(job) = job {
+        if let Err(e) = app.emit(DOWNLOAD_PROGRESS_EVENT, job) {
+            eprintln!("Failed to emit download progress: {}", e);
+        }
+    }
+}
+
+fn emit_queue(app: &AppHandle, inner: &Inner) {
+    if let Err(e) = app.emit(DOWNLOAD_QUEUE_EVENT, inner.queue_progress()) {
+        eprintln!("Failed to emit download queue progress: {}", e);
+    }
+}
+
+/// Start queued jobs until the concurrency limit is reached
+fn pump(app: &AppHandle, inner: &Arc<Inner>) {
+    let started: Vec<u64> = {
+        let mut entries = inner.entries.lock().unwrap();
+        let limit = inner.concurrency.load(Ordering::SeqCst);
+        let active = entries.values().filter(|e| e.job.state == DownloadState::Downloading).count();
+
+        entries
+            .values_mut()
+            .filter(|e| e.job.state == DownloadState::Queued)
+            .take(limit.saturating_sub(active))
+            .map(|e| {
+                e.job.state = DownloadState::Downloading;
+                e.job.id
+            })
+            .collect()
+    };
+
+    for id in started {
+        let app = app.clone();
+        let inner = inner.clone();
+        tauri::async_runtime::spawn(async move {
+            run_job(&app, &inner, id).await;
+            emit_job(&app, &inner, id);
+            emit_queue(&app, &inner);
+            pump(&app, &inner);
+        });
+    }
+}
+
+/// How a single transfer attempt ended
+enum Outcome {
+    Finished,
+    Paused,
+    Cancelled,
+}
+
+async fn run_job(app: &AppHandle, inner: &Inner, id: u64) {
+    let Ok((url, path, md5, signal)) = inner.update(id, |e| {
+        Ok((e.job.url.clone(), PathBuf::from(&e.job.path), e.job.md5.clone(), e.signal.clone()))
+    }) else {
+        return;
+    };
+
+    // Already on disk from an earlier run
+    if path.exists() {
+        inner.finish(id, DownloadState::Completed, false, None);
+        return;
+    }
+    if let Some(parent) = path.parent() {
+        if let Err(e) = tokio::fs::create_dir_all(parent).await {
+            inner.finish(id, DownloadState::Failed, false, Some(e.to_string()));
+            return;
+        }
+    }
+
+    emit_job(app, inner, id);
+    let part = part_path(&path);
+    let mut failures = 0;
+    let mut last_error = None;
+    let mut backoff = None;
+
+    loop {
+        if let Some(delay) = backoff.take() {
+            // Pause and cancel take effect without waiting out the delay
+            let mut rx = signal.subscribe();
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = rx.wait_for(|s| *s != SIGNAL_RUN) => {}
+            }
+        }
+
+        let control = *signal.borrow();
+        let outcome = match control {
+            SIGNAL_PAUSE => Ok(Outcome::Paused),
+            SIGNAL_CANCEL => Ok(Outcome::Cancelled),
+            _ => transfer(app, inner, id, &url, &part, &signal).await,
+        };
+
+        let error = match outcome {
+            Ok(Outcome::Paused) => {
+                if inner.settle_pause(id) {
+                    return;
+                }
+                // Resumed before the pause took effect
+                continue;
+            }
+            Ok(Outcome::Cancelled) => {
+                tokio::fs::remove_file(&part).await.ok();
+                inner.finish(id, DownloadState::Cancelled, false, None);
+                return;
+            }
+            Ok(Outcome::Finished) => match &md5 {
+                Some(expected) => match md5_base64(&part).await {
+                    Ok(actual) if &actual == expected => None,
+                    Ok(_) => {
+                        // Corrupt or stale partial data; start over from scratch
+                        tokio::fs::remove_file(&part).await.ok();
+                        Some("MD5 mismatch".to_string())
+                    }
+                    Err(e) => Some(e.to_string()),
+                },
+                None => None,
+            },
+            Err(e) => Some(e.to_string()),
+        };
+
+        if let Some(error) = error {
+            last_error = Some(error);
+            failures += 1;
+            if failures >= MAX_ATTEMPTS {
+                break;
+            }
+            backoff = Some(Duration::from_secs(2u64.pow(failures)));
+            continue;
+        }
+
+        match tokio::fs::rename(&part, &path).await {
+            Ok(()) => inner.finish(id, DownloadState::Completed, md5.is_some(), None),
+            Err(e) => inner.finish(id, DownloadState::Failed, false, Some(e.to_string())),
+        }
+        return;
+    }
+
+    inner.finish(id, DownloadState::Failed, false, last_error);
+}
+
+/// Stream `url` into `part`, continuing from its current length
+async fn transfer(
+    app: &AppHandle,
+    inner: &Inner,
+    id: u64,
+    url: &str,
+    part: &Path,
+    signal: &watch::Sender<u8>,
+) -> Result<Outcome> {
+    let offset = tokio::fs::metadata(part).await.map(|m| m.len()).unwrap_or(0);
+
+    let mut request = inner.client.get(url);
+    if offset > 0 {
+        request = request.header(header::RANGE, format!("bytes={}-", offset));
+    }
+    let mut response = request.send().await?;
+
+    let (mut downloaded, append) = match response.status() {
+        StatusCode::PARTIAL_CONTENT => (offset, true),
+        // The .part file already holds the whole file
+        StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => return Ok(Outcome::Finished),
+        status if status.is_success() => (0, false),
+        status => bail!("HTTP {}", status),
+    };
+    let total = response.content_length().map(|len| len + downloaded);
+    inner.set_progress(id, downloaded, total);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(part)
+        .await?;
+
+    let mut reported = downloaded;
+    while let Some(chunk) = response.chunk().await? {
+        let control = *signal.borrow();
+        match control {
+            SIGNAL_PAUSE => {
+                file.flush().await?;
+                return Ok(Outcome::Paused);
+            }
+            SIGNAL_CANCEL => return Ok(Outcome::Cancelled),
+            _ => {}
+        }
+
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+
+        if downloaded - reported >= PROGRESS_STEP_BYTES {
+            reported = downloaded;
+            inner.set_progress(id, downloaded, total);
+            emit_job(app, inner, id);
+            emit_queue(app, inner);
+        }
+    }
+    file.flush().await?;
+    inner.set_progress(id, downloaded, total.or(Some(downloaded)));
+
+    if total.is_some_and(|t| downloaded < t) {
+        bail!("Connection closed after {} of {} bytes", downloaded, total.unwrap_or(0));
+    }
+
+    Ok(Outcome::Finished)
+}
+
+pub fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// Base64-encoded MD5 of a file, the format 4chan uses for `md5`
+pub async fn md5_base64(path: &Path) -> Result<String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<String> {
+        let mut file = std::fs::File::open(&path)?;
+        let mut hasher = Md5::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok(general_purpose::STANDARD.encode(hasher.finalize()))
+    })
+    .await?
+}
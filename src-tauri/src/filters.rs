@@ -0,0 +1,659 @@
+/**
+ * Filter Rules
+ *
+ * 4chan X style filters, persisted in the cache database and applied to
+ * threads and catalogs before they are returned to the frontend.
+ *
+ * Each rule matches one post field against a regex, optionally scoped to
+ * a set of boards (or every board but some) and to OPs or replies only, and
+ * either hides the post, hides it together with the replies quoting it,
+ * highlights it, or adds the thread to the watcher.
+ */
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use regex::Regex;
+use rusqlite::{params, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::api::{CatalogThread, Post, Thread};
+use crate::cache;
+use crate::catalog_search::strip_html;
+use crate::sources::Site;
+use crate::watcher;
+
+lazy_static::lazy_static! {
+    // Enabled rules with compiled regexes; cleared whenever rules change
+    static ref COMPILED_RULES: Mutex<Option<Vec<CompiledRule>>> = Mutex::new(None);
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterField {
+    Name,
+    Tripcode,
+    PosterId,
+    Country,
+    Subject,
+    Comment,
+    Filename,
+    Md5,
+}
+
+impl FilterField {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::Tripcode => "tripcode",
+            Self::PosterId => "poster_id",
+            Self::Country => "country",
+            Self::Subject => "subject",
+            Self::Comment => "comment",
+            Self::Filename => "filename",
+            Self::Md5 => "md5",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "name" => Some(Self::Name),
+            "tripcode" => Some(Self::Tripcode),
+            "poster_id" => Some(Self::PosterId),
+            "country" => Some(Self::Country),
+            "subject" => Some(Self::Subject),
+            "comment" => Some(Self::Comment),
+            "filename" => Some(Self::Filename),
+            "md5" => Some(Self::Md5),
+            _ => None,
+        }
+    }
+
+    /// Field names used in 4chan X filter exports
+    fn from_4chanx(key: &str) -> Option<Self> {
+        match key {
+            "name" => Some(Self::Name),
+            "tripcode" => Some(Self::Tripcode),
+            "uniqueID" => Some(Self::PosterId),
+            "flag" => Some(Self::Country),
+            "subject" => Some(Self::Subject),
+            "comment" => Some(Self::Comment),
+            "filename" => Some(Self::Filename),
+            "MD5" => Some(Self::Md5),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterAction {
+    Hide,
+    Highlight,
+    HideWithReplies,
+    AutoWatch,
+}
+
+impl FilterAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hide => "hide",
+            Self::Highlight => "highlight",
+            Self::HideWithReplies => "hide_with_replies",
+            Self::AutoWatch => "auto_watch",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "hide" => Some(Self::Hide),
+            "highlight" => Some(Self::Highlight),
+            "hide_with_replies" => Some(Self::HideWithReplies),
+            "auto_watch" => Some(Self::AutoWatch),
+            _ => None,
+        }
+    }
+}
+
+/// Which posts of a thread a rule looks at
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterPosts {
+    #[default]
+    All,
+    OpOnly,
+    RepliesOnly,
+}
+
+impl FilterPosts {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::OpOnly => "op_only",
+            Self::RepliesOnly => "replies_only",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "all" => Some(Self::All),
+            "op_only" => Some(Self::OpOnly),
+            "replies_only" => Some(Self::RepliesOnly),
+            _ => None,
+        }
+    }
+
+    fn includes(&self, is_op: bool) -> bool {
+        match self {
+            Self::All => true,
+            Self::OpOnly => is_op,
+            Self::RepliesOnly => !is_op,
+        }
+    }
+}
+
+/// A stored filter rule
+#[derive(Debug, Serialize, Clone)]
+pub struct FilterRule {
+    pub id: i64,
+    pub field: FilterField,
+    pub pattern: String,
+    /// Boards the rule applies to; empty means every board
+    pub boards: Vec<String>,
+    /// Boards the rule never applies to
+    pub exclude_boards: Vec<String>,
+    pub posts: FilterPosts,
+    pub action: FilterAction,
+    pub enabled: bool,
+    pub created_at: i64,
+}
+
+/// Rule fields accepted from the frontend
+#[derive(Debug, Deserialize, Clone)]
+pub struct FilterRuleInput {
+    pub field: FilterField,
+    pub pattern: String,
+    #[serde(default)]
+    pub boards: Vec<String>,
+    #[serde(default)]
+    pub exclude_boards: Vec<String>,
+    #[serde(default)]
+    pub posts: FilterPosts,
+    pub action: FilterAction,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+struct CompiledRule {
+    field: FilterField,
+    regex: Regex,
+    boards: Vec<String>,
+    exclude_boards: Vec<String>,
+    posts: FilterPosts,
+    action: FilterAction,
+}
+
+impl CompiledRule {
+    fn applies_to(&self, board: &str) -> bool {
+        (self.boards.is_empty() || self.boards.iter().any(|b| b == board))
+            && !self.exclude_boards.iter().any(|b| b == board)
+    }
+}
+
+/// What the matching rules decided for one post
+#[derive(Debug, Default)]
+struct Verdict {
+    hide: bool,
+    hide_replies: bool,
+    highlight: bool,
+    watch: bool,
+}
+
+fn compile(pattern: &str) -> Result<Regex> {
+    Regex::new(pattern).map_err(|e| anyhow!("Invalid filter pattern: {}", e))
+}
+
+fn join_boards(boards: &[String]) -> String {
+    boards
+        .iter()
+        .map(|b| b.trim().trim_matches('/'))
+        .filter(|b| !b.is_empty())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn split_boards(boards: &str) -> Vec<String> {
+    boards.split(',').filter(|b| !b.is_empty()).map(String::from).collect()
+}
+
+const RULE_COLUMNS: &str = "id, field, pattern, boards, exclude_boards, posts, action, enabled, created_at";
+
+fn row_to_rule(row: &Row) -> rusqlite::Result<FilterRule> {
+    let field: String = row.get(1)?;
+    let boards: String = row.get(3)?;
+    let exclude_boards: String = row.get(4)?;
+    let posts: String = row.get(5)?;
+    let action: String = row.get(6)?;
+    Ok(FilterRule {
+        id: row.get(0)?,
+        field: FilterField::parse(&field).unwrap_or(FilterField::Comment),
+        pattern: row.get(2)?,
+        boards: split_boards(&boards),
+        exclude_boards: split_boards(&exclude_boards),
+        posts: FilterPosts::parse(&posts).unwrap_or_default(),
+        action: FilterAction::parse(&action).unwrap_or(FilterAction::Hide),
+        enabled: row.get(7)?,
+        created_at: row.get(8)?,
+    })
+}
+
+fn invalidate() {
+    *COMPILED_RULES.lock().unwrap() = None;
+}
+
+/// All filter rules, oldest first
+pub fn list_rules() -> Result<Vec<FilterRule>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM filter_rules ORDER BY id ASC", RULE_COLUMNS))?;
+    let rules = stmt.query_map([], row_to_rule)?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(rules)
+}
+
+fn get_rule(id: i64) -> Result<FilterRule> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    Ok(conn.query_row(
+        &format!("SELECT {} FROM filter_rules WHERE id = ?1", RULE_COLUMNS),
+        params![id],
+        row_to_rule,
+    )?)
+}
+
+pub fn add_rule(input: &FilterRuleInput) -> Result<FilterRule> {
+    compile(&input.pattern)?;
+
+    let id = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+
+        conn.execute(
+            r#"INSERT INTO filter_rules (field, pattern, boards, exclude_boards, posts, action, enabled, created_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
+            params![
+                input.field.as_str(),
+                input.pattern,
+                join_boards(&input.boards),
+                join_boards(&input.exclude_boards),
+                input.posts.as_str(),
+                input.action.as_str(),
+                input.enabled.unwrap_or(true),
+                Utc::now().timestamp(),
+            ],
+        )?;
+        conn.last_insert_rowid()
+    };
+
+    invalidate();
+    get_rule(id)
+}
+
+pub fn update_rule(id: i64, input: &FilterRuleInput) -> Result<FilterRule> {
+    compile(&input.pattern)?;
+
+    {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+
+        let updated = conn.execute(
+            r#"UPDATE filter_rules SET field = ?2, pattern = ?3, boards = ?4, exclude_boards = ?5, posts = ?6,
+               action = ?7, enabled = ?8 WHERE id = ?1"#,
+            params![
+                id,
+                input.field.as_str(),
+                input.pattern,
+                join_boards(&input.boards),
+                join_boards(&input.exclude_boards),
+                input.posts.as_str(),
+                input.action.as_str(),
+                input.enabled.unwrap_or(true),
+            ],
+        )?;
+        if updated == 0 {
+            bail!("Filter rule {} not found", id);
+        }
+    }
+
+    invalidate();
+    get_rule(id)
+}
+
+/// Delete a rule. Returns whether it existed.
+pub fn delete_rule(id: i64) -> Result<bool> {
+    let removed = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+        conn.execute("DELETE FROM filter_rules WHERE id = ?1", params![id])?
+    };
+
+    invalidate();
+    Ok(removed > 0)
+}
+
+/// Everything that makes two rules the same, for skipping duplicates on import
+#[derive(PartialEq, Eq, Hash)]
+struct RuleIdentity {
+    field: &'static str,
+    pattern: String,
+    boards: String,
+    exclude_boards: String,
+    posts: &'static str,
+    action: &'static str,
+}
+
+/// Import a 4chan X settings export (or just its filter section).
+/// Returns the number of rules added; duplicates, unparseable lines and lines
+/// with options we can't honour are skipped.
+pub fn import_4chanx(data: &str) -> Result<usize> {
+    let json: Value = serde_json::from_str(data).map_err(|e| anyhow!("Invalid 4chan X export: {}", e))?;
+    let conf = json.get("Conf").unwrap_or(&json);
+    let conf = conf.as_object().ok_or_else(|| anyhow!("Invalid 4chan X export: expected an object"))?;
+
+    let mut seen: HashSet<RuleIdentity> = list_rules()?
+        .into_iter()
+        .map(|r| RuleIdentity {
+            field: r.field.as_str(),
+            pattern: r.pattern,
+            boards: join_boards(&r.boards),
+            exclude_boards: join_boards(&r.exclude_boards),
+            posts: r.posts.as_str(),
+            action: r.action.as_str(),
+        })
+        .collect();
+
+    let mut imported = 0;
+    for (key, lines) in conf {
+        let (Some(field), Some(lines)) = (FilterField::from_4chanx(key), lines.as_str()) else {
+            continue;
+        };
+
+        for line in lines.lines() {
+            let input = match parse_4chanx_line(field, line) {
+                Ok(Some(input)) => input,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("Skipping 4chan X filter '{}': {}", line.trim(), e);
+                    continue;
+                }
+            };
+            let identity = RuleIdentity {
+                field: input.field.as_str(),
+                pattern: input.pattern.clone(),
+                boards: join_boards(&input.boards),
+                exclude_boards: join_boards(&input.exclude_boards),
+                posts: input.posts.as_str(),
+                action: input.action.as_str(),
+            };
+            // Also skips lines repeated within this export
+            if seen.contains(&identity) {
+                continue;
+            }
+            if add_rule(&input).is_ok() {
+                seen.insert(identity);
+                imported += 1;
+            }
+        }
+    }
+
+    Ok(imported)
+}
+
+/// Board list of a `boards:` or `exclude:` option. Entries may be
+/// site-qualified, e.g. "4chan.org/g".
+fn parse_4chanx_boards(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|b| b.rsplit('/').next().unwrap_or(b).trim().to_string())
+        .filter(|b| !b.is_empty())
+        .collect()
+}
+
+/// Parse one 4chan X filter line: `/regex/flags;boards:a,b;exclude:c;op:only;highlight`.
+/// Lines without slashes are exact matches (as used for MD5 lists).
+/// Options that would narrow the rule in ways rules can't express are an
+/// error rather than ignored, so an import never matches more than 4chan X did.
+fn parse_4chanx_line(field: FilterField, line: &str) -> Result<Option<FilterRuleInput>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (pattern, options) = if let Some(body) = line.strip_prefix('/') {
+        let Some(end) = body.rfind('/') else {
+            return Ok(None);
+        };
+        let rest = &body[end + 1..];
+        let flags_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        let flags = &rest[..flags_len];
+        let pattern = if flags.contains('i') {
+            format!("(?i){}", &body[..end])
+        } else {
+            body[..end].to_string()
+        };
+        (pattern, &rest[flags_len..])
+    } else {
+        let (value, options) = line.split_once(';').unwrap_or((line, ""));
+        (format!("^{}$", regex::escape(value.trim())), options)
+    };
+
+    let mut boards = Vec::new();
+    let mut exclude_boards = Vec::new();
+    let mut posts = FilterPosts::All;
+    let mut action = FilterAction::Hide;
+    for option in options.split(';').map(str::trim).filter(|o| !o.is_empty()) {
+        let (name, value) = option.split_once(':').unwrap_or((option, ""));
+        match name {
+            "boards" => boards = parse_4chanx_boards(value),
+            "exclude" => exclude_boards = parse_4chanx_boards(value),
+            "op" => {
+                posts = match value {
+                    "yes" => FilterPosts::All,
+                    "only" => FilterPosts::OpOnly,
+                    "no" => FilterPosts::RepliesOnly,
+                    _ => bail!("unknown op value '{}'", value),
+                }
+            }
+            "highlight" => action = FilterAction::Highlight,
+            // Presentation only; the rule matches the same posts without them
+            "top" | "stub" | "sticky" | "notify" | "noti" => {}
+            // Other options restrict which posts match
+            _ => bail!("unsupported option '{}'", name),
+        }
+    }
+
+    Ok(Some(FilterRuleInput {
+        field,
+        pattern,
+        boards,
+        exclude_boards,
+        posts,
+        action,
+        enabled: Some(true),
+    }))
+}
+
+fn compiled_rules() -> Vec<CompiledRule> {
+    let mut compiled = COMPILED_RULES.lock().unwrap();
+    if compiled.is_none() {
+        let rules = match list_rules() {
+            Ok(rules) => rules,
+            Err(e) => {
+                eprintln!("Failed to load filter rules: {}", e);
+                return Vec::new();
+            }
+        };
+        *compiled = Some(
+            rules
+                .into_iter()
+                .filter(|r| r.enabled)
+                .filter_map(|r| {
+                    Some(CompiledRule {
+                        field: r.field,
+                        regex: Regex::new(&r.pattern).ok()?,
+                        boards: r.boards,
+                        exclude_boards: r.exclude_boards,
+                        posts: r.posts,
+                        action: r.action,
+                    })
+                })
+                .collect(),
+        );
+    }
+    compiled.clone().unwrap_or_default()
+}
+
+fn rules_for(board: &str) -> Vec<CompiledRule> {
+    compiled_rules().into_iter().filter(|r| r.applies_to(board)).collect()
+}
+
+fn judge(rules: &[CompiledRule], is_op: bool, value: impl Fn(FilterField) -> Option<String>) -> Verdict {
+    let mut verdict = Verdict::default();
+    for rule in rules.iter().filter(|r| r.posts.includes(is_op)) {
+        let Some(text) = value(rule.field) else {
+            continue;
+        };
+        if !rule.regex.is_match(&text) {
+            continue;
+        }
+        match rule.action {
+            FilterAction::Hide => verdict.hide = true,
+            FilterAction::HideWithReplies => verdict.hide_replies = true,
+            FilterAction::Highlight => verdict.highlight = true,
+            FilterAction::AutoWatch => verdict.watch = true,
+        }
+    }
+    verdict
+}
+
+fn file_name(filename: &Option<String>, ext: &Option<String>) -> Option<String> {
+    filename.as_ref().map(|f| format!("{}{}", f, ext.as_deref().unwrap_or("")))
+}
+
+fn post_field(post: &Post, field: FilterField) -> Option<String> {
+    match field {
+        FilterField::Name => post.name.clone(),
+        FilterField::Tripcode => post.trip.clone(),
+        FilterField::PosterId => post.id.clone(),
+        FilterField::Country => match (&post.country, &post.country_name) {
+            (Some(code), Some(name)) => Some(format!("{} {}", code, name)),
+            (code, name) => code.clone().or_else(|| name.clone()),
+        },
+        FilterField::Subject => post.sub.clone(),
+        FilterField::Comment => post.com.as_deref().map(strip_html),
+        FilterField::Filename => file_name(&post.filename, &post.ext),
+        FilterField::Md5 => post.md5.clone(),
+    }
+}
+
+fn catalog_field(thread: &CatalogThread, field: FilterField) -> Option<String> {
+    match field {
+        FilterField::Name => thread.name.clone(),
+        FilterField::Tripcode => thread.trip.clone(),
+        FilterField::PosterId => thread.id.clone(),
+        FilterField::Country => thread.country.clone(),
+        FilterField::Subject => thread.sub.clone(),
+        FilterField::Comment => thread.com.as_deref().map(strip_html),
+        FilterField::Filename => file_name(&thread.filename, &thread.ext),
+        FilterField::Md5 => thread.md5.clone(),
+    }
+}
+
+/// Record that a rule auto-watched a thread. Returns false if one already did.
+fn claim_auto_watch(site: Site, board: &str, thread_id: u64) -> Result<bool> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO filter_auto_watched (site, board, thread_id, watched_at) VALUES (?1, ?2, ?3, ?4)",
+        params![site.id(), board, thread_id, Utc::now().timestamp()],
+    )?;
+    Ok(inserted > 0)
+}
+
+/// Watch a thread matched by an auto-watch rule, once: if the user unwatches
+/// it, later refreshes leave it alone
+fn auto_watch(site: Site, board: &str, thread_id: u64, subject: Option<&str>) {
+    match claim_auto_watch(site, board, thread_id) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            eprintln!("Failed to auto-watch /{}/{}: {}", board, thread_id, e);
+            return;
+        }
+    }
+    if let Err(e) = watcher::add_watch(site, board, thread_id, subject) {
+        eprintln!("Failed to auto-watch /{}/{}: {}", board, thread_id, e);
+    }
+}
+
+/// Apply filter rules to a thread. The OP is never hidden, only highlighted or watched.
+pub fn apply_to_thread(site: Site, board: &str, thread: &mut Thread) {
+    let rules = rules_for(board);
+    if rules.is_empty() {
+        return;
+    }
+
+    // Posts hidden together with their replies; grows as reply chains are hidden
+    let mut hidden_roots: HashSet<u64> = HashSet::new();
+    let mut posts = Vec::with_capacity(thread.posts.len());
+
+    for (i, mut post) in std::mem::take(&mut thread.posts).into_iter().enumerate() {
+        let verdict = judge(&rules, i == 0, |f| post_field(&post, f));
+
+        if i == 0 {
+            if verdict.watch {
+                auto_watch(site, board, post.no, post.sub.as_deref());
+            }
+            post.highlighted = verdict.highlight;
+            posts.push(post);
+            continue;
+        }
+
+        let replies_to_hidden = post.quoted_posts().iter().any(|q| hidden_roots.contains(q));
+        if verdict.hide_replies || replies_to_hidden {
+            hidden_roots.insert(post.no);
+            continue;
+        }
+        if verdict.hide {
+            continue;
+        }
+
+        post.highlighted = verdict.highlight;
+        posts.push(post);
+    }
+
+    thread.posts = posts;
+}
+
+/// Apply filter rules to catalog threads, matching against each OP
+pub fn apply_to_catalog(site: Site, board: &str, threads: &mut Vec<CatalogThread>) {
+    let rules = rules_for(board);
+    if rules.is_empty() {
+        return;
+    }
+
+    threads.retain_mut(|thread| {
+        let verdict = judge(&rules, true, |f| catalog_field(thread, f));
+        if verdict.watch {
+            auto_watch(site, board, thread.no, thread.sub.as_deref());
+        }
+        thread.highlighted = verdict.highlight;
+        !(verdict.hide || verdict.hide_replies)
+    });
+}
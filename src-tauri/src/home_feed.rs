@@ -0,0 +1,256 @@
+/**
+ * Home Feed
+ *
+ * The popular-threads feed on the home screen, built from the catalogs of
+ * boards the user picks on any site. Threads are ranked by replies, by
+ * velocity (from the board activity sampler, or the thread's lifetime
+ * average when it hasn't been sampled), by images or by age. NSFW boards
+ * can be left out using the sites' board lists.
+ *
+ * The assembled feed is kept in memory for `FeedConfig::cache_secs` so
+ * opening the home screen doesn't refetch every catalog; pages are cut from
+ * that copy. A feed missing a board whose catalog failed to load is served
+ * but not kept.
+ */
+
+use anyhow::Result;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+use crate::board_activity::{self, TrackedBoard};
+use crate::board_lists;
+use crate::cache;
+use crate::sources::{Site, Sources};
+
+const META_KEY: &str = "home_feed";
+
+/// Boards in the feed until the user picks their own
+const DEFAULT_BOARDS: [&str; 8] = ["g", "v", "a", "tv", "sp", "fit", "int", "sci"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedRanking {
+    Replies,
+    Velocity,
+    Images,
+    Newest,
+}
+
+/// What goes into the feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeedConfig {
+    pub boards: Vec<TrackedBoard>,
+    pub ranking: FeedRanking,
+    pub exclude_nsfw: bool,
+    /// Threads taken from each board, so one busy board doesn't fill the feed
+    pub per_board: usize,
+    /// How long a built feed is reused
+    pub cache_secs: i64,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        Self {
+            boards: DEFAULT_BOARDS
+                .iter()
+                .map(|b| TrackedBoard { site: Site::FourChan, board: b.to_string() })
+                .collect(),
+            ranking: FeedRanking::Velocity,
+            exclude_nsfw: true,
+            per_board: 5,
+            cache_secs: 5 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedThread {
+    pub site: Site,
+    pub board: String,
+    pub no: u64,
+    pub title: Option<String>,
+    pub com: Option<String>,
+    pub tim: Option<String>,
+    pub ext: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub replies: u32,
+    pub images: u32,
+    pub time: u64,
+    /// Replies per minute
+    pub velocity: f64,
+}
+
+/// One page of the feed
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedPage {
+    pub threads: Vec<FeedThread>,
+    pub page: usize,
+    pub page_size: usize,
+    pub total: usize,
+    pub ranking: FeedRanking,
+    /// When the feed was built
+    pub fetched_at: i64,
+}
+
+struct CachedFeed {
+    config: FeedConfig,
+    fetched_at: i64,
+    threads: Vec<FeedThread>,
+}
+
+lazy_static! {
+    static ref FEED: Mutex<Option<CachedFeed>> = Mutex::new(None);
+}
+
+pub fn config() -> Result<FeedConfig> {
+    match cache::get_meta(META_KEY)? {
+        Some(json) => Ok(serde_json::from_str(&json)?),
+        None => Ok(FeedConfig::default()),
+    }
+}
+
+/// Save the feed settings; the next request rebuilds the feed
+pub fn set_config(config: &FeedConfig) -> Result<()> {
+    cache::set_meta(META_KEY, &serde_json::to_string(config)?)?;
+    if let Ok(mut feed) = FEED.lock() {
+        *feed = None;
+    }
+    Ok(())
+}
+
+/// A page of the feed, rebuilt when the cached one is stale or `refresh` is
+/// set. `ranking` overrides the configured one.
+pub async fn page(
+    app: &AppHandle,
+    sources: &Sources,
+    ranking: Option<FeedRanking>,
+    page: usize,
+    page_size: usize,
+    refresh: bool,
+) -> Result<FeedPage> {
+    let mut config = config()?;
+    if let Some(ranking) = ranking {
+        config.ranking = ranking;
+    }
+    let now = Utc::now().timestamp();
+
+    let cached = FEED.lock().ok().and_then(|feed| {
+        feed.as_ref()
+            .filter(|f| !refresh && f.config == config && now - f.fetched_at < config.cache_secs)
+            .map(|f| (f.fetched_at, f.threads.clone()))
+    });
+
+    let (fetched_at, threads) = match cached {
+        Some(cached) => cached,
+        None => {
+            let (threads, complete) = build(app, sources, &config).await;
+            if let Ok(mut feed) = FEED.lock() {
+                *feed = complete.then(|| CachedFeed {
+                    config: config.clone(),
+                    fetched_at: now,
+                    threads: threads.clone(),
+                });
+            }
+            (now, threads)
+        }
+    };
+
+    let page_size = page_size.max(1);
+    Ok(FeedPage {
+        total: threads.len(),
+        threads: threads.into_iter().skip(page.saturating_mul(page_size)).take(page_size).collect(),
+        page,
+        page_size,
+        ranking: config.ranking,
+        fetched_at,
+    })
+}
+
+/// The ranked feed, and whether every board's catalog could be loaded
+async fn build(app: &AppHandle, sources: &Sources, config: &FeedConfig) -> (Vec<FeedThread>, bool) {
+    let nsfw = if config.exclude_nsfw { nsfw_boards(app, sources, config).await } else { HashSet::new() };
+    let now = Utc::now().timestamp();
+
+    let mut feed = Vec::new();
+    let mut complete = true;
+    for tracked in &config.boards {
+        let (site, board) = (tracked.site, tracked.board.as_str());
+        if nsfw.contains(&(site, board.to_string())) {
+            continue;
+        }
+
+        let source = sources.get(site);
+        let catalog = match source.catalog(board).await {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                eprintln!("Failed to load {} /{}/ for the home feed: {}", site.id(), board, e);
+                complete = false;
+                continue;
+            }
+        };
+        let sampled = board_activity::velocities(site, board).unwrap_or_default();
+
+        let mut threads: Vec<FeedThread> = catalog
+            .into_iter()
+            .filter(|t| t.sticky != Some(1))
+            .map(|t| FeedThread {
+                velocity: sampled
+                    .get(&t.no)
+                    .copied()
+                    .unwrap_or_else(|| t.replies as f64 * 60.0 / (now - t.time as i64).max(60) as f64),
+                thumbnail_url: t.tim.as_deref().map(|tim| source.thumbnail_url(board, tim)),
+                site,
+                board: board.to_string(),
+                no: t.no,
+                title: t.sub,
+                com: t.com,
+                tim: t.tim,
+                ext: t.ext,
+                replies: t.replies,
+                images: t.images,
+                time: t.time,
+            })
+            .collect();
+
+        rank(&mut threads, config.ranking);
+        threads.truncate(config.per_board);
+        feed.extend(threads);
+    }
+
+    rank(&mut feed, config.ranking);
+    (feed, complete)
+}
+
+fn rank(threads: &mut [FeedThread], ranking: FeedRanking) {
+    match ranking {
+        FeedRanking::Replies => threads.sort_by_key(|t| Reverse(t.replies)),
+        FeedRanking::Velocity => threads.sort_by(|a, b| b.velocity.total_cmp(&a.velocity)),
+        FeedRanking::Images => threads.sort_by_key(|t| Reverse(t.images)),
+        FeedRanking::Newest => threads.sort_by_key(|t| Reverse(t.time)),
+    }
+}
+
+/// NSFW boards among the sites in the feed, from their board lists
+async fn nsfw_boards(app: &AppHandle, sources: &Sources, config: &FeedConfig) -> HashSet<(Site, String)> {
+    let sites: HashSet<Site> = config.boards.iter().map(|b| b.site).collect();
+    let mut nsfw = HashSet::new();
+    for site in sites {
+        let boards: HashMap<String, bool> = board_lists::boards(app, sources, site)
+            .await
+            .into_iter()
+            .map(|b| (b.id, b.nsfw))
+            .collect();
+        for tracked in config.boards.iter().filter(|b| b.site == site) {
+            if boards.get(&tracked.board).copied().unwrap_or(false) {
+                nsfw.insert((site, tracked.board.clone()));
+            }
+        }
+    }
+    nsfw
+}
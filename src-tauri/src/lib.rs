@@ -1,17 +1,44 @@
 mod api;
+mod archive;
 mod commands;
+mod comment;
+mod cooldowns;
 mod boards_metadata;
+mod board_activity;
+mod board_capabilities;
+mod board_lists;
+mod catalog_search;
+mod downloads;
+mod filters;
+mod home_feed;
 mod video_server;
+mod video_stream;
 mod cache;
+mod media_cache;
+mod media_protocol;
+mod media_store;
+mod post_queue;
+mod reply_graph;
+mod sources;
+mod thread_stats;
+mod thumbnails;
+mod upload;
+mod watcher;
 
 use api::ChanClient;
 use commands::*;
+use downloads::DownloadManager;
+use sources::Sources;
 use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Initialize API client
     let client = ChanClient::new().expect("Failed to create API client");
+    let sources = Sources::new(client.clone()).expect("Failed to create imageboard sources");
+    let downloads = DownloadManager::new(
+        sources::browser_client().expect("Failed to create download client"),
+    );
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -19,6 +46,16 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
         .manage(client)
+        .manage(sources)
+        .manage(downloads)
+        // Thumbnails, images and videos load via chanmedia:// URLs
+        .register_asynchronous_uri_scheme_protocol(media_protocol::SCHEME, |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let sources = app.state::<Sources>();
+                responder.respond(media_protocol::handle(&sources, request).await);
+            });
+        })
         .setup(|app| {
             // Initialize cache database
             if let Err(e) = cache::init_cache_db() {
@@ -36,47 +73,83 @@ pub fn run() {
                 }
             }
 
-            // Start video server on app setup
-            let cache_dir = app.path().cache_dir()
-                .expect("Failed to get cache dir")
-                .join("videos");
-            
-            std::fs::create_dir_all(&cache_dir).ok();
-            
-            match video_server::start_video_server(cache_dir) {
+            // Start video server on app setup; it serves the media store
+            match video_server::start_video_server(media_store::media_root()) {
                 Ok(port) => println!("Video server started on port {}", port),
                 Err(e) => eprintln!("Failed to start video server: {}", e),
             }
+
+            // Keep the media store within its byte budget
+            media_cache::start();
+
+            // Poll watched threads in the background
+            watcher::start(app.handle().clone());
+
+            // Keep site board lists current
+            board_lists::start(app.handle().clone());
+
+            // Post queued drafts as their cooldowns expire
+            post_queue::start(app.handle().clone());
+
+            // Sample board catalogs for activity trends
+            board_activity::start(app.handle().clone());
             
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             fetch_all_boards_with_metadata,
+            fetch_boards,
             search_boards,
+            get_board_capabilities,
+            list_board_capabilities,
+            refresh_board_list,
             fetch_popular_threads,
+            get_home_feed_config,
+            set_home_feed_config,
             fetch_catalog,
+            search_catalog,
             fetch_thread,
+            refresh_thread,
+            get_reply_graph,
+            get_reply_chain,
+            get_conversation,
+            get_thread_stats,
+            get_activity_boards,
+            set_activity_boards,
+            sample_board_activity,
+            get_board_activity,
+            get_fastest_threads,
+            get_new_thread_rate,
             download_media,
+            get_media_url,
             get_cached_media_path,
+            get_local_media_info,
+            get_thumbnail_size,
+            set_thumbnail_size,
+            archive_thread,
+            enqueue_thread_media,
+            list_downloads,
+            pause_download,
+            resume_download,
+            cancel_download,
+            clear_finished_downloads,
+            set_download_concurrency,
             clear_media_cache,
             get_cache_size,
             fetch_image_with_cors_bypass,
             clear_image_cache,
             submit_post,
             get_post_cooldown,
+            get_post_cooldowns,
+            queue_post,
+            list_queued_posts,
+            update_queued_post,
+            cancel_queued_post,
+            validate_upload,
+            get_upload_limits,
+            get_own_posts,
             validate_pass_token,
             fetch_captcha,
-            fetch_sevenchan_catalog,
-            fetch_sevenchan_thread,
-            fetch_fourplebs_boards,
-            fetch_fourplebs_catalog,
-            fetch_fourplebs_thread,
-            fetch_archivedmoe_boards,
-            fetch_archivedmoe_catalog,
-            fetch_archivedmoe_thread,
-            fetch_twentytwochan_boards,
-            fetch_twentytwochan_catalog,
-            fetch_twentytwochan_thread,
             proxy_video,
             clear_video_cache,
             get_thread_cache_stats,
@@ -85,6 +158,24 @@ pub fn run() {
             cache_thread,
             cleanup_thread_cache,
             clear_thread_cache,
+            find_reposts,
+            get_storage_report,
+            get_media_cache_limits,
+            set_media_cache_limits,
+            pin_thread_media,
+            unpin_thread_media,
+            list_pinned_threads,
+            search_cached_posts,
+            watch_thread,
+            unwatch_thread,
+            list_watched_threads,
+            mark_watched_thread_read,
+            mark_own_post,
+            list_filter_rules,
+            add_filter_rule,
+            update_filter_rule,
+            delete_filter_rule,
+            import_4chanx_filters,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,433 @@
+/**
+ * Media Cache Manager
+ *
+ * Keeps the media store within a byte budget. Every stored file has a row in
+ * `media_files` (kind, size, last access); when usage exceeds a per-kind quota
+ * or the global budget, the least recently used files are evicted.
+ *
+ * Media of pinned threads is never evicted. Watched threads (including ones
+ * that have since been archived) are pinned implicitly; other threads can be
+ * pinned explicitly, e.g. when they are archived to disk.
+ */
+
+use anyhow::Result;
+use chrono::Utc;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::cache;
+use crate::media_store;
+
+const LIMITS_KEY: &str = "media_cache_limits";
+
+/// How often limits are enforced in the background
+const ENFORCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+const MIB: u64 = 1024 * 1024;
+
+/// Files whose refs belong to a pinned or watched thread
+const PINNED: &str = r#"EXISTS (
+    SELECT 1 FROM media_refs r
+    WHERE r.md5 = f.md5 AND r.ext = f.ext AND (
+        EXISTS (SELECT 1 FROM pinned_threads p WHERE p.board = r.board AND p.thread_id = r.thread_id)
+        OR EXISTS (SELECT 1 FROM watched_threads w WHERE w.board = r.board AND w.thread_id = r.thread_id)
+    )
+)"#;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Thumbnail,
+    Image,
+    Video,
+    Other,
+}
+
+impl MediaKind {
+    pub const ALL: [MediaKind; 4] = [Self::Thumbnail, Self::Image, Self::Video, Self::Other];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Thumbnail => "thumbnail",
+            Self::Image => "image",
+            Self::Video => "video",
+            Self::Other => "other",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "thumbnail" => Self::Thumbnail,
+            "image" => Self::Image,
+            "video" => Self::Video,
+            _ => Self::Other,
+        }
+    }
+
+    /// Classify by URL: `{tim}s.jpg` (or anything under `/thumb/`) is a thumbnail
+    pub fn from_url(url: &str) -> Self {
+        let ext = media_store::url_ext(url);
+        let name = url.rsplit('/').next().unwrap_or(url);
+        let stem = name.strip_suffix(ext.as_str()).unwrap_or(name);
+        let tim = stem.strip_suffix('s').unwrap_or("");
+
+        match ext.as_str() {
+            ".jpg" if url.contains("/thumb/") || (!tim.is_empty() && tim.bytes().all(|b| b.is_ascii_digit())) => {
+                Self::Thumbnail
+            }
+            ".jpg" | ".jpeg" | ".png" | ".gif" | ".webp" => Self::Image,
+            ".webm" | ".mp4" => Self::Video,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Byte budget for the media store
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheLimits {
+    /// Budget across all kinds
+    pub max_bytes: u64,
+    /// Optional quotas per kind
+    pub thumbnail_bytes: Option<u64>,
+    pub image_bytes: Option<u64>,
+    pub video_bytes: Option<u64>,
+    pub other_bytes: Option<u64>,
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 2048 * MIB,
+            thumbnail_bytes: Some(256 * MIB),
+            image_bytes: None,
+            video_bytes: Some(1024 * MIB),
+            other_bytes: None,
+        }
+    }
+}
+
+impl CacheLimits {
+    pub fn quota(&self, kind: MediaKind) -> Option<u64> {
+        match kind {
+            MediaKind::Thumbnail => self.thumbnail_bytes,
+            MediaKind::Image => self.image_bytes,
+            MediaKind::Video => self.video_bytes,
+            MediaKind::Other => self.other_bytes,
+        }
+    }
+}
+
+pub fn get_limits() -> Result<CacheLimits> {
+    Ok(match cache::get_meta(LIMITS_KEY)? {
+        Some(json) => serde_json::from_str(&json).unwrap_or_default(),
+        None => CacheLimits::default(),
+    })
+}
+
+pub fn set_limits(limits: &CacheLimits) -> Result<()> {
+    cache::set_meta(LIMITS_KEY, &serde_json::to_string(limits)?)?;
+    Ok(())
+}
+
+/// Never evict media of this thread
+pub fn pin_thread(board: &str, thread_id: u64, reason: &str) -> Result<()> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute(
+        "INSERT OR REPLACE INTO pinned_threads (board, thread_id, reason, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![board, thread_id as i64, reason, Utc::now().timestamp()],
+    )?;
+    Ok(())
+}
+
+pub fn unpin_thread(board: &str, thread_id: u64) -> Result<bool> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let removed = conn.execute(
+        "DELETE FROM pinned_threads WHERE board = ?1 AND thread_id = ?2",
+        params![board, thread_id as i64],
+    )?;
+    Ok(removed > 0)
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PinnedThread {
+    pub board: String,
+    pub thread_id: u64,
+    pub reason: String,
+    pub created_at: i64,
+}
+
+pub fn list_pins() -> Result<Vec<PinnedThread>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT board, thread_id, reason, created_at FROM pinned_threads ORDER BY created_at DESC",
+    )?;
+    let pins = stmt
+        .query_map([], |r| {
+            Ok(PinnedThread {
+                board: r.get(0)?,
+                thread_id: r.get::<_, i64>(1)? as u64,
+                reason: r.get(2)?,
+                created_at: r.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(pins)
+}
+
+/// Result of an eviction pass
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct Eviction {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+struct Candidate {
+    md5: String,
+    ext: String,
+    kind: MediaKind,
+    size: u64,
+}
+
+/// Evict least recently used, unpinned files until every quota and the
+/// global budget are met
+pub fn enforce_limits() -> Result<Eviction> {
+    let limits = get_limits()?;
+
+    let (victims, eviction) = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+
+        let mut usage: HashMap<MediaKind, u64> = HashMap::new();
+        let mut stmt = conn.prepare("SELECT kind, SUM(size) FROM media_files GROUP BY kind")?;
+        for row in stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))? {
+            let (kind, bytes) = row?;
+            *usage.entry(MediaKind::parse(&kind)).or_default() += bytes as u64;
+        }
+        let mut total: u64 = usage.values().sum();
+
+        let over_quota = |usage: &HashMap<MediaKind, u64>, kind: MediaKind| {
+            limits.quota(kind).is_some_and(|q| usage.get(&kind).copied().unwrap_or(0) > q)
+        };
+        if total <= limits.max_bytes && !MediaKind::ALL.iter().any(|&k| over_quota(&usage, k)) {
+            return Ok(Eviction::default());
+        }
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT md5, ext, kind, size FROM media_files f WHERE NOT {} ORDER BY last_access ASC",
+            PINNED
+        ))?;
+        let candidates = stmt
+            .query_map([], |r| {
+                Ok(Candidate {
+                    md5: r.get(0)?,
+                    ext: r.get(1)?,
+                    kind: MediaKind::parse(&r.get::<_, String>(2)?),
+                    size: r.get::<_, i64>(3)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        // Oldest first: a file goes if its kind is over quota or we're over budget
+        let mut victims = Vec::new();
+        let mut eviction = Eviction::default();
+        for candidate in candidates {
+            if total <= limits.max_bytes && !over_quota(&usage, candidate.kind) {
+                continue;
+            }
+            total -= candidate.size.min(total);
+            if let Some(used) = usage.get_mut(&candidate.kind) {
+                *used -= candidate.size.min(*used);
+            }
+            eviction.files += 1;
+            eviction.bytes += candidate.size;
+
+            conn.execute(
+                "DELETE FROM media_files WHERE md5 = ?1 AND ext = ?2",
+                params![candidate.md5, candidate.ext],
+            )?;
+            conn.execute(
+                "DELETE FROM media_refs WHERE md5 = ?1 AND ext = ?2",
+                params![candidate.md5, candidate.ext],
+            )?;
+            victims.push(candidate);
+        }
+        (victims, eviction)
+    };
+
+    for victim in victims {
+        if let Ok(path) = media_store::path_for(&victim.md5, &victim.ext) {
+            std::fs::remove_file(path).ok();
+        }
+    }
+
+    Ok(eviction)
+}
+
+/// Bring `media_files` in line with the disk: track files stored before it
+/// existed and forget files that were removed behind our back
+pub fn sync_with_disk() -> Result<()> {
+    let (untracked, tracked) = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+
+        // (md5, ext, any url) of referenced files not tracked yet
+        let mut stmt = conn.prepare(
+            r#"SELECT r.md5, r.ext, MIN(r.url) FROM media_refs r
+               WHERE NOT EXISTS (SELECT 1 FROM media_files f WHERE f.md5 = r.md5 AND f.ext = r.ext)
+               GROUP BY r.md5, r.ext"#,
+        )?;
+        let untracked: Vec<(String, String, String)> = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        let mut stmt = conn.prepare("SELECT md5, ext FROM media_files")?;
+        let tracked: Vec<(String, String)> = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        (untracked, tracked)
+    };
+
+    let size_of = |md5: &str, ext: &str| {
+        media_store::path_for(md5, ext)
+            .ok()
+            .and_then(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len())
+    };
+    let missing: Vec<_> = tracked.into_iter().filter(|(md5, ext)| size_of(md5, ext).is_none()).collect();
+
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let now = Utc::now().timestamp();
+
+    for (md5, ext, url) in untracked {
+        match size_of(&md5, &ext) {
+            Some(size) => {
+                conn.execute(
+                    r#"INSERT OR IGNORE INTO media_files (md5, ext, kind, size, created_at, last_access)
+                       VALUES (?1, ?2, ?3, ?4, ?5, ?5)"#,
+                    params![md5, ext, MediaKind::from_url(&url).as_str(), size as i64, now],
+                )?;
+            }
+            None => {
+                conn.execute("DELETE FROM media_refs WHERE md5 = ?1 AND ext = ?2", params![md5, ext])?;
+            }
+        }
+    }
+    for (md5, ext) in missing {
+        conn.execute("DELETE FROM media_files WHERE md5 = ?1 AND ext = ?2", params![md5, ext])?;
+        conn.execute("DELETE FROM media_refs WHERE md5 = ?1 AND ext = ?2", params![md5, ext])?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct KindUsage {
+    pub kind: MediaKind,
+    pub files: u64,
+    pub bytes: u64,
+    pub quota: Option<u64>,
+}
+
+/// Usage per board; files seen on several boards count towards each
+#[derive(Debug, Serialize, Clone)]
+pub struct BoardUsage {
+    /// `None` for media loaded without thread context
+    pub board: Option<String>,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StorageReport {
+    pub media_bytes: u64,
+    pub media_files: u64,
+    pub pinned_bytes: u64,
+    pub limits: CacheLimits,
+    pub by_kind: Vec<KindUsage>,
+    pub by_board: Vec<BoardUsage>,
+    /// Size of the thread cache database file
+    pub thread_db_bytes: u64,
+}
+
+pub fn storage_report() -> Result<StorageReport> {
+    let limits = get_limits()?;
+    let thread_db_bytes = cache::get_cache_stats()?.db_size_bytes;
+
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    let mut by_kind: HashMap<MediaKind, (u64, u64)> = HashMap::new();
+    let mut stmt = conn.prepare("SELECT kind, COUNT(*), SUM(size) FROM media_files GROUP BY kind")?;
+    for row in stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?, r.get::<_, i64>(2)?)))? {
+        let (kind, files, bytes) = row?;
+        let entry = by_kind.entry(MediaKind::parse(&kind)).or_default();
+        entry.0 += files as u64;
+        entry.1 += bytes as u64;
+    }
+    let by_kind: Vec<KindUsage> = MediaKind::ALL
+        .iter()
+        .map(|&kind| {
+            let (files, bytes) = by_kind.get(&kind).copied().unwrap_or_default();
+            KindUsage { kind, files, bytes, quota: limits.quota(kind) }
+        })
+        .collect();
+
+    let mut stmt = conn.prepare(
+        r#"SELECT board, COUNT(*), SUM(size) FROM (
+               SELECT DISTINCT r.board, f.md5, f.ext, f.size
+               FROM media_files f JOIN media_refs r ON r.md5 = f.md5 AND r.ext = f.ext
+           )
+           GROUP BY board
+           ORDER BY SUM(size) DESC"#,
+    )?;
+    let by_board = stmt
+        .query_map([], |r| {
+            Ok(BoardUsage {
+                board: r.get(0)?,
+                files: r.get::<_, i64>(1)? as u64,
+                bytes: r.get::<_, i64>(2)? as u64,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let pinned_bytes: i64 = conn.query_row(
+        &format!("SELECT COALESCE(SUM(size), 0) FROM media_files f WHERE {}", PINNED),
+        [],
+        |r| r.get(0),
+    )?;
+
+    Ok(StorageReport {
+        media_bytes: by_kind.iter().map(|k| k.bytes).sum(),
+        media_files: by_kind.iter().map(|k| k.files).sum(),
+        pinned_bytes: pinned_bytes as u64,
+        limits,
+        by_kind,
+        by_board,
+        thread_db_bytes,
+    })
+}
+
+/// Sync with disk once, then enforce limits periodically
+pub fn start() {
+    tauri::async_runtime::spawn(async {
+        if let Err(e) = tauri::async_runtime::spawn_blocking(sync_with_disk).await.unwrap_or(Ok(())) {
+            eprintln!("Media cache sync error: {}", e);
+        }
+        loop {
+            match tauri::async_runtime::spawn_blocking(enforce_limits).await {
+                Ok(Ok(eviction)) if eviction.files > 0 => {
+                    println!("Media cache: evicted {} files ({} bytes)", eviction.files, eviction.bytes);
+                }
+                Ok(Err(e)) => eprintln!("Media cache eviction error: {}", e),
+                _ => {}
+            }
+            tokio::time::sleep(ENFORCE_INTERVAL).await;
+        }
+    });
+}
@@ -0,0 +1,250 @@
+/**
+ * Media Protocol
+ *
+ * `chanmedia://` URI scheme serving thumbnails, images and videos straight
+ * from the media store, so the webview loads them like any other URL instead
+ * of receiving base64 data URLs over IPC.
+ *
+ *   chanmedia://localhost/{percent-encoded upstream url}?md5=..&board=..&thread=..
+ *
+ * (`http://chanmedia.localhost/...` on Windows; the frontend builds these
+ * with `convertFileSrc`.) All query parameters are optional. Cache misses are
+ * fetched upstream, verified against `md5` when given and stored; `board` and
+ * `thread` tie the file to a thread so pinned threads keep their media.
+ * `thumb` (a size in pixels, or empty for the configured size) serves a
+ * generated JPEG thumbnail of an image instead of the image itself.
+ * Videos are redirected to the video server, which serves stored ones from
+ * disk without buffering them and streams the rest while they download.
+ * Only URLs on a known source's media hosts are served; anything else gets 403.
+ *
+ *   chanmedia://localhost/store/{hex[0..2]}/{hex}{ext}
+ *
+ * serves a file already in the store, e.g. the thumbnail of a local file.
+ */
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use percent_encoding::percent_decode_str;
+use reqwest::header::REFERER;
+use reqwest::Client;
+use std::io::SeekFrom;
+use std::time::Duration;
+use tauri::http::{header, Request, Response, StatusCode};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::media_store::{self, StoredMedia};
+use crate::sources::{self, Sources};
+use crate::thumbnails;
+use crate::video_server::{self, parse_range, RangeRequest};
+use crate::video_stream;
+
+pub const SCHEME: &str = "chanmedia";
+
+const VIDEO_EXTENSIONS: &[&str] = &[".webm", ".mp4"];
+
+lazy_static! {
+    static ref CLIENT: Client = Client::builder()
+        .user_agent(sources::BROWSER_USER_AGENT)
+        .connect_timeout(Duration::from_secs(15))
+        .timeout(Duration::from_secs(120))
+        .build()
+        .expect("Failed to create media client");
+}
+
+/// Thread a media request belongs to
+pub type ThreadRef<'a> = Option<(&'a str, u64)>;
+
+/// Stored copy of `url`, downloading and storing it on a miss
+pub async fn resolve(url: &str, md5: Option<&str>, thread: ThreadRef<'_>) -> Result<StoredMedia> {
+    let ext = media_store::url_ext(url);
+
+    // Cached under this URL or, for reposts, another one
+    let cached = match media_store::lookup_url(url)? {
+        Some(stored) => Some(stored),
+        None => match md5 {
+            Some(md5) => media_store::lookup_md5(md5)?,
+            None => None,
+        },
+    };
+    if let Some(stored) = cached {
+        media_store::add_ref(url, thread.map(|t| t.0), thread.map(|t| t.1), &stored.md5, &ext)?;
+        return Ok(stored);
+    }
+
+    let bytes = CLIENT
+        .get(url)
+        .header(REFERER, "https://boards.4chan.org/")
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    let stored = media_store::store(&bytes, md5, &ext)?;
+    media_store::add_ref(url, thread.map(|t| t.0), thread.map(|t| t.1), &stored.md5, &ext)?;
+    Ok(stored)
+}
+
+/// Handle one `chanmedia://` request
+pub async fn handle(sources: &Sources, request: Request<Vec<u8>>) -> Response<Vec<u8>> {
+    match serve(sources, &request).await {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("chanmedia: {}: {}", request.uri(), e);
+            plain(StatusCode::BAD_GATEWAY, e.to_string())
+        }
+    }
+}
+
+async fn serve(sources: &Sources, request: &Request<Vec<u8>>) -> Result<Response<Vec<u8>>> {
+    let uri = request.uri();
+    let url = percent_decode_str(uri.path().trim_start_matches('/'))
+        .decode_utf8()
+        .map_err(|_| anyhow!("Invalid media URL"))?
+        .to_string();
+    if let Some(stored) = stored_file(&url) {
+        let ext = media_store::url_ext(&url);
+        return serve_file(request, &stored, &ext).await;
+    }
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Ok(plain(StatusCode::BAD_REQUEST, format!("Not a media URL: {}", url)));
+    }
+    if !sources.is_media_url(&url) {
+        return Ok(plain(StatusCode::FORBIDDEN, format!("Not a known media host: {}", url)));
+    }
+    let param = |name: &str| {
+        uri.query()?
+            .split('&')
+            .find_map(|p| p.strip_prefix(name)?.strip_prefix('='))
+            .and_then(|v| percent_decode_str(v).decode_utf8().ok())
+            .map(|v| v.to_string())
+    };
+    let md5 = param("md5");
+    let board = param("board");
+    let thread_id = param("thread").and_then(|t| t.parse::<u64>().ok());
+    let thread = board.as_deref().zip(thread_id);
+
+    let ext = media_store::url_ext(&url);
+    if let Some(size) = param("thumb") {
+        let size = size.parse().map(thumbnails::clamp_size).unwrap_or_else(|_| thumbnails::thumbnail_size());
+        let thumb = thumbnails::thumbnail_for_url(&url, md5.as_deref(), thread, size).await?;
+        let stored = StoredMedia { md5: thumb.md5, path: thumb.path };
+        return serve_file(request, &stored, ".jpg").await;
+    }
+
+    let stored = if VIDEO_EXTENSIONS.contains(&ext.as_str()) {
+        // Responses here are built in memory, so videos always go through the
+        // video server: stored ones as files, the rest streamed
+        let cached = match media_store::lookup_url(&url)? {
+            Some(stored) => Some(stored),
+            None => match &md5 {
+                Some(md5) => media_store::lookup_md5(md5)?,
+                None => None,
+            },
+        };
+        match cached {
+            Some(stored) => {
+                media_store::add_ref(&url, thread.map(|t| t.0), thread.map(|t| t.1), &stored.md5, &ext)?;
+                return redirect_to_video_server(&media_store::relative_path(&stored.md5, &ext)?);
+            }
+            None => return redirect_to_video_server(&video_stream::open(&url, md5.as_deref(), &ext)?),
+        }
+    } else {
+        resolve(&url, md5.as_deref(), thread).await?
+    };
+
+    serve_file(request, &stored, &ext).await
+}
+
+/// URL serving a file already in the store, by its path relative to the media root
+pub fn stored_url(relative_path: &str) -> String {
+    // Tauri serves custom schemes from http://{scheme}.localhost on Windows
+    if cfg!(windows) {
+        format!("http://{}.localhost/{}", SCHEME, relative_path)
+    } else {
+        format!("{}://localhost/{}", SCHEME, relative_path)
+    }
+}
+
+/// Stored file named by a `store/{hex[0..2]}/{hex}{ext}` path
+fn stored_file(path: &str) -> Option<StoredMedia> {
+    let mut parts = path.strip_prefix("store/")?.split('/');
+    let (prefix, name, None) = (parts.next()?, parts.next()?, parts.next()) else {
+        return None;
+    };
+    let (hex, ext) = name.split_at(name.find('.')?);
+    let valid = hex.len() == 32
+        && hex.bytes().all(|b| b.is_ascii_hexdigit())
+        && hex.starts_with(prefix)
+        && prefix.len() == 2
+        && ext[1..].bytes().all(|b| b.is_ascii_alphanumeric());
+    let path = media_store::media_root().join(path);
+    (valid && path.exists()).then(|| StoredMedia { md5: hex.to_string(), path })
+}
+
+fn redirect_to_video_server(path: &str) -> Result<Response<Vec<u8>>> {
+    let server = video_server::get_video_server_url().ok_or_else(|| anyhow!("Video server not running"))?;
+
+    Ok(Response::builder()
+        .status(StatusCode::TEMPORARY_REDIRECT)
+        .header(header::LOCATION, format!("{}/{}", server, path))
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Vec::new())?)
+}
+
+/// Respond with a stored file, honouring a single byte range
+async fn serve_file(request: &Request<Vec<u8>>, stored: &StoredMedia, ext: &str) -> Result<Response<Vec<u8>>> {
+    let mut file = tokio::fs::File::open(&stored.path).await?;
+    let size = file.metadata().await?.len();
+    let mime = mime_guess::from_ext(ext.trim_start_matches('.')).first_or_octet_stream();
+
+    // Stored files are content-addressed, so they never change
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, mime.as_ref())
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, format!("\"{}\"", stored.md5))
+        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+
+    let range = request.headers().get(header::RANGE).and_then(|v| v.to_str().ok());
+    let response = match parse_range(range, size) {
+        RangeRequest::Unsatisfiable => builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+            .body(Vec::new())?,
+        RangeRequest::Partial(ranges) if ranges.len() == 1 => {
+            let (start, end) = ranges[0];
+            let len = end - start + 1;
+            let mut bytes = vec![0; len as usize];
+            file.seek(SeekFrom::Start(start)).await?;
+            file.read_exact(&mut bytes).await?;
+
+            builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, size))
+                .header(header::CONTENT_LENGTH, len)
+                .body(bytes)?
+        }
+        // Whole file (multi-range requests are allowed to get it too)
+        _ => {
+            let mut bytes = Vec::with_capacity(size as usize);
+            file.read_to_end(&mut bytes).await?;
+
+            builder
+                .status(StatusCode::OK)
+                .header(header::CONTENT_LENGTH, size)
+                .body(bytes)?
+        }
+    };
+
+    Ok(response)
+}
+
+fn plain(status: StatusCode, message: String) -> Response<Vec<u8>> {
+    let mut response = Response::new(message.into_bytes());
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, header::HeaderValue::from_static("text/plain"));
+    response
+}
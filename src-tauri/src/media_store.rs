@@ -0,0 +1,283 @@
+/**
+ * Media Store
+ *
+ * Content-addressed storage for downloaded media.
+ *
+ * Files are verified against the site's base64 MD5 and stored once under
+ * `media/store/{hex[0..2]}/{hex}{ext}`, no matter how many posts, boards or
+ * URLs carry them. `media_refs` maps source URLs to stored files.
+ */
+
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use md5::{Digest, Md5};
+use rusqlite::{params, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::cache;
+use crate::media_cache::MediaKind;
+
+/// Suffix for temp files, unique within this process
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// Root of the media cache (also served by the video server)
+pub fn media_root() -> PathBuf {
+    let dir = dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("chandesk")
+        .join("media");
+
+    std::fs::create_dir_all(&dir).ok();
+    dir
+}
+
+/// Base64-encoded MD5, the format 4chan uses for `md5`
+pub fn md5_base64(bytes: &[u8]) -> String {
+    md5_from_digest(&Md5::digest(bytes))
+}
+
+/// Base64-encode a finished MD5 digest (for incremental hashing)
+pub fn md5_from_digest(digest: &[u8]) -> String {
+    general_purpose::STANDARD.encode(digest)
+}
+
+fn md5_hex(md5_base64: &str) -> Result<String> {
+    let digest = general_purpose::STANDARD
+        .decode(md5_base64)
+        .map_err(|_| anyhow!("Invalid MD5 '{}'", md5_base64))?;
+    if digest.len() != 16 {
+        bail!("Invalid MD5 '{}'", md5_base64);
+    }
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Store path relative to `media_root()`
+pub fn relative_path(md5_base64: &str, ext: &str) -> Result<String> {
+    let hex = md5_hex(md5_base64)?;
+    Ok(format!("store/{}/{}{}", &hex[..2], hex, ext))
+}
+
+pub fn path_for(md5_base64: &str, ext: &str) -> Result<PathBuf> {
+    Ok(media_root().join(relative_path(md5_base64, ext)?))
+}
+
+/// Check `bytes` against the expected MD5 (if any) and return their MD5
+pub fn verify(bytes: &[u8], expected_md5: Option<&str>) -> Result<String> {
+    let actual = md5_base64(bytes);
+    match expected_md5 {
+        Some(expected) if expected != actual => {
+            bail!("MD5 mismatch: expected {}, got {}", expected, actual)
+        }
+        _ => Ok(actual),
+    }
+}
+
+/// A file in the store
+#[derive(Debug, Clone)]
+pub struct StoredMedia {
+    pub md5: String,
+    pub path: PathBuf,
+}
+
+/// Verify and store `bytes`, writing only if the content is new
+pub fn store(bytes: &[u8], expected_md5: Option<&str>, ext: &str) -> Result<StoredMedia> {
+    let md5 = verify(bytes, expected_md5)?;
+    let path = path_for(&md5, ext)?;
+
+    if path.exists() {
+        return Ok(StoredMedia { md5, path });
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Write via a temp file so readers never see a partial file. The name is
+    // unique per write: the same file may be stored by two requests at once.
+    let tmp = path.with_extension(format!(
+        "{}-{}.part",
+        std::process::id(),
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    if let Err(e) = std::fs::write(&tmp, bytes).and_then(|_| std::fs::rename(&tmp, &path)) {
+        std::fs::remove_file(&tmp).ok();
+        return Err(e.into());
+    }
+
+    Ok(StoredMedia { md5, path })
+}
+
+/// Move an already verified file into the store (e.g. a finished stream).
+/// `tmp` must be on the same filesystem as the media root.
+pub fn adopt(tmp: &Path, md5: &str, ext: &str) -> Result<StoredMedia> {
+    let path = path_for(md5, ext)?;
+
+    if path.exists() {
+        std::fs::remove_file(tmp).ok();
+    } else {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(tmp, &path)?;
+    }
+
+    Ok(StoredMedia { md5: md5.to_string(), path })
+}
+
+/// Remember which stored file a URL resolved to. Also marks the file as
+/// used for the media cache's LRU eviction.
+pub fn add_ref(url: &str, board: Option<&str>, thread_id: Option<u64>, md5: &str, ext: &str) -> Result<()> {
+    let size = std::fs::metadata(path_for(md5, ext)?).map(|m| m.len()).unwrap_or(0);
+    let kind = MediaKind::from_url(url);
+
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let now = Utc::now().timestamp();
+
+    let filename = url.rsplit('/').next().unwrap_or(url);
+    conn.execute(
+        r#"INSERT OR REPLACE INTO media_refs (url, board, thread_id, filename, md5, ext, created_at)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+        params![url, board, thread_id.map(|t| t as i64), filename, md5, ext, now],
+    )?;
+    touch(conn, md5, ext, kind, size, now)?;
+
+    Ok(())
+}
+
+/// Track a stored file that has no source URL (e.g. a generated thumbnail)
+pub fn track(md5: &str, ext: &str, kind: MediaKind) -> Result<()> {
+    let size = std::fs::metadata(path_for(md5, ext)?).map(|m| m.len()).unwrap_or(0);
+
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    touch(conn, md5, ext, kind, size, Utc::now().timestamp())?;
+    Ok(())
+}
+
+/// Record a file in `media_files` and mark it used now
+fn touch(conn: &rusqlite::Connection, md5: &str, ext: &str, kind: MediaKind, size: u64, now: i64) -> Result<()> {
+    conn.execute(
+        r#"INSERT INTO media_files (md5, ext, kind, size, created_at, last_access)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+           ON CONFLICT(md5, ext) DO UPDATE SET size = excluded.size, last_access = excluded.last_access"#,
+        params![md5, ext, kind.as_str(), size as i64, now],
+    )?;
+    Ok(())
+}
+
+fn existing(row: Option<(String, String)>) -> Result<Option<StoredMedia>> {
+    match row {
+        Some((md5, ext)) => {
+            let path = path_for(&md5, &ext)?;
+            Ok(path.exists().then_some(StoredMedia { md5, path }))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Stored file previously downloaded from `url`
+pub fn lookup_url(url: &str) -> Result<Option<StoredMedia>> {
+    let row = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+        conn.query_row(
+            "SELECT md5, ext FROM media_refs WHERE url = ?1",
+            params![url],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?
+    };
+    existing(row)
+}
+
+/// Stored file downloaded for a thread under `filename` (last URL segment)
+pub fn lookup_file(board: &str, thread_id: u64, filename: &str) -> Result<Option<StoredMedia>> {
+    let row = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+        conn.query_row(
+            "SELECT md5, ext FROM media_refs WHERE board = ?1 AND thread_id = ?2 AND filename = ?3",
+            params![board, thread_id as i64, filename],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?
+    };
+    existing(row)
+}
+
+/// Stored file with this MD5, whatever URL it came from
+pub fn lookup_md5(md5: &str) -> Result<Option<StoredMedia>> {
+    let row = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+        conn.query_row(
+            "SELECT md5, ext FROM media_refs WHERE md5 = ?1 LIMIT 1",
+            params![md5],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .optional()?
+    };
+    existing(row)
+}
+
+/// File extension (with dot) of the last URL segment
+pub fn url_ext(url: &str) -> String {
+    let name = url.rsplit('/').next().unwrap_or(url);
+    let name = name.split(['?', '#']).next().unwrap_or(name);
+    name.rfind('.').map(|i| name[i..].to_lowercase()).unwrap_or_default()
+}
+
+/// Delete stored files with one of the given extensions, and their refs.
+/// Returns the number of bytes freed.
+pub fn remove_by_ext(exts: &[&str]) -> Result<u64> {
+    let files: Vec<(String, String)> = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+
+        // Generated thumbnails are tracked in media_files without any ref
+        let placeholders = vec!["?"; exts.len()].join(", ");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT md5, ext FROM media_refs WHERE ext IN ({0}) UNION SELECT md5, ext FROM media_files WHERE ext IN ({0})",
+            placeholders
+        ))?;
+        let files = stmt
+            .query_map(rusqlite::params_from_iter(exts.iter().chain(exts)), |r| Ok((r.get(0)?, r.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        conn.execute(
+            &format!("DELETE FROM media_refs WHERE ext IN ({})", placeholders),
+            rusqlite::params_from_iter(exts),
+        )?;
+        conn.execute(
+            &format!("DELETE FROM media_files WHERE ext IN ({})", placeholders),
+            rusqlite::params_from_iter(exts),
+        )?;
+        files
+    };
+
+    let mut freed = 0;
+    for (md5, ext) in files {
+        let Ok(path) = path_for(&md5, &ext) else {
+            continue;
+        };
+        if let Ok(meta) = std::fs::metadata(&path) {
+            if std::fs::remove_file(&path).is_ok() {
+                freed += meta.len();
+            }
+        }
+    }
+
+    Ok(freed)
+}
+
+/// Forget all refs (after the media directory was wiped)
+pub fn clear_refs() -> Result<()> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute("DELETE FROM media_refs", [])?;
+    conn.execute("DELETE FROM media_files", [])?;
+    Ok(())
+}
@@ -0,0 +1,387 @@
+/**
+ * Post Queue
+ *
+ * Drafts saved in SQLite and posted in the background once the board's
+ * cooldown for that kind of post has passed.
+ *
+ * Features:
+ * - Drafts keep the whole request (file path, sanitize options, captcha)
+ *   and survive restarts
+ * - Network errors and 5xx responses are retried with backoff
+ * - A server cooldown rejection re-queues the draft for when it ends
+ * - Anything else (bad captcha, ban, rejected file) fails the draft so it
+ *   can be edited and re-queued
+ * - `post-queue` events on every change, `queued-post-sent` and
+ *   `queued-post-failed` when a draft is done
+ *
+ * Captchas expire after a couple of minutes, so drafts that wait out a long
+ * cooldown usually need a 4chan Pass or a fresh captcha. Pass tokens are only
+ * kept in memory; drafts restored after a restart are posted without one.
+ */
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension, Row};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::api::ChanClient;
+use crate::cache;
+use crate::commands::{send_post, PostRequest, PostResponse};
+use crate::cooldowns::{self, PostKind};
+
+/// Event emitted whenever a draft changes
+pub const POST_QUEUE_EVENT: &str = "post-queue";
+/// Event emitted when a draft was posted
+pub const POST_SENT_EVENT: &str = "queued-post-sent";
+/// Event emitted when a draft failed for good
+pub const POST_FAILED_EVENT: &str = "queued-post-failed";
+
+/// How often the scheduler looks for due drafts
+const TICK_INTERVAL: Duration = Duration::from_secs(5);
+/// Attempts before a draft that keeps hitting network errors is failed
+const MAX_ATTEMPTS: u32 = 5;
+/// Delay before the first retry, doubled for every further one
+const RETRY_BASE_SECS: i64 = 15;
+
+lazy_static! {
+    /// 4chan Pass token per draft id, never written to the cache database
+    static ref PASS_TOKENS: Mutex<HashMap<i64, String>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueStatus {
+    Queued,
+    Posting,
+    Sent,
+    Failed,
+}
+
+impl QueueStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Posting => "posting",
+            Self::Sent => "sent",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "posting" => Self::Posting,
+            "sent" => Self::Sent,
+            "failed" => Self::Failed,
+            _ => Self::Queued,
+        }
+    }
+}
+
+/// A draft and its posting state
+#[derive(Debug, Serialize, Clone)]
+pub struct QueuedPost {
+    pub id: i64,
+    pub request: PostRequest,
+    pub status: QueueStatus,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    pub last_error: Option<String>,
+    pub thread_id: Option<u64>,
+    pub post_id: Option<u64>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const QUEUE_COLUMNS: &str = "id, request, status, attempts, next_attempt_at, last_error, \
+    thread_id, post_id, created_at, updated_at";
+
+fn row_to_post(row: &Row) -> rusqlite::Result<QueuedPost> {
+    let request: String = row.get(1)?;
+    let status: String = row.get(2)?;
+    Ok(QueuedPost {
+        id: row.get(0)?,
+        request: serde_json::from_str(&request)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e)))?,
+        status: QueueStatus::parse(&status),
+        attempts: row.get(3)?,
+        next_attempt_at: row.get(4)?,
+        last_error: row.get(5)?,
+        thread_id: row.get::<_, Option<i64>>(6)?.map(|n| n as u64),
+        post_id: row.get::<_, Option<i64>>(7)?.map(|n| n as u64),
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+fn get(id: i64) -> Result<Option<QueuedPost>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let post = conn
+        .query_row(
+            &format!("SELECT {} FROM post_queue WHERE id = ?1", QUEUE_COLUMNS),
+            params![id],
+            row_to_post,
+        )
+        .optional()?;
+    Ok(post)
+}
+
+fn save(post: &QueuedPost) -> Result<()> {
+    let request = serde_json::to_string(&post.request)?;
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute(
+        r#"UPDATE post_queue SET
+               request = ?2, status = ?3, attempts = ?4, next_attempt_at = ?5,
+               last_error = ?6, thread_id = ?7, post_id = ?8, updated_at = ?9
+           WHERE id = ?1"#,
+        params![
+            post.id,
+            request,
+            post.status.as_str(),
+            post.attempts,
+            post.next_attempt_at,
+            post.last_error,
+            post.thread_id.map(|n| n as i64),
+            post.post_id.map(|n| n as i64),
+            post.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Save a draft; it is posted on the next tick its cooldown allows
+pub fn enqueue(request: PostRequest, pass_token: Option<String>) -> Result<QueuedPost> {
+    let json = serde_json::to_string(&request)?;
+    let now = Utc::now().timestamp();
+    let id = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+        conn.execute(
+            r#"INSERT INTO post_queue (request, status, next_attempt_at, created_at, updated_at)
+               VALUES (?1, 'queued', ?2, ?2, ?2)"#,
+            params![json, now],
+        )?;
+        conn.last_insert_rowid()
+    };
+    if let Some(token) = pass_token {
+        PASS_TOKENS.lock().unwrap().insert(id, token);
+    }
+
+    match get(id)? {
+        Some(post) => Ok(post),
+        None => bail!("Queued post {} disappeared", id),
+    }
+}
+
+/// All drafts, oldest first
+pub fn list() -> Result<Vec<QueuedPost>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM post_queue ORDER BY id ASC", QUEUE_COLUMNS))?;
+    let posts = stmt.query_map([], row_to_post)?.filter_map(|r| r.ok()).collect();
+    Ok(posts)
+}
+
+/// Replace the request of a waiting or failed draft and queue it again.
+/// A `None` pass token keeps the stored one.
+pub fn update(id: i64, request: PostRequest, pass_token: Option<String>) -> Result<QueuedPost> {
+    let Some(mut post) = get(id)? else {
+        bail!("No queued post with id {}", id);
+    };
+    match post.status {
+        QueueStatus::Posting => bail!("Post {} is being sent", id),
+        QueueStatus::Sent => bail!("Post {} was already sent", id),
+        QueueStatus::Queued | QueueStatus::Failed => {}
+    }
+
+    let now = Utc::now().timestamp();
+    post.request = request;
+    post.status = QueueStatus::Queued;
+    post.attempts = 0;
+    post.next_attempt_at = now;
+    post.last_error = None;
+    post.updated_at = now;
+    save(&post)?;
+    if let Some(token) = pass_token {
+        PASS_TOKENS.lock().unwrap().insert(id, token);
+    }
+    Ok(post)
+}
+
+/// Delete a draft, or dismiss a sent or failed one. Returns whether it existed.
+pub fn cancel(id: i64) -> Result<bool> {
+    if let Some(post) = get(id)? {
+        if post.status == QueueStatus::Posting {
+            bail!("Post {} is being sent", id);
+        }
+    }
+
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let removed = conn.execute("DELETE FROM post_queue WHERE id = ?1", params![id])?;
+    PASS_TOKENS.lock().unwrap().remove(&id);
+    Ok(removed > 0)
+}
+
+/// Start the background posting task
+pub fn start(app: AppHandle) {
+    // A post cut off by a restart may or may not have gone through;
+    // let the user decide rather than risk posting it twice
+    if let Err(e) = fail_interrupted() {
+        eprintln!("Failed to recover post queue: {}", e);
+    }
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = post_due(&app).await {
+                eprintln!("Post queue error: {}", e);
+            }
+            tokio::time::sleep(TICK_INTERVAL).await;
+        }
+    });
+}
+
+fn fail_interrupted() -> Result<()> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute(
+        r#"UPDATE post_queue SET status = 'failed', last_error = 'Interrupted while posting', updated_at = ?1
+           WHERE status = 'posting'"#,
+        params![Utc::now().timestamp()],
+    )?;
+    Ok(())
+}
+
+fn due_ids(now: i64) -> Result<Vec<i64>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let mut stmt = conn.prepare(
+        "SELECT id FROM post_queue WHERE status = 'queued' AND next_attempt_at <= ?1 ORDER BY id ASC",
+    )?;
+    let ids = stmt.query_map(params![now], |r| r.get(0))?.filter_map(|r| r.ok()).collect();
+    Ok(ids)
+}
+
+/// Mark a draft as being sent, reading it fresh under the same lock so edits
+/// made while earlier drafts were posting are honoured. `None` if it was
+/// cancelled or is no longer due.
+fn claim(id: i64) -> Result<Option<QueuedPost>> {
+    let now = Utc::now().timestamp();
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let Some(mut post) = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM post_queue WHERE id = ?1 AND status = 'queued' AND next_attempt_at <= ?2",
+                QUEUE_COLUMNS
+            ),
+            params![id, now],
+            row_to_post,
+        )
+        .optional()?
+    else {
+        return Ok(None);
+    };
+
+    conn.execute(
+        "UPDATE post_queue SET status = 'posting', attempts = attempts + 1, updated_at = ?2 WHERE id = ?1",
+        params![id, now],
+    )?;
+    post.status = QueueStatus::Posting;
+    post.attempts += 1;
+    post.updated_at = now;
+    Ok(Some(post))
+}
+
+async fn post_due(app: &AppHandle) -> Result<()> {
+    let client = app.state::<ChanClient>();
+
+    for id in due_ids(Utc::now().timestamp())? {
+        // Sending an earlier draft can take a while; this one may have been
+        // edited or cancelled since the list was read
+        let Some(mut post) = claim(id)? else {
+            continue;
+        };
+        let board = post.request.board.clone();
+        let kind = PostKind::of(post.request.resto, post.request.file_path.is_some());
+
+        // Earlier drafts on the same board may have started a cooldown
+        let wait = cooldowns::remaining(&board, kind)?;
+        if wait > 0 {
+            post.status = QueueStatus::Queued;
+            post.attempts -= 1;
+            post.next_attempt_at = Utc::now().timestamp() + wait as i64;
+            save(&post)?;
+            continue;
+        }
+        emit(app, POST_QUEUE_EVENT, &post);
+
+        let pass_token = PASS_TOKENS.lock().unwrap().get(&post.id).cloned();
+        let result = send_post(&post.request, pass_token.as_deref(), &client).await;
+        let post = apply_result(post, result, cooldowns::remaining(&board, kind).unwrap_or(0));
+        save(&post)?;
+        if post.status == QueueStatus::Sent {
+            PASS_TOKENS.lock().unwrap().remove(&post.id);
+        }
+
+        emit(app, POST_QUEUE_EVENT, &post);
+        match post.status {
+            QueueStatus::Sent => emit(app, POST_SENT_EVENT, &post),
+            QueueStatus::Failed => emit(app, POST_FAILED_EVENT, &post),
+            QueueStatus::Queued | QueueStatus::Posting => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Next state of a draft after a posting attempt. `cooldown` is the wait the
+/// attempt left behind, if the server rejected it as too soon.
+fn apply_result(mut post: QueuedPost, result: Result<PostResponse, String>, cooldown: u64) -> QueuedPost {
+    let now = Utc::now().timestamp();
+    post.updated_at = now;
+
+    let error = match result {
+        Ok(response) if response.success => {
+            post.status = QueueStatus::Sent;
+            post.thread_id = response.thread_id;
+            post.post_id = response.post_id;
+            post.last_error = None;
+            return post;
+        }
+        Ok(response) => response.error.unwrap_or_else(|| "Post failed".to_string()),
+        Err(e) => e,
+    };
+
+    if cooldown > 0 {
+        // Too soon is not the draft's fault; try again once the wait is over
+        post.status = QueueStatus::Queued;
+        post.attempts -= 1;
+        post.next_attempt_at = now + cooldown as i64;
+    } else if is_transient(&error) && post.attempts < MAX_ATTEMPTS {
+        post.status = QueueStatus::Queued;
+        post.next_attempt_at = now + RETRY_BASE_SECS * 2i64.pow(post.attempts - 1);
+    } else {
+        post.status = QueueStatus::Failed;
+    }
+    post.last_error = Some(error);
+    post
+}
+
+/// Errors worth retrying unchanged: the request never arrived or the server
+/// had trouble
+fn is_transient(error: &str) -> bool {
+    error.starts_with("Network error") || error.starts_with("Post failed: HTTP 5")
+}
+
+fn emit(app: &AppHandle, event: &str, post: &QueuedPost) {
+    if let Err(e) = app.emit(event, post) {
+        eprintln!("Failed to emit {}: {}", event, e);
+    }
+}
@@ -0,0 +1,193 @@
+/**
+ * Reply Graph
+ *
+ * Quote links between the posts of a thread, built from the parsed comments
+ * (`comment::parse`).
+ *
+ * For every post: the posts it quotes, the posts quoting it, quotes of
+ * other threads or boards, and quotes of posts that are not in the thread
+ * (deleted or never there). On top of that, reply chains (everything a post
+ * answers and everything answering it) and the posts connecting two posts.
+ */
+
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+use crate::api::Post;
+use crate::comment::{self, CommentFormat, CommentNode};
+
+/// A quote leading out of the thread
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ExternalQuote {
+    /// Set for cross-board quotes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub board: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread: Option<u64>,
+    /// Unset for links to a whole board (`>>>/g/`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no: Option<u64>,
+}
+
+/// Quote links of one post
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PostLinks {
+    pub replies_to: Vec<u64>,
+    pub replied_by: Vec<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cross_thread: Vec<ExternalQuote>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cross_board: Vec<ExternalQuote>,
+    /// Quoted posts that are not in the thread
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dead_links: Vec<u64>,
+}
+
+/// Quote links of every post in a thread, by post number
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplyGraph {
+    pub thread_id: u64,
+    pub posts: BTreeMap<u64, PostLinks>,
+}
+
+impl ReplyGraph {
+    /// Build the graph of a thread. Posts without `com_ast` are parsed as
+    /// `format`.
+    pub fn build(posts: &[Post], format: CommentFormat) -> Self {
+        let thread_id = posts.first().map_or(0, |op| op.no);
+        let present: HashSet<u64> = posts.iter().map(|p| p.no).collect();
+        let mut graph = ReplyGraph {
+            thread_id,
+            posts: posts.iter().map(|p| (p.no, PostLinks::default())).collect(),
+        };
+
+        for post in posts {
+            let parsed;
+            let nodes = match (&post.com_ast, &post.com) {
+                (Some(ast), _) => ast,
+                (None, Some(com)) => {
+                    parsed = comment::parse(com, format);
+                    &parsed
+                }
+                (None, None) => continue,
+            };
+
+            let mut links = PostLinks::default();
+            for node in comment::flatten(nodes) {
+                match node {
+                    CommentNode::Quote { no, thread } if thread.is_none() || *thread == Some(thread_id) => {
+                        if present.contains(no) {
+                            push_unique(&mut links.replies_to, *no);
+                        } else {
+                            push_unique(&mut links.dead_links, *no);
+                        }
+                    }
+                    CommentNode::Quote { no, thread } => {
+                        let quote = ExternalQuote { board: None, thread: *thread, no: Some(*no) };
+                        if !links.cross_thread.contains(&quote) {
+                            links.cross_thread.push(quote);
+                        }
+                    }
+                    CommentNode::CrossQuote { board, thread, no } => {
+                        let quote = ExternalQuote { board: Some(board.clone()), thread: *thread, no: *no };
+                        if !links.cross_board.contains(&quote) {
+                            links.cross_board.push(quote);
+                        }
+                    }
+                    CommentNode::DeadLink { board: None, no } => push_unique(&mut links.dead_links, *no),
+                    CommentNode::DeadLink { board: Some(board), no } => {
+                        let quote = ExternalQuote { board: Some(board.clone()), thread: None, no: Some(*no) };
+                        if !links.cross_board.contains(&quote) {
+                            links.cross_board.push(quote);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if let Some(entry) = graph.posts.get_mut(&post.no) {
+                entry.replies_to = links.replies_to;
+                entry.cross_thread = links.cross_thread;
+                entry.cross_board = links.cross_board;
+                entry.dead_links = links.dead_links;
+            }
+        }
+
+        // Backlinks, in post order since `posts` is a BTreeMap
+        let edges: Vec<(u64, u64)> = graph
+            .posts
+            .iter()
+            .flat_map(|(&from, links)| links.replies_to.iter().map(move |&to| (from, to)))
+            .collect();
+        for (from, to) in edges {
+            if let Some(target) = graph.posts.get_mut(&to) {
+                push_unique(&mut target.replied_by, from);
+            }
+        }
+
+        graph
+    }
+
+    /// Posts `no` quotes, directly or through the posts it quotes, and `no` itself
+    pub fn ancestors(&self, no: u64) -> BTreeSet<u64> {
+        self.reach(no, |links| &links.replies_to)
+    }
+
+    /// Posts replying to `no`, directly or through other replies, and `no` itself
+    pub fn descendants(&self, no: u64) -> BTreeSet<u64> {
+        self.reach(no, |links| &links.replied_by)
+    }
+
+    /// Everything `no` answers and everything answering it
+    pub fn reply_chain(&self, no: u64) -> Result<BTreeSet<u64>> {
+        if !self.posts.contains_key(&no) {
+            bail!("Post {} is not in thread {}", no, self.thread_id);
+        }
+        let mut chain = self.ancestors(no);
+        chain.extend(self.descendants(no));
+        Ok(chain)
+    }
+
+    /// Posts on the quote paths between `a` and `b`, in either direction
+    pub fn conversation(&self, a: u64, b: u64) -> Result<BTreeSet<u64>> {
+        for no in [a, b] {
+            if !self.posts.contains_key(&no) {
+                bail!("Post {} is not in thread {}", no, self.thread_id);
+            }
+        }
+
+        let forward: BTreeSet<u64> = self.descendants(a).intersection(&self.ancestors(b)).copied().collect();
+        let backward: BTreeSet<u64> = self.descendants(b).intersection(&self.ancestors(a)).copied().collect();
+        if forward.is_empty() && backward.is_empty() {
+            bail!("Posts {} and {} are not connected by replies", a, b);
+        }
+        Ok(forward.union(&backward).copied().collect())
+    }
+
+    fn reach(&self, start: u64, next: impl Fn(&PostLinks) -> &Vec<u64>) -> BTreeSet<u64> {
+        let mut seen = BTreeSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(no) = queue.pop_front() {
+            if let Some(links) = self.posts.get(&no) {
+                for &other in next(links) {
+                    if seen.insert(other) {
+                        queue.push_back(other);
+                    }
+                }
+            }
+        }
+        seen
+    }
+}
+
+fn push_unique(list: &mut Vec<u64>, no: u64) {
+    if !list.contains(&no) {
+        list.push(no);
+    }
+}
+
+/// The posts of `posts` whose numbers are in `selected`, in thread order
+pub fn select(posts: Vec<Post>, selected: &BTreeSet<u64>) -> Vec<Post> {
+    posts.into_iter().filter(|p| selected.contains(&p.no)).collect()
+}
@@ -0,0 +1,129 @@
+/**
+ * Archived.moe Source
+ *
+ * Archived.moe is a 4chan archive using FoolFuuka software (same as 4plebs).
+ */
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+
+use super::{boards_from_table, foolfuuka, ImageboardSource, SiteBoard};
+use crate::api::{CatalogThread, Thread};
+
+const ARCHIVEDMOE_BASE: &str = "https://archived.moe";
+const ARCHIVEDMOE_API_BASE: &str = "https://archived.moe/_/api/chan";
+
+/// Archived boards on archived.moe
+const ARCHIVEDMOE_BOARDS: &[(&str, &str, bool)] = &[
+    ("a", "Anime & Manga", false),
+    ("c", "Anime/Cute", false),
+    ("g", "Technology", false),
+    ("k", "Weapons", false),
+    ("m", "Mecha", false),
+    ("o", "Auto", false),
+    ("n", "Transportation", false),
+    ("p", "Photography", false),
+    ("v", "Video Games", false),
+    ("vg", "Video Game Generals", false),
+    ("vm", "Video Games/Multiplayer", false),
+    ("vmg", "Video Games/Mobile", false),
+    ("vp", "Pokémon", false),
+    ("vr", "Retro Games", false),
+    ("vst", "Video Games/Strategy", false),
+    ("vt", "Virtual YouTubers", false),
+    ("w", "Anime/Wallpapers", false),
+    ("wg", "Wallpapers/General", false),
+    ("i", "Oekaki", false),
+    ("ic", "Artwork/Critique", false),
+    ("r", "Adult Requests", true),
+    ("r9k", "ROBOT9001", true),
+    ("s4s", "Shit 4chan Says", true),
+    ("cm", "Cute/Male", false),
+    ("hm", "Handsome Men", true),
+    ("lgbt", "LGBT", true),
+    ("y", "Yaoi", true),
+    ("3", "3DCG", true),
+    ("aco", "Adult Cartoons", true),
+    ("adv", "Advice", false),
+    ("an", "Animals & Nature", false),
+    ("bant", "International/Random", true),
+    ("biz", "Business & Finance", false),
+    ("cgl", "Cosplay & EGL", false),
+    ("ck", "Food & Cooking", false),
+    ("co", "Comics & Cartoons", false),
+    ("diy", "Do It Yourself", false),
+    ("fa", "Fashion", false),
+    ("fit", "Fitness", false),
+    ("gd", "Graphic Design", false),
+    ("hc", "Hardcore", true),
+    ("his", "History & Humanities", false),
+    ("int", "International", false),
+    ("jp", "Otaku Culture", false),
+    ("lit", "Literature", false),
+    ("mlp", "My Little Pony", false),
+    ("mu", "Music", false),
+    ("news", "Current News", false),
+    ("out", "Outdoors", false),
+    ("po", "Papercraft & Origami", false),
+    ("pw", "Professional Wrestling", false),
+    ("qst", "Quests", false),
+    ("sci", "Science & Math", false),
+    ("soc", "Cams & Meetups", true),
+    ("sp", "Sports", false),
+    ("tg", "Traditional Games", false),
+    ("toy", "Toys", false),
+    ("trv", "Travel", false),
+    ("tv", "Television & Film", false),
+    ("vip", "Very Important Posts", false),
+    ("vrpg", "Video Games/RPG", false),
+    ("wsg", "Worksafe GIF", false),
+    ("wsr", "Worksafe Requests", false),
+    ("x", "Paranormal", false),
+    ("xs", "Extreme Sports", false),
+];
+
+pub struct ArchivedMoe {
+    client: Client,
+}
+
+impl ArchivedMoe {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+fn describe(board: &str) -> String {
+    format!("archived.moe archive of /{}/", board)
+}
+
+#[async_trait]
+impl ImageboardSource for ArchivedMoe {
+    fn static_boards(&self) -> Vec<SiteBoard> {
+        boards_from_table(ARCHIVEDMOE_BOARDS, "Archive", describe)
+    }
+
+    async fn boards(&self) -> Result<Vec<SiteBoard>> {
+        foolfuuka::fetch_boards(&self.client, ARCHIVEDMOE_API_BASE, &self.static_boards(), describe).await
+    }
+
+    async fn catalog(&self, board: &str) -> Result<Vec<CatalogThread>> {
+        foolfuuka::fetch_catalog(&self.client, ARCHIVEDMOE_API_BASE, board).await
+    }
+
+    async fn thread(&self, board: &str, thread_id: u64) -> Result<Thread> {
+        foolfuuka::fetch_thread(&self.client, ARCHIVEDMOE_API_BASE, board, thread_id).await
+    }
+
+    fn media_url(&self, board: &str, tim: &str, ext: &str) -> String {
+        format!("{}/{}/full_image/{}{}", ARCHIVEDMOE_BASE, board, tim, ext)
+    }
+
+    fn thumbnail_url(&self, board: &str, tim: &str) -> String {
+        format!("{}/{}/thumb/{}s.jpg", ARCHIVEDMOE_BASE, board, tim)
+    }
+
+    fn media_hosts(&self) -> &'static [&'static str] {
+        &["archived.moe"]
+    }
+}
@@ -0,0 +1,267 @@
+/**
+ * FoolFuuka Archive Support
+ *
+ * Shared parsing for 4chan archives running FoolFuuka (4plebs, archived.moe).
+ * API Documentation: https://archive.4plebs.org/_/api/chan/
+ */
+
+use anyhow::{bail, Result};
+use reqwest::Client;
+use serde_json::Value;
+
+use super::SiteBoard;
+use crate::api::{ApiError, CatalogThread, Post, Thread};
+
+/// FoolFuuka returns most numbers either as JSON numbers or as strings
+fn get_u64(value: &Value, key: &str) -> Option<u64> {
+    let field = value.get(key)?;
+    field
+        .as_u64()
+        .or_else(|| field.as_str().and_then(|s| s.parse().ok()))
+}
+
+fn get_string(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(|v| v.as_str()).map(String::from)
+}
+
+/// Prefer the sanitized comment, falling back to the raw one
+fn get_comment(post: &Value) -> Option<String> {
+    post.get("comment_sanitized")
+        .or_else(|| post.get("comment"))
+        .and_then(|v| v.as_str())
+        .map(String::from)
+}
+
+/// Media fields of a post: (tim, ext, filename, fsize, w, h, tn_w, tn_h, md5)
+type MediaFields = (
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<u64>,
+    Option<u32>,
+    Option<u32>,
+    Option<u32>,
+    Option<u32>,
+    Option<String>,
+);
+
+fn parse_media(post: &Value) -> MediaFields {
+    let m = match post.get("media") {
+        Some(m) if !m.is_null() => m,
+        _ => return Default::default(),
+    };
+
+    let media_filename = m.get("media_filename").and_then(|v| v.as_str());
+
+    // The stored file name (media_orig) is what the image servers use;
+    // older archives only expose the numeric media id
+    let tim = m.get("media_orig").and_then(|v| v.as_str())
+        .and_then(|f| f.rsplit_once('.').map(|(n, _)| n.to_string()))
+        .or_else(|| get_string(m, "media_id"))
+        .or_else(|| get_u64(m, "media_id").map(|n| n.to_string()));
+    let ext = media_filename
+        .and_then(|f| f.rsplit_once('.').map(|(_, e)| format!(".{}", e)));
+    let filename = media_filename
+        .and_then(|f| f.rsplit_once('.').map(|(n, _)| n.to_string()));
+
+    (
+        tim,
+        ext,
+        filename,
+        get_u64(m, "media_size"),
+        get_u64(m, "media_w").map(|n| n as u32),
+        get_u64(m, "media_h").map(|n| n as u32),
+        get_u64(m, "preview_w").map(|n| n as u32),
+        get_u64(m, "preview_h").map(|n| n as u32),
+        get_string(m, "media_hash"),
+    )
+}
+
+/// Parse a FoolFuuka post from the API response
+fn parse_post(post: &Value, thread_num: u64) -> Option<Post> {
+    let no = get_u64(post, "num")?;
+    let time = get_u64(post, "timestamp")?;
+    let (tim, ext, filename, fsize, w, h, tn_w, tn_h, md5) = parse_media(post);
+
+    Some(Post {
+        no,
+        resto: if no == thread_num { 0 } else { thread_num },
+        time,
+        name: get_string(post, "name"),
+        trip: get_string(post, "trip"),
+        id: get_string(post, "poster_hash"),
+        capcode: get_string(post, "capcode").filter(|c| c != "N"),
+        country: get_string(post, "poster_country"),
+        country_name: get_string(post, "poster_country_name"),
+        sub: get_string(post, "title"),
+        com: get_comment(post),
+        tim,
+        ext,
+        filename,
+        fsize,
+        md5,
+        w,
+        h,
+        tn_w,
+        tn_h,
+        ..Default::default()
+    })
+}
+
+/// FoolFuuka flags are `1`/`0`, `"1"`/`"0"` or booleans depending on version
+fn get_flag(value: &Value, key: &str) -> bool {
+    let Some(field) = value.get(key) else {
+        return false;
+    };
+    field
+        .as_bool()
+        .or_else(|| field.as_u64().map(|n| n != 0))
+        .or_else(|| field.as_str().map(|s| s == "1" || s == "true"))
+        .unwrap_or(false)
+}
+
+/// Fetch the archived boards from `/_/api/chan/archives/`. NSFW flags the
+/// API doesn't carry are taken from `known` (the static table).
+pub(super) async fn fetch_boards(
+    client: &Client,
+    api_base: &str,
+    known: &[SiteBoard],
+    describe: impl Fn(&str) -> String,
+) -> Result<Vec<SiteBoard>> {
+    let url = format!("{}/archives/", api_base);
+    let response = client.get(&url).send().await?;
+
+    if !response.status().is_success() {
+        return Err(ApiError::Status(response.status()).into());
+    }
+
+    let json: Value = serde_json::from_str(&response.text().await?)?;
+    // Keyed by board on some versions, a plain list on others
+    let archives: Vec<&Value> = match json.get("archives") {
+        Some(Value::Object(map)) => map.values().collect(),
+        Some(Value::Array(list)) => list.iter().collect(),
+        _ => bail!("Unexpected archives response"),
+    };
+
+    let mut boards: Vec<SiteBoard> = archives
+        .into_iter()
+        .filter(|a| !get_flag(a, "hidden"))
+        .filter_map(|a| {
+            let id = get_string(a, "shortname")?;
+            let nsfw = get_flag(a, "is_nsfw") || known.iter().any(|b| b.id == id && b.nsfw);
+            Some(SiteBoard {
+                name: get_string(a, "name").unwrap_or_else(|| id.clone()),
+                description: Some(describe(&id)),
+                nsfw,
+                category: "Archive".to_string(),
+                id,
+            })
+        })
+        .collect();
+    boards.sort_by(|a, b| a.id.cmp(&b.id));
+
+    if boards.is_empty() {
+        bail!("Archive lists no boards");
+    }
+    Ok(boards)
+}
+
+/// Fetch the first index page of a board and flatten it into catalog threads
+pub(super) async fn fetch_catalog(client: &Client, api_base: &str, board: &str) -> Result<Vec<CatalogThread>> {
+    let url = format!("{}/index/?board={}&page=1", api_base, board);
+
+    let response = client.get(&url).send().await?;
+
+    if !response.status().is_success() {
+        bail!("Failed to fetch catalog: HTTP {}", response.status());
+    }
+
+    let json: Value = serde_json::from_str(&response.text().await?)?;
+
+    // FoolFuuka index format: { "board_shortname": { thread_num: { op: {...}, posts: {...} } } }
+    let mut threads = Vec::new();
+
+    if let Some(obj) = json.get(board).and_then(|b| b.as_object()) {
+        for (thread_num_str, thread_data) in obj {
+            let thread_num: u64 = thread_num_str.parse().unwrap_or(0);
+
+            let Some(op) = thread_data.get("op") else {
+                continue;
+            };
+
+            // The index only embeds the latest replies, so count what we have
+            let posts_count = thread_data.get("posts")
+                .and_then(|p| p.as_object())
+                .map(|p| p.len() as u32)
+                .unwrap_or(0);
+
+            let (tim, ext, filename, _, w, h, tn_w, tn_h, md5) = parse_media(op);
+
+            threads.push(CatalogThread {
+                no: thread_num,
+                time: get_u64(op, "timestamp").unwrap_or(0),
+                replies: posts_count,
+                images: 0, // FoolFuuka doesn't provide image count in index
+                name: get_string(op, "name"),
+                trip: get_string(op, "trip"),
+                id: get_string(op, "poster_hash"),
+                country: get_string(op, "poster_country"),
+                sub: get_string(op, "title"),
+                com: get_comment(op),
+                tim,
+                ext,
+                filename,
+                md5,
+                w,
+                h,
+                tn_w,
+                tn_h,
+                sticky: get_u64(op, "sticky").map(|n| n as u8),
+                closed: get_u64(op, "locked").map(|n| n as u8),
+                ..Default::default()
+            });
+        }
+    }
+
+    // Sort by time descending (newest first)
+    threads.sort_by_key(|t| std::cmp::Reverse(t.time));
+
+    Ok(threads)
+}
+
+/// Fetch a full thread, OP first and replies in post order
+pub(super) async fn fetch_thread(client: &Client, api_base: &str, board: &str, thread_id: u64) -> Result<Thread> {
+    let url = format!("{}/thread/?board={}&num={}", api_base, board, thread_id);
+
+    let response = client.get(&url).send().await?;
+
+    if !response.status().is_success() {
+        return Err(ApiError::Status(response.status()).into());
+    }
+
+    let json: Value = serde_json::from_str(&response.text().await?)?;
+
+    // FoolFuuka thread format: { thread_num: { op: {...}, posts: { post_num: {...}, ... } } }
+    let mut posts = Vec::new();
+
+    if let Some(thread_data) = json.get(thread_id.to_string()) {
+        if let Some(op) = thread_data.get("op").and_then(|op| parse_post(op, thread_id)) {
+            posts.push(op);
+        }
+
+        if let Some(replies) = thread_data.get("posts").and_then(|p| p.as_object()) {
+            let mut reply_posts: Vec<Post> = replies.values()
+                .filter_map(|post_data| parse_post(post_data, thread_id))
+                .collect();
+
+            reply_posts.sort_by_key(|p| p.no);
+            posts.extend(reply_posts);
+        }
+    }
+
+    if posts.is_empty() {
+        bail!("Thread not found or empty");
+    }
+
+    Ok(Thread { posts })
+}
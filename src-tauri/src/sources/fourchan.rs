@@ -0,0 +1,93 @@
+/**
+ * 4chan Source
+ *
+ * Uses the official read-only JSON API through the rate-limited `ChanClient`.
+ */
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::{ImageboardSource, SiteBoard};
+use crate::api::{
+    boards_endpoint, catalog_endpoint, thread_endpoint, Board, BoardList, Catalog, CatalogThread, ChanClient,
+    Conditional, Thread,
+};
+use crate::board_capabilities;
+use crate::boards_metadata::{categorize_board, get_all_boards_info};
+
+const FOURCHAN_MEDIA_BASE: &str = "https://i.4cdn.org";
+
+pub struct FourChan {
+    client: ChanClient,
+}
+
+impl FourChan {
+    pub fn new(client: ChanClient) -> Self {
+        Self { client }
+    }
+}
+
+fn site_board(board: &Board) -> SiteBoard {
+    SiteBoard {
+        id: board.board.clone(),
+        name: board.title.clone(),
+        description: board.meta_description.clone(),
+        nsfw: board.ws_board == 0,
+        category: categorize_board(&board.board).display_name().to_string(),
+    }
+}
+
+#[async_trait]
+impl ImageboardSource for FourChan {
+    fn static_boards(&self) -> Vec<SiteBoard> {
+        get_all_boards_info().into_iter().map(SiteBoard::from).collect()
+    }
+
+    async fn boards(&self) -> Result<Vec<SiteBoard>> {
+        let list: BoardList = self.client.get_json(&boards_endpoint()).await?;
+        // Keep board capabilities in step with the list
+        board_capabilities::remember(&list);
+        Ok(list.boards.iter().map(site_board).collect())
+    }
+
+    async fn catalog(&self, board: &str) -> Result<Vec<CatalogThread>> {
+        let catalog: Catalog = self.client.get_json(&catalog_endpoint(board)).await?;
+
+        // Flatten pages, keeping the page number on each thread
+        Ok(catalog
+            .into_iter()
+            .flat_map(|page| {
+                let page_num = page.page;
+                page.threads.into_iter().map(move |mut thread| {
+                    thread.page.get_or_insert(page_num);
+                    thread
+                })
+            })
+            .collect())
+    }
+
+    async fn thread(&self, board: &str, thread_id: u64) -> Result<Thread> {
+        self.client.get_json(&thread_endpoint(board, thread_id)).await
+    }
+
+    async fn thread_since(
+        &self,
+        board: &str,
+        thread_id: u64,
+        since: Option<&str>,
+    ) -> Result<Conditional<(Thread, Option<String>)>> {
+        self.client.get_json_since(&thread_endpoint(board, thread_id), since).await
+    }
+
+    fn media_url(&self, board: &str, tim: &str, ext: &str) -> String {
+        format!("{}/{}/{}{}", FOURCHAN_MEDIA_BASE, board, tim, ext)
+    }
+
+    fn thumbnail_url(&self, board: &str, tim: &str) -> String {
+        format!("{}/{}/{}s.jpg", FOURCHAN_MEDIA_BASE, board, tim)
+    }
+
+    fn media_hosts(&self) -> &'static [&'static str] {
+        &["i.4cdn.org"]
+    }
+}
@@ -0,0 +1,75 @@
+/**
+ * 4plebs Source
+ *
+ * 4plebs is a 4chan archive using FoolFuuka software.
+ */
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+
+use super::{boards_from_table, foolfuuka, ImageboardSource, SiteBoard};
+use crate::api::{CatalogThread, Thread};
+
+const FOURPLEBS_API_BASE: &str = "https://archive.4plebs.org/_/api/chan";
+const FOURPLEBS_IMAGE_BASE: &str = "https://i.4pcdn.org";
+
+/// Archived boards on 4plebs
+const FOURPLEBS_BOARDS: &[(&str, &str, bool)] = &[
+    ("adv", "Advice", false),
+    ("f", "Flash", false),
+    ("hr", "High Resolution", false),
+    ("o", "Auto", false),
+    ("pol", "Politically Incorrect", true),
+    ("s4s", "Shit 4chan Says", true),
+    ("sp", "Sports", false),
+    ("tg", "Traditional Games", false),
+    ("trv", "Travel", false),
+    ("tv", "Television & Film", false),
+    ("x", "Paranormal", false),
+];
+
+pub struct FourPlebs {
+    client: Client,
+}
+
+impl FourPlebs {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+fn describe(board: &str) -> String {
+    format!("4plebs archive of /{}/", board)
+}
+
+#[async_trait]
+impl ImageboardSource for FourPlebs {
+    fn static_boards(&self) -> Vec<SiteBoard> {
+        boards_from_table(FOURPLEBS_BOARDS, "Archive", describe)
+    }
+
+    async fn boards(&self) -> Result<Vec<SiteBoard>> {
+        foolfuuka::fetch_boards(&self.client, FOURPLEBS_API_BASE, &self.static_boards(), describe).await
+    }
+
+    async fn catalog(&self, board: &str) -> Result<Vec<CatalogThread>> {
+        foolfuuka::fetch_catalog(&self.client, FOURPLEBS_API_BASE, board).await
+    }
+
+    async fn thread(&self, board: &str, thread_id: u64) -> Result<Thread> {
+        foolfuuka::fetch_thread(&self.client, FOURPLEBS_API_BASE, board, thread_id).await
+    }
+
+    fn media_url(&self, board: &str, tim: &str, ext: &str) -> String {
+        format!("{}/{}/{}{}", FOURPLEBS_IMAGE_BASE, board, tim, ext)
+    }
+
+    fn thumbnail_url(&self, board: &str, tim: &str) -> String {
+        format!("{}/{}/{}s.jpg", FOURPLEBS_IMAGE_BASE, board, tim)
+    }
+
+    fn media_hosts(&self) -> &'static [&'static str] {
+        &["i.4pcdn.org"]
+    }
+}
@@ -0,0 +1,227 @@
+/**
+ * Imageboard Sources
+ *
+ * A single `ImageboardSource` trait implemented by every supported site.
+ * Commands pick a source by `Site` and always get back the normalized
+ * `Post` / `CatalogThread` types from `api::types`, no matter which
+ * backend (4chan API, FoolFuuka archive, Kusaba X, HTML scraping) served it.
+ */
+
+mod fourchan;
+mod sevenchan;
+mod foolfuuka;
+mod fourplebs;
+mod archivedmoe;
+mod twentytwochan;
+
+pub use fourchan::FourChan;
+pub use sevenchan::SevenChan;
+pub use fourplebs::FourPlebs;
+pub use archivedmoe::ArchivedMoe;
+pub use twentytwochan::TwentyTwoChan;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::api::{CatalogThread, ChanClient, Conditional, Thread};
+use crate::boards_metadata::BoardInfo;
+use crate::comment::CommentFormat;
+
+/// Browser user agent used for sites that reject unknown clients
+pub(crate) const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36";
+
+/// Supported sites. Serialized ids match the frontend provider ids.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Site {
+    #[serde(rename = "4chan")]
+    FourChan,
+    #[serde(rename = "7chan")]
+    SevenChan,
+    #[serde(rename = "4plebs")]
+    FourPlebs,
+    #[serde(rename = "archivedmoe")]
+    ArchivedMoe,
+    #[serde(rename = "22chan")]
+    TwentyTwoChan,
+}
+
+impl Site {
+    pub const ALL: [Site; 5] = [
+        Self::FourChan,
+        Self::SevenChan,
+        Self::FourPlebs,
+        Self::ArchivedMoe,
+        Self::TwentyTwoChan,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::FourChan => "4chan",
+            Self::SevenChan => "7chan",
+            Self::FourPlebs => "4plebs",
+            Self::ArchivedMoe => "archivedmoe",
+            Self::TwentyTwoChan => "22chan",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "4chan" => Some(Self::FourChan),
+            "7chan" => Some(Self::SevenChan),
+            "4plebs" => Some(Self::FourPlebs),
+            "archivedmoe" => Some(Self::ArchivedMoe),
+            "22chan" => Some(Self::TwentyTwoChan),
+            _ => None,
+        }
+    }
+
+    /// Encoding of post comments: 4chan-style HTML, or plain text from the
+    /// archives' sanitized comments and 22chan's scraped posts
+    pub fn comment_format(&self) -> CommentFormat {
+        match self {
+            Self::FourChan | Self::SevenChan => CommentFormat::Html,
+            Self::FourPlebs | Self::ArchivedMoe | Self::TwentyTwoChan => CommentFormat::Text,
+        }
+    }
+}
+
+/// Board entry as reported by a source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteBoard {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub nsfw: bool,
+    pub category: String,
+}
+
+impl From<BoardInfo> for SiteBoard {
+    fn from(info: BoardInfo) -> Self {
+        Self {
+            id: info.id,
+            name: info.name,
+            description: info.description,
+            nsfw: info.nsfw,
+            category: info.category.display_name().to_string(),
+        }
+    }
+}
+
+/// Build a `SiteBoard` list from a static `(id, name, nsfw)` table
+fn boards_from_table(
+    table: &[(&str, &str, bool)],
+    category: &str,
+    describe: impl Fn(&str) -> String,
+) -> Vec<SiteBoard> {
+    table
+        .iter()
+        .map(|(id, name, nsfw)| SiteBoard {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: Some(describe(id)),
+            nsfw: *nsfw,
+            category: category.to_string(),
+        })
+        .collect()
+}
+
+/// HTTP client shared by the scraping/archive sources
+pub(crate) fn browser_client() -> Result<Client> {
+    Ok(Client::builder()
+        .user_agent(BROWSER_USER_AGENT)
+        .timeout(Duration::from_secs(30))
+        .build()?)
+}
+
+/// Common interface over every imageboard backend
+#[async_trait]
+pub trait ImageboardSource: Send + Sync {
+    /// Boards compiled into the app, used until (or when) the live list
+    /// can't be fetched
+    fn static_boards(&self) -> Vec<SiteBoard>;
+
+    /// Fetch the site's current board list. Sites without one fall back to
+    /// the static table.
+    async fn boards(&self) -> Result<Vec<SiteBoard>> {
+        Ok(self.static_boards())
+    }
+
+    /// Fetch a board catalog as a flat list of thread OPs
+    async fn catalog(&self, board: &str) -> Result<Vec<CatalogThread>>;
+
+    /// Fetch all posts of a thread, OP first
+    async fn thread(&self, board: &str, thread_id: u64) -> Result<Thread>;
+
+    /// Fetch a thread unless it is unchanged since `since`, a `Last-Modified`
+    /// value returned by an earlier call. Sources without conditional request
+    /// support always return the full thread.
+    async fn thread_since(
+        &self,
+        board: &str,
+        thread_id: u64,
+        _since: Option<&str>,
+    ) -> Result<Conditional<(Thread, Option<String>)>> {
+        Ok(Conditional::Modified((self.thread(board, thread_id).await?, None)))
+    }
+
+    /// Full-size media URL for a post's `tim` + `ext`
+    fn media_url(&self, board: &str, tim: &str, ext: &str) -> String;
+
+    /// Thumbnail URL for a post's `tim`
+    fn thumbnail_url(&self, board: &str, tim: &str) -> String;
+
+    /// Hosts serving the site's media and thumbnails
+    fn media_hosts(&self) -> &'static [&'static str];
+}
+
+/// Registry of all sources, managed as Tauri state
+pub struct Sources {
+    fourchan: FourChan,
+    sevenchan: SevenChan,
+    fourplebs: FourPlebs,
+    archivedmoe: ArchivedMoe,
+    twentytwochan: TwentyTwoChan,
+}
+
+impl Sources {
+    pub fn new(client: ChanClient) -> Result<Self> {
+        let browser = browser_client()?;
+
+        Ok(Self {
+            fourchan: FourChan::new(client),
+            sevenchan: SevenChan::new(browser.clone()),
+            fourplebs: FourPlebs::new(browser.clone()),
+            archivedmoe: ArchivedMoe::new(browser.clone()),
+            twentytwochan: TwentyTwoChan::new(browser),
+        })
+    }
+
+    pub fn get(&self, site: Site) -> &dyn ImageboardSource {
+        match site {
+            Site::FourChan => &self.fourchan,
+            Site::SevenChan => &self.sevenchan,
+            Site::FourPlebs => &self.fourplebs,
+            Site::ArchivedMoe => &self.archivedmoe,
+            Site::TwentyTwoChan => &self.twentytwochan,
+        }
+    }
+
+    /// Whether `url` points at the media host of a supported site
+    pub fn is_media_url(&self, url: &str) -> bool {
+        let Ok(url) = Url::parse(url) else {
+            return false;
+        };
+        if !matches!(url.scheme(), "https" | "http") {
+            return false;
+        }
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        Site::ALL
+            .iter()
+            .any(|site| self.get(*site).media_hosts().contains(&host))
+    }
+}
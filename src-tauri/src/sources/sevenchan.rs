@@ -0,0 +1,184 @@
+/**
+ * 7chan Source
+ *
+ * 7chan uses Kusaba X which has a different API format than 4chan.
+ * The catalog and thread endpoints return JSON in a different structure,
+ * and not every board exposes them.
+ */
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+
+use super::{boards_from_table, ImageboardSource, SiteBoard};
+use crate::api::{ApiError, CatalogThread, Post, Thread};
+use crate::thumbnails;
+
+const SEVENCHAN_BASE: &str = "https://7chan.org";
+
+/// 7chan boards - hardcoded since there's no board list API
+const SEVENCHAN_BOARDS: &[(&str, &str, bool)] = &[
+    ("a", "Anime", false),
+    ("me", "Mecha", false),
+    ("co", "Comics & Cartoons", false),
+    ("tv", "Movies & TV", false),
+    ("pr", "Programming", false),
+    ("tech", "Technology", false),
+    ("tg", "Tabletop Games", false),
+    ("vg", "Video Games", false),
+    ("b", "Random", true),
+    ("gfx", "Graphics", false),
+    ("w", "Weapons", false),
+    ("fit", "Fitness", false),
+    ("fl", "Flash", false),
+    ("lit", "Literature", false),
+    ("phi", "Philosophy", false),
+    ("x", "Paranormal", false),
+    ("gif", "Animated GIFs", true),
+    ("d", "Alternative Hentai", true),
+    ("h", "Hentai", true),
+    ("di", "Sexy Beautiful Traps", true),
+];
+
+pub struct SevenChan {
+    client: Client,
+}
+
+impl SevenChan {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    /// Fallback: fetch page 0 and extract the OP of every thread on it
+    async fn catalog_from_first_page(&self, board: &str) -> Result<Vec<CatalogThread>> {
+        let page_url = format!("{}/{}/0.json", SEVENCHAN_BASE, board);
+        let response = self.client.get(&page_url).send().await?;
+
+        if response.status().is_success() {
+            let text = response.text().await?;
+            if let Ok(page) = serde_json::from_str::<Value>(&text) {
+                if let Some(threads) = page.get("threads").and_then(|t| t.as_array()) {
+                    let catalog: Vec<CatalogThread> = threads
+                        .iter()
+                        .filter_map(|t| {
+                            let posts = t.get("posts")?.as_array()?;
+                            let op = posts.first()?;
+                            Some(CatalogThread {
+                                no: op.get("no")?.as_u64()?,
+                                time: op.get("time")?.as_u64()?,
+                                sub: op.get("sub").and_then(|v| v.as_str()).map(String::from),
+                                com: op.get("com").and_then(|v| v.as_str()).map(String::from),
+                                tim: op.get("tim").and_then(|v| v.as_u64()).map(|n| n.to_string()),
+                                ext: op.get("ext").and_then(|v| v.as_str()).map(String::from),
+                                replies: posts.len().saturating_sub(1) as u32,
+                                page: Some(0),
+                                ..Default::default()
+                            })
+                        })
+                        .collect();
+                    return Ok(catalog);
+                }
+            }
+        }
+
+        bail!("7chan /{}/ is not accessible or has no JSON API", board)
+    }
+
+    async fn fetch_catalog(&self, board: &str) -> Result<Vec<CatalogThread>> {
+        // Try JSON catalog first (some Kusaba X boards support this)
+        let url = format!("{}/{}/catalog.json", SEVENCHAN_BASE, board);
+
+        let response = match self.client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => response,
+            _ => return self.catalog_from_first_page(board).await,
+        };
+
+        let text = response.text().await?;
+
+        // Try to parse as JSON array of pages (4chan-like format)
+        if let Ok(pages) = serde_json::from_str::<Vec<Value>>(&text) {
+            let mut threads = Vec::new();
+            for page in pages {
+                if let Some(page_threads) = page.get("threads").and_then(|t| t.as_array()) {
+                    for thread in page_threads {
+                        if let Ok(t) = serde_json::from_value::<CatalogThread>(thread.clone()) {
+                            threads.push(t);
+                        }
+                    }
+                }
+            }
+            return Ok(threads);
+        }
+
+        // Try flat array format
+        if let Ok(threads) = serde_json::from_str::<Vec<CatalogThread>>(&text) {
+            return Ok(threads);
+        }
+
+        bail!("Failed to parse 7chan catalog")
+    }
+
+    async fn fetch_thread(&self, board: &str, thread_id: u64) -> Result<Thread> {
+        let url = format!("{}/{}/res/{}.json", SEVENCHAN_BASE, board, thread_id);
+
+        let response = self.client.get(&url).send().await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::Status(response.status()).into());
+        }
+
+        let text = response.text().await?;
+
+        // Try to parse - 7chan format may vary
+        if let Ok(thread) = serde_json::from_str::<Thread>(&text) {
+            return Ok(thread);
+        }
+
+        // Fall back to parsing posts one by one, skipping malformed entries
+        if let Ok(value) = serde_json::from_str::<Value>(&text) {
+            if let Some(posts) = value.get("posts").and_then(|p| p.as_array()) {
+                let posts: Vec<Post> = posts
+                    .iter()
+                    .filter_map(|p| serde_json::from_value(p.clone()).ok())
+                    .collect();
+                return Ok(Thread { posts });
+            }
+        }
+
+        bail!("Failed to parse 7chan thread")
+    }
+}
+
+#[async_trait]
+impl ImageboardSource for SevenChan {
+    // No board list API or index worth scraping
+    fn static_boards(&self) -> Vec<SiteBoard> {
+        boards_from_table(SEVENCHAN_BOARDS, "7chan", |id| format!("7chan /{}/", id))
+    }
+
+    async fn catalog(&self, board: &str) -> Result<Vec<CatalogThread>> {
+        let mut threads = self.fetch_catalog(board).await?;
+        // The page fallback and some boards' catalogs leave out image sizes
+        thumbnails::fill_dimensions(&self.client, &mut threads, |tim, ext| self.media_url(board, tim, ext)).await;
+        Ok(threads)
+    }
+
+    async fn thread(&self, board: &str, thread_id: u64) -> Result<Thread> {
+        let mut thread = self.fetch_thread(board, thread_id).await?;
+        thumbnails::fill_dimensions(&self.client, &mut thread.posts, |tim, ext| self.media_url(board, tim, ext)).await;
+        Ok(thread)
+    }
+
+    fn media_url(&self, board: &str, tim: &str, ext: &str) -> String {
+        format!("{}/{}/src/{}{}", SEVENCHAN_BASE, board, tim, ext)
+    }
+
+    fn thumbnail_url(&self, board: &str, tim: &str) -> String {
+        format!("{}/{}/thumb/{}s.jpg", SEVENCHAN_BASE, board, tim)
+    }
+
+    fn media_hosts(&self) -> &'static [&'static str] {
+        &["7chan.org"]
+    }
+}
@@ -0,0 +1,304 @@
+/**
+ * 22chan Source
+ *
+ * 22chan uses a custom Django/Python stack with no JSON API,
+ * so we use HTML scraping to extract data.
+ */
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use scraper::{ElementRef, Html, Selector};
+
+use super::{boards_from_table, ImageboardSource, SiteBoard};
+use crate::api::{ApiError, CatalogThread, Post, Thread};
+use crate::thumbnails;
+
+const TWENTYTWOCHAN_BASE: &str = "https://22chan.org";
+
+/// 22chan boards - hardcoded since there's no API
+const TWENTYTWOCHAN_BOARDS: &[(&str, &str, bool)] = &[
+    ("a", "Anime & Manga", false),
+    ("b", "Random", true),
+    ("cat", "Cinema", false),
+    ("co", "Comics & Cartoons", false),
+    ("fit", "Fitness", false),
+    ("k", "Weapons", false),
+    ("lit", "Literature", false),
+    ("meta", "Meta", false),
+    ("mu", "Music", false),
+    ("out", "Outdoors", false),
+    ("pol", "Politics", true),
+    ("sci", "Science", false),
+    ("tech", "Technology", false),
+    ("v", "Video Games General", false),
+    ("vg", "Video Games", false),
+    ("w", "Wallpapers", false),
+];
+
+/// Parse a CSS selector, surfacing failures as errors
+fn selector(css: &str) -> Result<Selector> {
+    Selector::parse(css).map_err(|e| anyhow::anyhow!("Invalid selector {}: {}", css, e))
+}
+
+/// Text content of the first element matching `selector`
+fn select_text(el: &ElementRef, selector: &Selector) -> Option<String> {
+    el.select(selector)
+        .next()
+        .map(|el| el.text().collect::<String>().trim().to_string())
+}
+
+/// Split an upload link into (tim, ext, filename)
+fn parse_upload_href(href: &str) -> (Option<String>, Option<String>, Option<String>) {
+    let fname = href.rsplit('/').next().unwrap_or("");
+    match fname.rsplit_once('.') {
+        Some((stem, ext)) => (Some(stem.to_string()), Some(format!(".{}", ext)), Some(fname.to_string())),
+        None => (None, None, None),
+    }
+}
+
+/// Selectors used on both board and thread pages
+struct PageSelectors {
+    name: Selector,
+    subject: Selector,
+    image: Selector,
+    inner: Selector,
+}
+
+impl PageSelectors {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            name: selector(".name")?,
+            subject: selector(".subject")?,
+            image: selector("a[href*='/UserMedia/uploads/']")?,
+            inner: selector(".inner")?,
+        })
+    }
+
+    fn upload(&self, el: &ElementRef) -> (Option<String>, Option<String>, Option<String>) {
+        el.select(&self.image)
+            .next()
+            .and_then(|a| a.value().attr("href"))
+            .map(parse_upload_href)
+            .unwrap_or((None, None, None))
+    }
+}
+
+/// Post number from the `data-slug` attribute
+fn post_slug(el: &ElementRef) -> Option<u64> {
+    el.value().attr("data-slug").and_then(|s| s.parse().ok())
+}
+
+pub struct TwentyTwoChan {
+    client: Client,
+}
+
+impl TwentyTwoChan {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    async fn fetch_html(&self, url: &str) -> Result<String> {
+        let response = self.client.get(url).send().await?;
+
+        if !response.status().is_success() {
+            return Err(ApiError::Status(response.status()).into());
+        }
+
+        Ok(response.text().await?)
+    }
+}
+
+#[async_trait]
+impl ImageboardSource for TwentyTwoChan {
+    fn static_boards(&self) -> Vec<SiteBoard> {
+        boards_from_table(TWENTYTWOCHAN_BOARDS, "22chan", |id| format!("22chan /{}/", id))
+    }
+
+    /// Scrape the board links off the front page. Only links to boards in the
+    /// static table, or labelled like a board (`/xyz/ - Name`), count; the
+    /// page also links rules, FAQ and news pages by the same kind of path.
+    async fn boards(&self) -> Result<Vec<SiteBoard>> {
+        let html = self.fetch_html(&format!("{}/", TWENTYTWOCHAN_BASE)).await?;
+        let document = Html::parse_document(&html);
+        let link_selector = selector("a[href]")?;
+        let known = self.static_boards();
+
+        let mut boards: Vec<SiteBoard> = Vec::new();
+        for link in document.select(&link_selector) {
+            let href = link.value().attr("href").unwrap_or_default();
+            let path = href.strip_prefix(TWENTYTWOCHAN_BASE).unwrap_or(href);
+            let Some(id) = path.strip_prefix('/').and_then(|p| p.strip_suffix('/')) else {
+                continue;
+            };
+            if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) || boards.iter().any(|b| b.id == id) {
+                continue;
+            }
+
+            // Links read "/a/ - Anime & Manga" or just the name
+            let text = link.text().collect::<String>();
+            let label = format!("/{}/", id);
+            let known_board = known.iter().find(|b| b.id == id);
+            if known_board.is_none() && !text.trim().starts_with(&label) {
+                continue;
+            }
+            let name = text
+                .trim()
+                .trim_start_matches(&label)
+                .trim_start_matches([' ', '-'])
+                .trim();
+
+            boards.push(SiteBoard {
+                id: id.to_string(),
+                name: if name.is_empty() {
+                    known_board.map_or_else(|| id.to_string(), |b| b.name.clone())
+                } else {
+                    name.to_string()
+                },
+                description: Some(format!("22chan /{}/", id)),
+                nsfw: known_board.is_some_and(|b| b.nsfw),
+                category: "22chan".to_string(),
+            });
+        }
+
+        if boards.is_empty() {
+            bail!("No boards found on the 22chan front page");
+        }
+        Ok(boards)
+    }
+
+    async fn catalog(&self, board: &str) -> Result<Vec<CatalogThread>> {
+        let url = format!("{}/{}/", TWENTYTWOCHAN_BASE, board);
+        let html = self.fetch_html(&url).await?;
+        // The parsed document isn't Send, so drop it before probing images
+        let mut threads = {
+            let document = Html::parse_document(&html);
+
+            let thread_selector = selector(".thread")?;
+            let s = PageSelectors::new()?;
+
+            let mut threads = Vec::new();
+
+            for thread_el in document.select(&thread_selector) {
+                let Some(thread_id) = post_slug(&thread_el).filter(|id| *id != 0) else {
+                    continue;
+                };
+
+                let (tim, ext, _) = s.upload(&thread_el);
+
+                // Content preview: skip the header line and keep the first few lines
+                let com = thread_el.select(&s.inner).next().map(|el| {
+                    el.text()
+                        .collect::<String>()
+                        .lines()
+                        .skip(1)
+                        .take(5)
+                        .collect::<Vec<&str>>()
+                        .join("\n")
+                        .trim()
+                        .to_string()
+                });
+
+                // Parse reply count from header text (looking for ★ N pattern)
+                let header_text = thread_el.text().collect::<String>();
+                let replies = header_text
+                    .split('★')
+                    .nth(1)
+                    .and_then(|s| s.split_whitespace().next())
+                    .and_then(|s| s.parse().ok())
+                    .unwrap_or(0);
+
+                threads.push(CatalogThread {
+                    no: thread_id,
+                    sub: select_text(&thread_el, &s.subject),
+                    name: select_text(&thread_el, &s.name),
+                    com,
+                    tim,
+                    ext,
+                    replies,
+                    ..Default::default()
+                });
+            }
+
+            threads
+        };
+
+        // Pages only link the files; probe them for their sizes
+        thumbnails::fill_dimensions(&self.client, &mut threads, |tim, ext| self.media_url(board, tim, ext)).await;
+
+        Ok(threads)
+    }
+
+    async fn thread(&self, board: &str, thread_id: u64) -> Result<Thread> {
+        let url = format!("{}/{}/{}/", TWENTYTWOCHAN_BASE, board, thread_id);
+        let html = self.fetch_html(&url).await?;
+        // The parsed document isn't Send, so drop it before probing images
+        let mut posts = {
+            let document = Html::parse_document(&html);
+
+            // OP is .thread, replies are .reply
+            let op_selector = selector(".thread")?;
+            let reply_selector = selector(".reply")?;
+            let s = PageSelectors::new()?;
+
+            let mut posts = Vec::new();
+
+            if let Some(op_el) = document.select(&op_selector).next() {
+                let (tim, ext, filename) = s.upload(&op_el);
+
+                posts.push(Post {
+                    no: post_slug(&op_el).unwrap_or(thread_id),
+                    resto: 0,
+                    name: select_text(&op_el, &s.name),
+                    sub: select_text(&op_el, &s.subject),
+                    com: select_text(&op_el, &s.inner),
+                    tim,
+                    ext,
+                    filename,
+                    ..Default::default()
+                });
+            }
+
+            for reply_el in document.select(&reply_selector) {
+                let Some(post_id) = post_slug(&reply_el).filter(|id| *id != 0) else {
+                    continue;
+                };
+
+                let (tim, ext, filename) = s.upload(&reply_el);
+
+                posts.push(Post {
+                    no: post_id,
+                    resto: thread_id,
+                    name: select_text(&reply_el, &s.name),
+                    com: select_text(&reply_el, &s.inner),
+                    tim,
+                    ext,
+                    filename,
+                    ..Default::default()
+                });
+            }
+
+            if posts.is_empty() {
+                bail!("Thread not found or empty");
+            }
+
+            posts
+        };
+
+        thumbnails::fill_dimensions(&self.client, &mut posts, |tim, ext| self.media_url(board, tim, ext)).await;
+
+        Ok(Thread { posts })
+    }
+
+    fn media_url(&self, _board: &str, tim: &str, ext: &str) -> String {
+        format!("{}/UserMedia/uploads/{}{}", TWENTYTWOCHAN_BASE, tim, ext)
+    }
+
+    fn thumbnail_url(&self, _board: &str, tim: &str) -> String {
+        format!("{}/UserMedia/uploads/thumbnails/{}s.jpg", TWENTYTWOCHAN_BASE, tim)
+    }
+
+    fn media_hosts(&self) -> &'static [&'static str] {
+        &["22chan.org"]
+    }
+}
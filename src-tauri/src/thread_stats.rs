@@ -0,0 +1,262 @@
+/**
+ * Thread Statistics
+ *
+ * Chart-ready numbers about one thread, computed from its posts: activity
+ * over time, posters by ID, countries, the most quoted posts, media mix,
+ * comment length and when the thread will hit its bump limit at the
+ * current pace.
+ *
+ * Works on live threads and on the thread cache, which lacks IDs and
+ * countries; those breakdowns are then empty.
+ */
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::api::Post;
+use crate::cache::CachedPost;
+use crate::catalog_search::strip_html;
+use crate::comment::CommentFormat;
+use crate::reply_graph::ReplyGraph;
+
+/// Aim for about this many timeline buckets
+const TARGET_BUCKETS: u64 = 60;
+const MIN_BUCKET_SECS: u64 = 60;
+/// Window the bump limit projection takes its pace from
+const PACE_WINDOW_SECS: u64 = 60 * 60;
+const MOST_QUOTED: usize = 10;
+const VIDEO_EXTS: [&str; 3] = [".webm", ".mp4", ".mkv"];
+
+/// Posts in one slice of the thread's lifetime
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityBucket {
+    /// Unix time the bucket starts at
+    pub start: u64,
+    pub posts: u32,
+    pub posts_per_minute: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PosterCount {
+    pub id: String,
+    pub posts: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CountryCount {
+    pub code: String,
+    pub name: Option<String>,
+    pub posts: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotedPost {
+    pub no: u64,
+    pub replies: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaStats {
+    pub images: u32,
+    pub videos: u32,
+    /// Share of posts with a file
+    pub file_ratio: f64,
+    /// Share of files that are videos
+    pub video_ratio: f64,
+}
+
+/// When the thread reaches its bump limit if it keeps the pace of the last hour
+#[derive(Debug, Clone, Serialize)]
+pub struct BumpProjection {
+    pub bump_limit: u32,
+    pub replies: u32,
+    pub reached: bool,
+    pub replies_per_minute: f64,
+    /// Seconds after the last post, unset when reached or the thread is idle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projected_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ThreadStats {
+    pub thread_id: u64,
+    pub posts: u32,
+    pub first_post_at: u64,
+    pub last_post_at: u64,
+    pub posts_per_minute: f64,
+    pub bucket_secs: u64,
+    pub timeline: Vec<ActivityBucket>,
+    pub unique_posters: u32,
+    pub posters: Vec<PosterCount>,
+    pub countries: Vec<CountryCount>,
+    pub most_quoted: Vec<QuotedPost>,
+    pub media: MediaStats,
+    /// In characters of plain text, over posts with a comment
+    pub average_comment_length: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bump_limit: Option<BumpProjection>,
+}
+
+/// Statistics of a thread. `bump_limit` is the board's, when known.
+pub fn compute(posts: &[Post], format: CommentFormat, bump_limit: Option<u32>) -> ThreadStats {
+    let first = posts.iter().map(|p| p.time).min().unwrap_or(0);
+    let last = posts.iter().map(|p| p.time).max().unwrap_or(0);
+    let span = last - first;
+
+    // Timeline
+    let bucket_secs = (span / TARGET_BUCKETS).max(MIN_BUCKET_SECS);
+    let mut timeline: Vec<ActivityBucket> = (0..=span / bucket_secs)
+        .map(|i| ActivityBucket { start: first + i * bucket_secs, posts: 0, posts_per_minute: 0.0 })
+        .collect();
+    for post in posts {
+        if let Some(bucket) = timeline.get_mut(((post.time - first) / bucket_secs) as usize) {
+            bucket.posts += 1;
+        }
+    }
+    for bucket in &mut timeline {
+        bucket.posts_per_minute = bucket.posts as f64 * 60.0 / bucket_secs as f64;
+    }
+
+    // Posters and countries
+    let mut by_id: HashMap<&str, u32> = HashMap::new();
+    let mut by_country: HashMap<&str, (Option<&str>, u32)> = HashMap::new();
+    for post in posts {
+        if let Some(id) = post.id.as_deref() {
+            *by_id.entry(id).or_default() += 1;
+        }
+        if let Some(code) = post.country.as_deref() {
+            let entry = by_country.entry(code).or_insert((post.country_name.as_deref(), 0));
+            entry.1 += 1;
+        }
+    }
+    let mut posters: Vec<PosterCount> = by_id
+        .into_iter()
+        .map(|(id, posts)| PosterCount { id: id.to_string(), posts })
+        .collect();
+    posters.sort_by(|a, b| b.posts.cmp(&a.posts).then_with(|| a.id.cmp(&b.id)));
+    let mut countries: Vec<CountryCount> = by_country
+        .into_iter()
+        .map(|(code, (name, posts))| CountryCount { code: code.to_string(), name: name.map(String::from), posts })
+        .collect();
+    countries.sort_by(|a, b| b.posts.cmp(&a.posts).then_with(|| a.code.cmp(&b.code)));
+
+    // Most quoted
+    let graph = ReplyGraph::build(posts, format);
+    let mut most_quoted: Vec<QuotedPost> = graph
+        .posts
+        .iter()
+        .filter(|(_, links)| !links.replied_by.is_empty())
+        .map(|(&no, links)| QuotedPost { no, replies: links.replied_by.len() as u32 })
+        .collect();
+    most_quoted.sort_by(|a, b| b.replies.cmp(&a.replies).then_with(|| a.no.cmp(&b.no)));
+    most_quoted.truncate(MOST_QUOTED);
+
+    // Media
+    let (mut images, mut videos) = (0, 0);
+    for post in posts.iter().filter(|p| p.filedeleted != Some(1)) {
+        match post.ext.as_deref() {
+            Some(ext) if VIDEO_EXTS.contains(&ext.to_lowercase().as_str()) => videos += 1,
+            Some(_) => images += 1,
+            None => {}
+        }
+    }
+    let files = images + videos;
+    let media = MediaStats {
+        images,
+        videos,
+        file_ratio: ratio(files, posts.len() as u32),
+        video_ratio: ratio(videos, files),
+    };
+
+    // Comment length
+    let lengths: Vec<usize> = posts
+        .iter()
+        .filter_map(|p| p.com.as_deref())
+        .map(|com| match format {
+            CommentFormat::Html => strip_html(com).chars().count(),
+            CommentFormat::Text => com.chars().count(),
+        })
+        .collect();
+    let average_comment_length = if lengths.is_empty() {
+        0.0
+    } else {
+        lengths.iter().sum::<usize>() as f64 / lengths.len() as f64
+    };
+
+    ThreadStats {
+        thread_id: posts.first().map_or(0, |op| op.no),
+        posts: posts.len() as u32,
+        first_post_at: first,
+        last_post_at: last,
+        posts_per_minute: posts.len() as f64 * 60.0 / span.max(MIN_BUCKET_SECS) as f64,
+        bucket_secs,
+        timeline,
+        unique_posters: posters.len() as u32,
+        posters,
+        countries,
+        most_quoted,
+        media,
+        average_comment_length,
+        bump_limit: bump_limit.map(|limit| project_bump_limit(posts, limit, last)),
+    }
+}
+
+fn project_bump_limit(posts: &[Post], bump_limit: u32, last: u64) -> BumpProjection {
+    // The OP's reply count includes deleted posts; prefer it when present
+    let replies = posts
+        .first()
+        .and_then(|op| op.replies)
+        .unwrap_or(posts.len().saturating_sub(1) as u32);
+    let reached = replies >= bump_limit || posts.first().and_then(|op| op.bumplimit) == Some(1);
+
+    let first = posts.iter().map(|p| p.time).min().unwrap_or(last);
+    let window_start = last.saturating_sub(PACE_WINDOW_SECS).max(first);
+    let recent = posts.iter().skip(1).filter(|p| p.time >= window_start).count();
+    let replies_per_minute = recent as f64 * 60.0 / (last - window_start).max(MIN_BUCKET_SECS) as f64;
+
+    let eta_secs = (!reached && replies_per_minute > 0.0)
+        .then(|| ((bump_limit - replies) as f64 / replies_per_minute * 60.0).round() as u64);
+
+    BumpProjection {
+        bump_limit,
+        replies,
+        reached,
+        replies_per_minute,
+        eta_secs,
+        projected_at: eta_secs.map(|eta| last + eta),
+    }
+}
+
+fn ratio(part: u32, whole: u32) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Posts of a cached thread, as far as the cache keeps them
+pub fn posts_from_cache(cached: Vec<CachedPost>) -> Vec<Post> {
+    cached
+        .into_iter()
+        .map(|p| Post {
+            no: p.post_no as u64,
+            resto: p.resto as u64,
+            time: p.time as u64,
+            name: p.name,
+            trip: p.trip,
+            sub: p.subject,
+            com: p.comment,
+            tim: p.tim.map(|t| t.to_string()),
+            ext: p.ext,
+            filename: p.filename,
+            fsize: p.fsize.map(|s| s as u64),
+            w: p.w.map(|w| w as u32),
+            h: p.h.map(|h| h as u32),
+            md5: p.md5,
+            ..Default::default()
+        })
+        .collect()
+}
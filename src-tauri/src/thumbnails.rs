@@ -0,0 +1,393 @@
+/**
+ * Thumbnails
+ *
+ * Image probing and thumbnail generation for media the site doesn't describe:
+ * 22chan pages carry no dimensions or thumbnails, 7chan's page fallback drops
+ * them, and local files picked for posting have neither.
+ *
+ * Decodes JPEG, PNG, GIF (first frame) and WebP. Generated thumbnails are
+ * JPEGs kept in the media store, one per source file and size, so they are
+ * subject to the media cache's quotas like downloaded thumbnails. Dimensions
+ * probed from remote files are remembered in `media_dimensions`, so each file
+ * is only probed once.
+ */
+
+use anyhow::{anyhow, bail, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, GenericImageView, ImageReader, RgbImage};
+use reqwest::header::RANGE;
+use reqwest::Client;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::api::{CatalogThread, Post};
+use crate::cache;
+use crate::media_cache::MediaKind;
+use crate::media_protocol::{self, ThreadRef};
+use crate::media_store;
+
+/// Longest side of generated thumbnails unless configured otherwise (4chan OP size)
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 250;
+const MIN_THUMBNAIL_SIZE: u32 = 50;
+const MAX_THUMBNAIL_SIZE: u32 = 1024;
+const SIZE_KEY: &str = "thumbnail_size";
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Headers of every supported format fit in this many leading bytes
+/// (unless a JPEG carries a huge EXIF block; those are skipped)
+const PROBE_BYTES: usize = 64 * 1024;
+const PROBE_CONCURRENCY: usize = 8;
+
+const IMAGE_EXTENSIONS: &[&str] = &[".jpg", ".jpeg", ".png", ".gif", ".webp"];
+
+/// Configured thumbnail size (longest side, in pixels)
+pub fn thumbnail_size() -> u32 {
+    cache::get_meta(SIZE_KEY)
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_THUMBNAIL_SIZE)
+}
+
+/// Limit a requested thumbnail size to a sane range
+pub fn clamp_size(size: u32) -> u32 {
+    size.clamp(MIN_THUMBNAIL_SIZE, MAX_THUMBNAIL_SIZE)
+}
+
+/// Set the thumbnail size, clamped to a sane range; returns the stored value
+pub fn set_thumbnail_size(size: u32) -> Result<u32> {
+    let size = clamp_size(size);
+    cache::set_meta(SIZE_KEY, &size.to_string())?;
+    Ok(size)
+}
+
+pub fn is_image_ext(ext: &str) -> bool {
+    IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str())
+}
+
+/// Width and height from an image's header; `bytes` may be a prefix of the file
+pub fn dimensions(bytes: &[u8]) -> Result<(u32, u32)> {
+    Ok(ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_dimensions()?)
+}
+
+/// Size of a `w`x`h` image scaled down to fit in `max`x`max`
+pub fn fit(w: u32, h: u32, max: u32) -> (u32, u32) {
+    if w <= max && h <= max {
+        return (w, h);
+    }
+    let longest = w.max(h) as u64;
+    let scale = |v: u32| ((v as u64 * max as u64 / longest) as u32).max(1);
+    (scale(w), scale(h))
+}
+
+/// A generated JPEG thumbnail
+pub struct Thumbnail {
+    pub bytes: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub source_width: u32,
+    pub source_height: u32,
+}
+
+/// Decode an image and encode a JPEG thumbnail fitting in `max`x`max`.
+/// Transparent areas are flattened onto white.
+pub fn generate(bytes: &[u8], max: u32) -> Result<Thumbnail> {
+    let image = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .decode()?;
+    let (source_width, source_height) = image.dimensions();
+
+    let (width, height) = fit(source_width, source_height, max);
+    let thumb = if (width, height) == (source_width, source_height) {
+        image
+    } else {
+        image.thumbnail_exact(width, height)
+    };
+
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY).encode_image(&flatten(&thumb))?;
+
+    Ok(Thumbnail {
+        bytes: out,
+        width,
+        height,
+        source_width,
+        source_height,
+    })
+}
+
+fn flatten(image: &DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+/// A thumbnail in the media store
+#[derive(Debug, Serialize, Clone)]
+pub struct StoredThumbnail {
+    #[serde(skip)]
+    pub md5: String,
+    #[serde(skip)]
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    pub source_width: u32,
+    pub source_height: u32,
+}
+
+fn lookup(source_md5: &str, size: u32) -> Result<Option<StoredThumbnail>> {
+    let row = {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+        conn.query_row(
+            r#"SELECT thumb_md5, width, height, source_width, source_height
+               FROM media_thumbnails WHERE source_md5 = ?1 AND size = ?2"#,
+            params![source_md5, size],
+            |r| Ok((r.get::<_, String>(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+        )
+        .optional()?
+    };
+
+    let Some((thumb_md5, width, height, source_width, source_height)) = row else {
+        return Ok(None);
+    };
+    // Thumbnails can be evicted like any other file
+    let path = media_store::path_for(&thumb_md5, ".jpg")?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    media_store::track(&thumb_md5, ".jpg", MediaKind::Thumbnail)?;
+
+    Ok(Some(StoredThumbnail { md5: thumb_md5, path, width, height, source_width, source_height }))
+}
+
+/// Generate (or reuse) a thumbnail for a file in the media store
+pub async fn thumbnail_for_stored(source_md5: &str, source_path: PathBuf, size: u32) -> Result<StoredThumbnail> {
+    if let Some(thumb) = lookup(source_md5, size)? {
+        return Ok(thumb);
+    }
+    let bytes = tokio::fs::read(&source_path).await?;
+    store_thumbnail(source_md5, size, bytes).await
+}
+
+async fn store_thumbnail(source_md5: &str, size: u32, bytes: Vec<u8>) -> Result<StoredThumbnail> {
+    let thumb = tokio::task::spawn_blocking(move || generate(&bytes, size)).await??;
+
+    let stored = media_store::store(&thumb.bytes, None, ".jpg")?;
+    media_store::track(&stored.md5, ".jpg", MediaKind::Thumbnail)?;
+
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute(
+        r#"INSERT OR REPLACE INTO media_thumbnails
+           (source_md5, size, thumb_md5, width, height, source_width, source_height)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+        params![source_md5, size, stored.md5, thumb.width, thumb.height, thumb.source_width, thumb.source_height],
+    )?;
+
+    Ok(StoredThumbnail {
+        md5: stored.md5,
+        path: stored.path,
+        width: thumb.width,
+        height: thumb.height,
+        source_width: thumb.source_width,
+        source_height: thumb.source_height,
+    })
+}
+
+/// Thumbnail of a remote image, downloading the image on a miss
+pub async fn thumbnail_for_url(url: &str, md5: Option<&str>, thread: ThreadRef<'_>, size: u32) -> Result<StoredThumbnail> {
+    if !is_image_ext(&media_store::url_ext(url)) {
+        bail!("Can't generate a thumbnail for {}", url);
+    }
+    let source = media_protocol::resolve(url, md5, thread).await?;
+    let thumb = thumbnail_for_stored(&source.md5, source.path, size).await?;
+    remember_dimensions(url, thumb.source_width, thumb.source_height)?;
+    Ok(thumb)
+}
+
+fn remember_dimensions(url: &str, width: u32, height: u32) -> Result<()> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    conn.execute(
+        "INSERT OR REPLACE INTO media_dimensions (url, width, height) VALUES (?1, ?2, ?3)",
+        params![url, width, height],
+    )?;
+    Ok(())
+}
+
+fn known_dimensions(urls: &[String]) -> Result<HashMap<String, (u32, u32)>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+    let mut stmt = conn.prepare("SELECT width, height FROM media_dimensions WHERE url = ?1")?;
+
+    let mut known = HashMap::new();
+    for url in urls {
+        if let Some(dims) = stmt.query_row(params![url], |r| Ok((r.get(0)?, r.get(1)?))).optional()? {
+            known.insert(url.clone(), dims);
+        }
+    }
+    Ok(known)
+}
+
+/// Dimensions of a remote image: from the media store when it's cached,
+/// otherwise from the first bytes of the file
+async fn probe(client: &Client, url: &str) -> Result<(u32, u32)> {
+    if let Some(stored) = media_store::lookup_url(url)? {
+        let bytes = tokio::fs::read(&stored.path).await?;
+        return dimensions(&bytes);
+    }
+
+    let mut response = client
+        .get(url)
+        .header(RANGE, format!("bytes=0-{}", PROBE_BYTES - 1))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    // Servers ignoring the range send the whole file; stop reading early
+    let mut head = Vec::with_capacity(PROBE_BYTES);
+    while let Some(chunk) = response.chunk().await? {
+        head.extend_from_slice(&chunk);
+        if head.len() >= PROBE_BYTES {
+            break;
+        }
+    }
+    dimensions(&head).map_err(|e| anyhow!("{}: {}", url, e))
+}
+
+/// Posts and catalog entries whose media dimensions can be filled in
+pub trait MediaDimensions {
+    /// `tim` and `ext` of an image lacking dimensions
+    fn missing_dimensions(&self) -> Option<(&str, &str)>;
+    fn set_dimensions(&mut self, w: u32, h: u32, tn_w: u32, tn_h: u32);
+}
+
+macro_rules! impl_media_dimensions {
+    ($ty:ty) => {
+        impl MediaDimensions for $ty {
+            fn missing_dimensions(&self) -> Option<(&str, &str)> {
+                match (&self.tim, &self.ext, self.w, self.h) {
+                    (Some(tim), Some(ext), None, _) | (Some(tim), Some(ext), _, None) if is_image_ext(ext) => {
+                        Some((tim, ext))
+                    }
+                    _ => None,
+                }
+            }
+
+            fn set_dimensions(&mut self, w: u32, h: u32, tn_w: u32, tn_h: u32) {
+                self.w = Some(w);
+                self.h = Some(h);
+                self.tn_w = self.tn_w.or(Some(tn_w));
+                self.tn_h = self.tn_h.or(Some(tn_h));
+            }
+        }
+    };
+}
+
+impl_media_dimensions!(Post);
+impl_media_dimensions!(CatalogThread);
+
+/// Fill in `w`/`h`/`tn_w`/`tn_h` for images that lack them. Failures leave
+/// the fields empty; this never fails the page load.
+pub async fn fill_dimensions<T: MediaDimensions>(
+    client: &Client,
+    items: &mut [T],
+    media_url: impl Fn(&str, &str) -> String,
+) {
+    let urls: Vec<Option<String>> = items
+        .iter()
+        .map(|item| item.missing_dimensions().map(|(tim, ext)| media_url(tim, ext)))
+        .collect();
+    let wanted: Vec<String> = urls.iter().flatten().cloned().collect();
+    if wanted.is_empty() {
+        return;
+    }
+
+    let mut known = known_dimensions(&wanted).unwrap_or_else(|e| {
+        eprintln!("Failed to read media dimensions: {}", e);
+        HashMap::new()
+    });
+
+    // Probe the rest a few at a time
+    let permits = Arc::new(Semaphore::new(PROBE_CONCURRENCY));
+    let mut probes = JoinSet::new();
+    for url in wanted.into_iter().filter(|u| !known.contains_key(u)) {
+        let client = client.clone();
+        let permits = permits.clone();
+        probes.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let dims = probe(&client, &url).await;
+            (url, dims)
+        });
+    }
+    while let Some(joined) = probes.join_next().await {
+        match joined {
+            Ok((url, Ok((w, h)))) => {
+                if let Err(e) = remember_dimensions(&url, w, h) {
+                    eprintln!("Failed to save media dimensions: {}", e);
+                }
+                known.insert(url, (w, h));
+            }
+            Ok((_, Err(e))) => eprintln!("Failed to probe image: {}", e),
+            Err(e) => eprintln!("Image probe panicked: {}", e),
+        }
+    }
+
+    let size = thumbnail_size();
+    for (item, url) in items.iter_mut().zip(urls) {
+        if let Some(&(w, h)) = url.as_ref().and_then(|u| known.get(u)) {
+            let (tn_w, tn_h) = fit(w, h, size);
+            item.set_dimensions(w, h, tn_w, tn_h);
+        }
+    }
+}
+
+/// Metadata and a thumbnail for a local file, e.g. one picked for posting
+#[derive(Debug, Serialize)]
+pub struct LocalImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub size: u64,
+    pub md5: String,
+    pub thumbnail: StoredThumbnail,
+    /// `chanmedia://` URL of the thumbnail
+    pub thumbnail_url: String,
+}
+
+/// Dimensions and a thumbnail of a local file. The thumbnail is stored by
+/// the file's content; the file itself is left where it is.
+pub async fn local_image_info(path: PathBuf, size: u32) -> Result<LocalImageInfo> {
+    let bytes = tokio::fs::read(&path).await?;
+    let md5 = media_store::md5_base64(&bytes);
+    let file_size = bytes.len() as u64;
+
+    let thumbnail = match lookup(&md5, size)? {
+        Some(thumb) => thumb,
+        None => store_thumbnail(&md5, size, bytes).await?,
+    };
+
+    Ok(LocalImageInfo {
+        width: thumbnail.source_width,
+        height: thumbnail.source_height,
+        size: file_size,
+        md5,
+        thumbnail_url: media_protocol::stored_url(&media_store::relative_path(&thumbnail.md5, ".jpg")?),
+        thumbnail,
+    })
+}
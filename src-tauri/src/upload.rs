@@ -0,0 +1,387 @@
+/**
+ * Upload Preparation
+ *
+ * Runs on a file picked for `submit_post` before it is sent:
+ *
+ * - strips EXIF/GPS/XMP/IPTC segments and comments from JPEGs and text/EXIF
+ *   chunks from PNGs, without re-encoding. JPEGs rotated by an EXIF
+ *   orientation tag are re-encoded upright instead, since dropping the tag
+ *   would turn them sideways.
+ * - optionally gives the file a random, timestamp-like name
+ * - optionally appends random bytes so the site's duplicate-file check
+ *   doesn't recognize a repost (decoders ignore data after the end marker)
+ * - validates the result against the board's limits and reports every
+ *   problem as a structured `UploadError` instead of letting the site reject
+ *   the post
+ */
+
+use image::codecs::jpeg::JpegEncoder;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader};
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+
+use crate::board_capabilities::BoardCapabilities;
+use crate::thumbnails;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Largest image side 4chan accepts on any board
+const MAX_DIMENSION: u32 = 10_000;
+
+/// Quality used when a JPEG has to be re-encoded to bake in its orientation
+const REENCODE_QUALITY: u8 = 92;
+
+/// Upper bound for appended noise, so a typo can't blow up the upload
+const MAX_NOISE_BYTES: usize = 4096;
+
+/// Sanitization steps requested for an upload; all off by default
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SanitizeOptions {
+    pub strip_metadata: bool,
+    pub randomize_filename: bool,
+    /// Number of random bytes appended to the file (0 = none)
+    pub noise_bytes: usize,
+    /// Limits to validate against instead of the board's defaults
+    pub limits: Option<UploadLimits>,
+}
+
+/// What a board accepts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadLimits {
+    pub max_filesize: u64,
+    pub max_webm_filesize: u64,
+    /// Lowercase extensions with the dot, e.g. `.jpg`
+    pub extensions: Vec<String>,
+    pub max_width: u32,
+    pub max_height: u32,
+    #[serde(default)]
+    pub min_width: u32,
+    #[serde(default)]
+    pub min_height: u32,
+}
+
+impl From<&BoardCapabilities> for UploadLimits {
+    fn from(board: &BoardCapabilities) -> Self {
+        Self {
+            max_filesize: board.max_filesize,
+            max_webm_filesize: board.max_webm_filesize,
+            extensions: extensions_for(&board.id),
+            max_width: MAX_DIMENSION,
+            max_height: MAX_DIMENSION,
+            min_width: board.min_image_width,
+            min_height: board.min_image_height,
+        }
+    }
+}
+
+/// File types `board` accepts (not part of `boards.json`)
+fn extensions_for(board: &str) -> Vec<String> {
+    let mut extensions: Vec<String> = [".jpg", ".png", ".gif", ".webm"].iter().map(|e| e.to_string()).collect();
+    match board {
+        "f" => extensions = vec![".swf".to_string()],
+        "po" => extensions.push(".pdf".to_string()),
+        _ => {}
+    }
+    extensions
+}
+
+/// 4chan's published upload limits for `board`, for when its record from
+/// `boards.json` isn't available
+pub fn limits_for(board: &str) -> UploadLimits {
+    let extensions = extensions_for(board);
+
+    let (max_filesize, max_webm_filesize) = match board {
+        "wsg" | "gif" => (4 * MIB, 6 * MIB),
+        "f" => (10 * MIB, 0),
+        "hr" | "hc" | "w" | "wg" => (8 * MIB, 3 * MIB),
+        _ => (4 * MIB, 3 * MIB),
+    };
+
+    UploadLimits {
+        max_filesize,
+        max_webm_filesize,
+        extensions,
+        max_width: MAX_DIMENSION,
+        max_height: MAX_DIMENSION,
+        min_width: 0,
+        min_height: 0,
+    }
+}
+
+/// A problem that would get the upload rejected
+#[derive(Debug, Clone, Serialize, Deserialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum UploadError {
+    #[error("File is too large ({size} bytes, the limit is {max})")]
+    TooLarge { size: u64, max: u64 },
+    #[error("{ext} files are not allowed on /{board}/")]
+    ExtensionNotAllowed { ext: String, board: String, allowed: Vec<String> },
+    #[error("Image is too large ({width}x{height}, the limit is {max_width}x{max_height})")]
+    DimensionsTooLarge { width: u32, height: u32, max_width: u32, max_height: u32 },
+    #[error("Image is too small ({width}x{height}, the minimum is {min_width}x{min_height})")]
+    DimensionsTooSmall { width: u32, height: u32, min_width: u32, min_height: u32 },
+    #[error("File can't be read: {message}")]
+    Unreadable { message: String },
+}
+
+/// A file ready to be attached to a post
+pub struct PreparedUpload {
+    pub bytes: Vec<u8>,
+    pub filename: String,
+    pub mime: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// Sanitize `bytes` as requested and validate the result against `limits`
+/// (overridden by `options.limits`)
+pub fn prepare(
+    board: &str,
+    bytes: Vec<u8>,
+    filename: &str,
+    options: &SanitizeOptions,
+    limits: UploadLimits,
+) -> Result<PreparedUpload, Vec<UploadError>> {
+    let ext = file_ext(filename);
+
+    let mut bytes = if options.strip_metadata {
+        strip_metadata(bytes, &ext).map_err(|message| vec![UploadError::Unreadable { message }])?
+    } else {
+        bytes
+    };
+
+    if options.noise_bytes > 0 {
+        let mut noise = vec![0; options.noise_bytes.min(MAX_NOISE_BYTES)];
+        rand::thread_rng().fill_bytes(&mut noise);
+        bytes.extend_from_slice(&noise);
+    }
+
+    let filename = if options.randomize_filename {
+        format!("{}{}", random_name(), ext)
+    } else {
+        filename.to_string()
+    };
+
+    let limits = options.limits.clone().unwrap_or(limits);
+    let (width, height) = validate(board, &bytes, &ext, &limits)?;
+
+    Ok(PreparedUpload {
+        mime: mime_guess::from_path(&filename).first_or_octet_stream().to_string(),
+        bytes,
+        filename,
+        width,
+        height,
+    })
+}
+
+/// Check a file against `limits`, returning its dimensions if it's an image
+pub fn validate(
+    board: &str,
+    bytes: &[u8],
+    ext: &str,
+    limits: &UploadLimits,
+) -> Result<(Option<u32>, Option<u32>), Vec<UploadError>> {
+    let mut errors = Vec::new();
+
+    if !limits.extensions.iter().any(|allowed| allowed == ext) {
+        errors.push(UploadError::ExtensionNotAllowed {
+            ext: ext.to_string(),
+            board: board.to_string(),
+            allowed: limits.extensions.clone(),
+        });
+    }
+
+    let size = bytes.len() as u64;
+    let max = if ext == ".webm" { limits.max_webm_filesize } else { limits.max_filesize };
+    if size > max {
+        errors.push(UploadError::TooLarge { size, max });
+    }
+
+    let mut dimensions = (None, None);
+    if thumbnails::is_image_ext(ext) {
+        match thumbnails::dimensions(bytes) {
+            Ok((width, height)) => {
+                if width > limits.max_width || height > limits.max_height {
+                    errors.push(UploadError::DimensionsTooLarge {
+                        width,
+                        height,
+                        max_width: limits.max_width,
+                        max_height: limits.max_height,
+                    });
+                }
+                if width < limits.min_width || height < limits.min_height {
+                    errors.push(UploadError::DimensionsTooSmall {
+                        width,
+                        height,
+                        min_width: limits.min_width,
+                        min_height: limits.min_height,
+                    });
+                }
+                dimensions = (Some(width), Some(height));
+            }
+            Err(e) => errors.push(UploadError::Unreadable { message: e.to_string() }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(dimensions)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Lowercase extension with the dot; `.jpeg` is reported as `.jpg`
+pub fn file_ext(filename: &str) -> String {
+    let ext = filename
+        .rsplit_once('.')
+        .map(|(_, ext)| format!(".{}", ext.to_lowercase()))
+        .unwrap_or_default();
+    if ext == ".jpeg" {
+        ".jpg".to_string()
+    } else {
+        ext
+    }
+}
+
+/// A name that looks like a 4chan upload timestamp from the past month
+fn random_name() -> String {
+    let mut rng = rand::thread_rng();
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let ms = now_ms - rng.gen_range(0..30 * 24 * 3600 * 1000);
+    format!("{}{:03}", ms, rng.gen_range(0..1000))
+}
+
+/// Remove metadata from JPEG and PNG files; other types pass through
+pub fn strip_metadata(bytes: Vec<u8>, ext: &str) -> Result<Vec<u8>, String> {
+    match ext {
+        ".jpg" => match jpeg_orientation(&bytes) {
+            Some(orientation) if orientation != Orientation::NoTransforms => reencode_upright(&bytes, orientation),
+            _ => strip_jpeg(&bytes),
+        },
+        ".png" => strip_png(&bytes),
+        _ => Ok(bytes),
+    }
+}
+
+fn jpeg_orientation(bytes: &[u8]) -> Option<Orientation> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_decoder()
+        .ok()?;
+    decoder.orientation().ok()
+}
+
+fn reencode_upright(bytes: &[u8], orientation: Orientation) -> Result<Vec<u8>, String> {
+    let mut image = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?
+        .decode()
+        .map_err(|e| e.to_string())?;
+    image.apply_orientation(orientation);
+
+    // The encoder writes a bare JFIF header, so no metadata survives
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, REENCODE_QUALITY)
+        .encode_image(&DynamicImage::ImageRgb8(image.to_rgb8()))
+        .map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+/// Copy a JPEG's segments, dropping APP1 (EXIF, XMP), APP12, APP13 (IPTC)
+/// and comments. JFIF, ICC profiles (APP2) and the Adobe marker (APP14) are
+/// kept because they affect how the image decodes.
+fn strip_jpeg(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    const DROPPED: &[u8] = &[0xE1, 0xEC, 0xED, 0xFE];
+
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return Err("Not a JPEG file".to_string());
+    }
+
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&bytes[..2]);
+    let mut pos = 2;
+
+    while pos < bytes.len() {
+        if bytes[pos] != 0xFF {
+            return Err(format!("Corrupt JPEG marker at byte {}", pos));
+        }
+        // Markers may be padded with extra 0xFF bytes
+        let mut marker_pos = pos + 1;
+        while bytes.get(marker_pos) == Some(&0xFF) {
+            marker_pos += 1;
+        }
+        let Some(&marker) = bytes.get(marker_pos) else {
+            break;
+        };
+
+        match marker {
+            // Start of scan: entropy-coded data follows, copy the rest as is
+            0xDA => {
+                out.extend_from_slice(&bytes[pos..]);
+                return Ok(out);
+            }
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => {
+                out.extend_from_slice(&bytes[pos..=marker_pos]);
+                pos = marker_pos + 1;
+            }
+            0xD9 => {
+                out.extend_from_slice(&bytes[pos..=marker_pos]);
+                return Ok(out);
+            }
+            _ => {
+                let len = bytes
+                    .get(marker_pos + 1..marker_pos + 3)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+                    .ok_or("Truncated JPEG segment")?;
+                let end = marker_pos + 1 + len;
+                if len < 2 || end > bytes.len() {
+                    return Err("Truncated JPEG segment".to_string());
+                }
+                if !DROPPED.contains(&marker) {
+                    out.extend_from_slice(&bytes[pos..end]);
+                }
+                pos = end;
+            }
+        }
+    }
+
+    Err("JPEG has no image data".to_string())
+}
+
+/// Copy a PNG's chunks up to IEND, dropping text, EXIF and timestamp chunks
+fn strip_png(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    const DROPPED: &[&[u8]] = &[b"tEXt", b"zTXt", b"iTXt", b"eXIf", b"tIME"];
+
+    if !bytes.starts_with(SIGNATURE) {
+        return Err("Not a PNG file".to_string());
+    }
+
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(SIGNATURE);
+    let mut pos = SIGNATURE.len();
+
+    while pos + 8 <= bytes.len() {
+        let len = u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let kind = &bytes[pos + 4..pos + 8];
+        // Length, type, data and CRC
+        let end = pos + 12 + len;
+        if end > bytes.len() {
+            return Err("Truncated PNG chunk".to_string());
+        }
+        if !DROPPED.contains(&kind) {
+            out.extend_from_slice(&bytes[pos..end]);
+        }
+        if kind == b"IEND" {
+            return Ok(out);
+        }
+        pos = end;
+    }
+
+    Err("PNG has no IEND chunk".to_string())
+}
@@ -1,22 +1,50 @@
 /**
  * Video Server Module
- * 
+ *
  * Runs a local HTTP server to serve cached videos, bypassing browser security restrictions.
  * This approach is more reliable than Tauri's asset protocol for video streaming.
+ *
+ * The server runs on the shared tokio runtime (hyper, HTTP/1.1 with keep-alive)
+ * and accepts at most `MAX_CONNECTIONS` connections at a time. Files support
+ * HEAD, conditional requests (ETag / Last-Modified) and RFC 7233 byte ranges,
+ * including suffix and multi-range requests. `/stream/...` paths serve videos
+ * that are still being downloaded (see `video_stream`).
  */
 
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use hyper::body::Bytes;
+use hyper::header::{
+    HeaderValue, ACCEPT_RANGES, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+    ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_MAX_AGE, ALLOW,
+    CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE,
+    LAST_MODIFIED, RANGE,
+};
+use hyper::http::response::Builder;
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::convert::Infallible;
+use std::io::SeekFrom;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU16, Ordering};
 use std::sync::Arc;
-use std::thread;
-use tauri::{AppHandle, Manager};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::Semaphore;
+
+use crate::video_stream::{self, VideoStream};
 
 static VIDEO_SERVER_PORT: AtomicU16 = AtomicU16::new(0);
 
+/// Connections served concurrently; further ones wait in the accept queue
+const MAX_CONNECTIONS: usize = 32;
+
+/// Requests asking for more ranges than this get the whole file instead
+const MAX_RANGES: usize = 16;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
 /// Get the video server base URL
 pub fn get_video_server_url() -> Option<String> {
     let port = VIDEO_SERVER_PORT.load(Ordering::SeqCst);
@@ -29,87 +57,93 @@ pub fn get_video_server_url() -> Option<String> {
 
 /// Start the video server on a random available port
 pub fn start_video_server(cache_dir: PathBuf) -> Result<u16, String> {
-    // Find an available port
+    // Bind synchronously so the port is known before the app asks for it
     let listener = TcpListener::bind("127.0.0.1:0")
         .map_err(|e| format!("Failed to bind: {}", e))?;
-    
+    listener.set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure socket: {}", e))?;
+
     let port = listener.local_addr()
         .map_err(|e| format!("Failed to get addr: {}", e))?
         .port();
-    
+
     VIDEO_SERVER_PORT.store(port, Ordering::SeqCst);
-    
+
     println!("Starting video server on http://127.0.0.1:{}", port);
-    
-    // Spawn server thread
-    let cache_dir_clone = cache_dir.clone();
-    thread::spawn(move || {
-        for stream in listener.incoming() {
-            match stream {
-                Ok(stream) => {
-                    let cache_dir = cache_dir_clone.clone();
-                    thread::spawn(move || {
-                        if let Err(e) = handle_request(stream, &cache_dir) {
-                            eprintln!("Request error: {}", e);
-                        }
-                    });
-                }
-                Err(e) => eprintln!("Connection failed: {}", e),
+
+    let root = Arc::new(cache_dir);
+    tauri::async_runtime::spawn(async move {
+        let listener = match tokio::net::TcpListener::from_std(listener) {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("Video server failed to start: {}", e);
+                return;
             }
+        };
+
+        let workers = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+        loop {
+            let Ok(permit) = workers.clone().acquire_owned().await else {
+                break;
+            };
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    eprintln!("Connection failed: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+
+            let root = root.clone();
+            tauri::async_runtime::spawn(async move {
+                let service = service_fn(move |req| handle_request(req, root.clone()));
+                let connection = Http::new()
+                    .http1_keep_alive(true)
+                    .serve_connection(stream, service);
+                if let Err(e) = connection.await {
+                    eprintln!("Request error: {}", e);
+                }
+                drop(permit);
+            });
         }
     });
-    
+
     Ok(port)
 }
 
-fn handle_request(mut stream: TcpStream, cache_dir: &PathBuf) -> Result<(), String> {
-    let mut buffer = [0; 4096];
-    let bytes_read = stream.read(&mut buffer)
-        .map_err(|e| format!("Failed to read: {}", e))?;
-    
-    let request = String::from_utf8_lossy(&buffer[..bytes_read]);
-    
-    // Parse request line
-    let first_line = request.lines().next().unwrap_or("");
-    let parts: Vec<&str> = first_line.split_whitespace().collect();
-    
-    if parts.len() < 2 {
-        return send_error(&mut stream, 400, "Bad Request");
-    }
-    
-    let path = parts[1];
-    
-    // Handle CORS preflight
-    if parts[0] == "OPTIONS" {
-        return send_cors_preflight(&mut stream);
-    }
-    
-    // Only handle GET
-    if parts[0] != "GET" {
-        return send_error(&mut stream, 405, "Method Not Allowed");
-    }
-    
-    // Get filename from path
-    let filename = path.trim_start_matches('/');
-    if filename.is_empty() || filename.contains("..") {
-        return send_error(&mut stream, 400, "Invalid path");
-    }
-    
-    let file_path = cache_dir.join(filename);
-    
-    // Check if file exists
-    if !file_path.exists() {
-        return send_error(&mut stream, 404, "Not Found");
-    }
-    
-    // Read file and send response
-    let mut file = File::open(&file_path)
-        .map_err(|e| format!("Failed to open file: {}", e))?;
-    
-    let metadata = file.metadata()
-        .map_err(|e| format!("Failed to get metadata: {}", e))?;
-    
-    let content_type = if filename.ends_with(".webm") {
+async fn handle_request(req: Request<Body>, root: Arc<PathBuf>) -> Result<Response<Body>, Infallible> {
+    let mut response = match *req.method() {
+        Method::OPTIONS => cors_preflight(),
+        Method::GET | Method::HEAD => {
+            let path = req.uri().path().trim_start_matches('/');
+            if path.is_empty() || path.contains('\\') || path.split('/').any(|s| s == "..") {
+                error(StatusCode::BAD_REQUEST, "Invalid path")
+            } else if let Some(name) = path.strip_prefix("stream/") {
+                // Videos still downloading are streamed while they are fetched
+                serve_stream(&req, name).await
+            } else {
+                serve_file(&req, &root.join(path)).await
+            }
+        }
+        _ => {
+            let mut response = error(StatusCode::METHOD_NOT_ALLOWED, "Method Not Allowed");
+            response.headers_mut().insert(ALLOW, HeaderValue::from_static("GET, HEAD, OPTIONS"));
+            response
+        }
+    };
+
+    let headers = response.headers_mut();
+    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    headers.insert(
+        ACCESS_CONTROL_EXPOSE_HEADERS,
+        HeaderValue::from_static("Accept-Ranges, Content-Length, Content-Range, ETag"),
+    );
+    Ok(response)
+}
+
+fn content_type(filename: &str) -> &'static str {
+    if filename.ends_with(".webm") {
         "video/webm"
     } else if filename.ends_with(".mp4") {
         "video/mp4"
@@ -117,104 +151,390 @@ fn handle_request(mut stream: TcpStream, cache_dir: &PathBuf) -> Result<(), Stri
         "image/gif"
     } else {
         "application/octet-stream"
+    }
+}
+
+/// Serve a file from the media directory
+async fn serve_file(req: &Request<Body>, path: &Path) -> Response<Body> {
+    let meta = match tokio::fs::metadata(path).await {
+        Ok(meta) if meta.is_file() => meta,
+        _ => return error(StatusCode::NOT_FOUND, "Not Found"),
     };
-    
-    // Parse Range header for seeking support
-    let range_header = request.lines()
-        .find(|l| l.to_lowercase().starts_with("range:"))
-        .map(|l| l.trim_start_matches(|c: char| !c.is_ascii_digit()));
-    
-    let file_size = metadata.len();
-    
-    let (start, end, status) = if let Some(range) = range_header {
-        // Parse range: bytes=0-1234
-        let range = range.trim().trim_start_matches("bytes=");
-        let parts: Vec<&str> = range.split('-').collect();
-        let start: u64 = parts.get(0).and_then(|s| s.parse().ok()).unwrap_or(0);
-        let end: u64 = parts.get(1)
-            .and_then(|s| if s.is_empty() { None } else { s.parse().ok() })
-            .unwrap_or(file_size - 1)
-            .min(file_size - 1);
-        (start, end, 206)
+    let size = meta.len();
+    let modified = meta.modified().ok().map(DateTime::<Utc>::from);
+    let etag = format!("\"{:x}-{:x}\"", size, modified.map(|m| m.timestamp()).unwrap_or(0));
+    let content_type = content_type(&path.to_string_lossy());
+
+    let mut builder = Response::builder()
+        .header(ACCEPT_RANGES, "bytes")
+        .header(ETAG, &etag);
+    if let Some(modified) = modified {
+        builder = builder.header(LAST_MODIFIED, http_date(modified));
+    }
+
+    if not_modified(req, &etag, modified) {
+        return empty(builder.status(StatusCode::NOT_MODIFIED));
+    }
+
+    let ranges = if if_range_matches(req, &etag, modified) {
+        parse_range(req.headers().get(RANGE).and_then(|v| v.to_str().ok()), size)
     } else {
-        (0, file_size - 1, 200)
+        RangeRequest::Full
     };
-    
-    let content_length = end - start + 1;
-    
-    // Seek to start position
-    use std::io::Seek;
-    file.seek(std::io::SeekFrom::Start(start))
-        .map_err(|e| format!("Failed to seek: {}", e))?;
-    
-    // Build response
-    let status_text = if status == 206 { "Partial Content" } else { "OK" };
-    let mut response = format!(
-        "HTTP/1.1 {} {}\r\n\
-        Content-Type: {}\r\n\
-        Content-Length: {}\r\n\
-        Accept-Ranges: bytes\r\n\
-        Access-Control-Allow-Origin: *\r\n\
-        Access-Control-Allow-Methods: GET, OPTIONS\r\n\
-        Access-Control-Allow-Headers: Range\r\n\
-        Connection: close\r\n",
-        status, status_text, content_type, content_length
-    );
-    
-    if status == 206 {
-        response.push_str(&format!(
-            "Content-Range: bytes {}-{}/{}\r\n",
-            start, end, file_size
-        ));
+    let head = req.method() == Method::HEAD;
+    let path = path.to_path_buf();
+
+    match ranges {
+        RangeRequest::Unsatisfiable => range_not_satisfiable(builder, size),
+        RangeRequest::Full => {
+            let builder = builder
+                .status(StatusCode::OK)
+                .header(CONTENT_TYPE, content_type)
+                .header(CONTENT_LENGTH, size);
+            if head || size == 0 {
+                empty(builder)
+            } else {
+                body(builder, file_body(path, vec![Segment::File(0, size)]))
+            }
+        }
+        RangeRequest::Partial(ranges) if ranges.len() == 1 => {
+            let (start, end) = ranges[0];
+            let builder = builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(CONTENT_TYPE, content_type)
+                .header(CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, size))
+                .header(CONTENT_LENGTH, end - start + 1);
+            if head {
+                empty(builder)
+            } else {
+                body(builder, file_body(path, vec![Segment::File(start, end - start + 1)]))
+            }
+        }
+        RangeRequest::Partial(ranges) => {
+            // multipart/byteranges, one part per requested range
+            let boundary = format!(
+                "chandesk-{:x}",
+                SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos()
+            );
+            let mut segments = Vec::new();
+            let mut length = 0;
+            for (start, end) in ranges {
+                let part_header = format!(
+                    "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                    boundary, content_type, start, end, size
+                );
+                length += part_header.len() as u64 + (end - start + 1);
+                segments.push(Segment::Data(Bytes::from(part_header)));
+                segments.push(Segment::File(start, end - start + 1));
+            }
+            let trailer = format!("\r\n--{}--\r\n", boundary);
+            length += trailer.len() as u64;
+            segments.push(Segment::Data(Bytes::from(trailer)));
+
+            let builder = builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(CONTENT_TYPE, format!("multipart/byteranges; boundary={}", boundary))
+                .header(CONTENT_LENGTH, length);
+            if head {
+                empty(builder)
+            } else {
+                body(builder, file_body(path, segments))
+            }
+        }
+    }
+}
+
+/// Serve a video that is still being fetched: from the part file when the
+/// requested range is (nearly) downloaded, otherwise straight from upstream
+async fn serve_stream(req: &Request<Body>, name: &str) -> Response<Body> {
+    let Some(video) = video_stream::get(name) else {
+        return error(StatusCode::NOT_FOUND, "Not Found");
+    };
+
+    let total = match video.wait_for_length().await {
+        Ok(total) => total,
+        Err(e) => {
+            eprintln!("Video stream unavailable: {}", e);
+            return error(StatusCode::BAD_GATEWAY, "Bad Gateway");
+        }
+    };
+
+    let head = req.method() == Method::HEAD;
+    let builder = Response::builder()
+        .header(CONTENT_TYPE, content_type(name))
+        .header(ACCEPT_RANGES, "bytes");
+
+    // Without a length we can't answer ranges; stream the whole file
+    let Some(total) = total else {
+        let builder = builder.status(StatusCode::OK);
+        return if head { empty(builder) } else { body(builder, stream_body(video, 0, None)) };
+    };
+
+    let (builder, start, len) = match parse_range(req.headers().get(RANGE).and_then(|v| v.to_str().ok()), total) {
+        RangeRequest::Unsatisfiable => return range_not_satisfiable(builder, total),
+        RangeRequest::Partial(ranges) if ranges.len() == 1 => {
+            let (start, end) = ranges[0];
+            let builder = builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, total));
+            (builder, start, end - start + 1)
+        }
+        // Multiple ranges of a file still downloading: send it whole
+        _ => (builder.status(StatusCode::OK), 0, total),
+    };
+
+    let builder = builder.header(CONTENT_LENGTH, len);
+    if head || len == 0 {
+        empty(builder)
+    } else {
+        body(builder, stream_body(video, start, Some(len)))
+    }
+}
+
+/// A piece of a file response body
+enum Segment {
+    Data(Bytes),
+    /// `(offset, length)` within the file
+    File(u64, u64),
+}
+
+/// Body fed from `path` by a background task
+fn file_body(path: PathBuf, segments: Vec<Segment>) -> Body {
+    let (mut tx, body) = Body::channel();
+
+    tauri::async_runtime::spawn(async move {
+        let result: anyhow::Result<()> = async {
+            let mut file = tokio::fs::File::open(&path).await?;
+            for segment in segments {
+                match segment {
+                    Segment::Data(data) => {
+                        if tx.send_data(data).await.is_err() {
+                            return Ok(());
+                        }
+                    }
+                    Segment::File(start, len) => {
+                        file.seek(SeekFrom::Start(start)).await?;
+                        let mut remaining = len;
+                        while remaining > 0 {
+                            let mut buf = vec![0; remaining.min(CHUNK_SIZE as u64) as usize];
+                            let read = file.read(&mut buf).await?;
+                            if read == 0 {
+                                anyhow::bail!("{} shrank while being served", path.display());
+                            }
+                            buf.truncate(read);
+                            remaining -= read as u64;
+                            if tx.send_data(Bytes::from(buf)).await.is_err() {
+                                // Player went away (e.g. seeked elsewhere)
+                                return Ok(());
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            eprintln!("Failed to serve {}: {}", path.display(), e);
+            tx.abort();
+        }
+    });
+
+    body
+}
+
+/// Body fed from a video stream, from disk or forwarded from upstream
+fn stream_body(video: Arc<VideoStream>, start: u64, len: Option<u64>) -> Body {
+    let (mut tx, body) = Body::channel();
+
+    tauri::async_runtime::spawn(async move {
+        let result: anyhow::Result<()> = async {
+            match len {
+                Some(len) if !video.serves_from_disk(start) => {
+                    let mut response = video.fetch_upstream(start, start + len - 1).await?;
+                    while let Some(chunk) = response.chunk().await? {
+                        if tx.send_data(chunk).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                _ => {
+                    let mut reader = video.reader(start).await?;
+                    let mut remaining = len.unwrap_or(u64::MAX);
+                    while remaining > 0 {
+                        let max = remaining.min(CHUNK_SIZE as u64) as usize;
+                        let Some(chunk) = reader.next_chunk(max).await? else {
+                            break;
+                        };
+                        remaining -= chunk.len() as u64;
+                        if tx.send_data(Bytes::from(chunk)).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            eprintln!("Video stream error: {}", e);
+            tx.abort();
+        }
+    });
+
+    body
+}
+
+/// Outcome of parsing a `Range` header against a representation of known size
+#[derive(Debug, PartialEq)]
+pub(crate) enum RangeRequest {
+    /// No (usable) Range header: send the whole representation
+    Full,
+    /// Inclusive `(first, last)` byte positions, in request order
+    Partial(Vec<(u64, u64)>),
+    /// Valid header but no range overlaps the representation (416)
+    Unsatisfiable,
+}
+
+/// Parse `bytes=` ranges per RFC 7233 section 2.1: `a-b`, open-ended `a-` and
+/// suffix `-n` specs, comma separated. Syntactically invalid headers are
+/// ignored, as are unknown units.
+pub(crate) fn parse_range(header: Option<&str>, size: u64) -> RangeRequest {
+    let Some(spec) = header.and_then(|v| v.trim().strip_prefix("bytes=")) else {
+        return RangeRequest::Full;
+    };
+
+    let mut specs = 0;
+    let mut ranges = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        specs += 1;
+        let Some((first, last)) = part.split_once('-') else {
+            return RangeRequest::Full;
+        };
+        let (first, last) = (first.trim(), last.trim());
+
+        if first.is_empty() {
+            // Suffix range: the last `n` bytes
+            let Ok(n) = last.parse::<u64>() else {
+                return RangeRequest::Full;
+            };
+            if n > 0 && size > 0 {
+                ranges.push((size.saturating_sub(n), size - 1));
+            }
+            continue;
+        }
+
+        let Ok(first) = first.parse::<u64>() else {
+            return RangeRequest::Full;
+        };
+        let last = if last.is_empty() {
+            u64::MAX
+        } else {
+            match last.parse::<u64>() {
+                Ok(last) if last >= first => last,
+                _ => return RangeRequest::Full,
+            }
+        };
+        if first < size {
+            ranges.push((first, last.min(size - 1)));
+        }
+    }
+
+    if specs == 0 || ranges.len() > MAX_RANGES {
+        RangeRequest::Full
+    } else if ranges.is_empty() {
+        RangeRequest::Unsatisfiable
+    } else {
+        RangeRequest::Partial(ranges)
     }
-    
-    response.push_str("\r\n");
-    
-    stream.write_all(response.as_bytes())
-        .map_err(|e| format!("Failed to write headers: {}", e))?;
-    
-    // Stream file content
-    let mut buffer = vec![0u8; 64 * 1024]; // 64KB chunks
-    let mut remaining = content_length as usize;
-    
-    while remaining > 0 {
-        let to_read = remaining.min(buffer.len());
-        let bytes_read = file.read(&mut buffer[..to_read])
-            .map_err(|e| format!("Failed to read file: {}", e))?;
-        
-        if bytes_read == 0 {
-            break;
+}
+
+/// `If-None-Match` takes precedence over `If-Modified-Since` (RFC 7232 §6)
+fn not_modified(req: &Request<Body>, etag: &str, modified: Option<DateTime<Utc>>) -> bool {
+    let headers = req.headers();
+    if let Some(tags) = headers.get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        // Weak comparison
+        return tags
+            .split(',')
+            .map(|t| t.trim().trim_start_matches("W/"))
+            .any(|t| t == "*" || t == etag);
+    }
+
+    match (headers.get(IF_MODIFIED_SINCE).and_then(|v| v.to_str().ok()), modified) {
+        (Some(since), Some(modified)) => parse_http_date(since)
+            .is_some_and(|since| modified.timestamp() <= since.timestamp()),
+        _ => false,
+    }
+}
+
+/// Whether a Range request still applies given `If-Range` (RFC 7233 §3.2)
+fn if_range_matches(req: &Request<Body>, etag: &str, modified: Option<DateTime<Utc>>) -> bool {
+    let Some(value) = req.headers().get(IF_RANGE).and_then(|v| v.to_str().ok()) else {
+        return true;
+    };
+    let value = value.trim();
+
+    if value.starts_with('"') {
+        // Strong comparison
+        value == etag
+    } else if value.starts_with("W/") {
+        false
+    } else {
+        match (parse_http_date(value), modified) {
+            (Some(date), Some(modified)) => date.timestamp() == modified.timestamp(),
+            _ => false,
         }
-        
-        stream.write_all(&buffer[..bytes_read])
-            .map_err(|e| format!("Failed to write: {}", e))?;
-        
-        remaining -= bytes_read;
     }
-    
-    Ok(())
-}
-
-fn send_error(stream: &mut TcpStream, code: u16, message: &str) -> Result<(), String> {
-    let response = format!(
-        "HTTP/1.1 {} {}\r\n\
-        Content-Type: text/plain\r\n\
-        Content-Length: {}\r\n\
-        Access-Control-Allow-Origin: *\r\n\
-        Connection: close\r\n\r\n{}",
-        code, message, message.len(), message
+}
+
+fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value.trim())
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+fn body(builder: Builder, body: Body) -> Response<Body> {
+    builder
+        .body(body)
+        .unwrap_or_else(|e| error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()))
+}
+
+fn empty(builder: Builder) -> Response<Body> {
+    body(builder, Body::empty())
+}
+
+fn range_not_satisfiable(builder: Builder, size: u64) -> Response<Body> {
+    empty(
+        builder
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(CONTENT_RANGE, format!("bytes */{}", size)),
+    )
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(message.to_string()));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+    response
+}
+
+fn cors_preflight() -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::NO_CONTENT;
+    let headers = response.headers_mut();
+    headers.insert(ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("GET, HEAD, OPTIONS"));
+    headers.insert(
+        ACCESS_CONTROL_ALLOW_HEADERS,
+        HeaderValue::from_static("Range, If-Range, If-None-Match, If-Modified-Since"),
     );
-    stream.write_all(response.as_bytes())
-        .map_err(|e| format!("Failed to send error: {}", e))
-}
-
-fn send_cors_preflight(stream: &mut TcpStream) -> Result<(), String> {
-    let response = "HTTP/1.1 204 No Content\r\n\
-        Access-Control-Allow-Origin: *\r\n\
-        Access-Control-Allow-Methods: GET, OPTIONS\r\n\
-        Access-Control-Allow-Headers: Range\r\n\
-        Access-Control-Max-Age: 86400\r\n\
-        Connection: close\r\n\r\n";
-    stream.write_all(response.as_bytes())
-        .map_err(|e| format!("Failed to send preflight: {}", e))
+    headers.insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from_static("86400"));
+    response
 }
@@ -0,0 +1,259 @@
+/**
+ * Video Stream Module
+ *
+ * Streaming proxy behind the video server. On a cache miss a single background
+ * task fetches the file from the CDN into `media/stream/{id}{ext}.part`; players
+ * read from that file while it grows instead of waiting for the whole download.
+ *
+ * Seeks that land inside (or just past) the downloaded prefix are served from
+ * disk. Seeks further ahead (e.g. a WebM player looking for cues at the end)
+ * are forwarded upstream as Range requests and piped through untouched.
+ *
+ * Download progress is published on a `watch` channel so video server
+ * connections can await new bytes without blocking a thread.
+ *
+ * When the download completes it is verified against the post MD5 and moved
+ * into the media store, so the next `proxy_video` call is a plain cache hit.
+ */
+
+use anyhow::{anyhow, bail, Result};
+use lazy_static::lazy_static;
+use md5::{Digest, Md5};
+use reqwest::header::{RANGE, REFERER};
+use reqwest::{Client, Response, StatusCode};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::watch;
+
+use crate::media_store;
+use crate::sources;
+
+/// Seeks at most this far past the downloaded prefix wait for the download
+/// rather than opening a second upstream connection
+const LOOKAHEAD: u64 = 1024 * 1024;
+
+/// How long a reader waits for new bytes before giving up on a stalled download
+const STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+const CDN_REFERER: &str = "https://boards.4chan.org/";
+
+lazy_static! {
+    static ref STREAMS: Mutex<HashMap<String, Arc<VideoStream>>> = Mutex::new(HashMap::new());
+    // No overall timeout: a long video can take minutes to come down
+    static ref CLIENT: Client = Client::builder()
+        .user_agent(sources::BROWSER_USER_AGENT)
+        .connect_timeout(Duration::from_secs(15))
+        .build()
+        .expect("Failed to create stream client");
+}
+
+#[derive(Default)]
+struct Fill {
+    /// Bytes written to the part file so far (always a prefix)
+    written: u64,
+    /// Total size from the upstream response, once headers arrived
+    total: Option<u64>,
+    headers_received: bool,
+    /// Set when the download finished; `Ok` holds the file in the store
+    done: Option<Result<PathBuf, String>>,
+}
+
+/// A video being fetched from the CDN and served while it downloads
+pub struct VideoStream {
+    url: String,
+    md5: Option<String>,
+    ext: String,
+    part: PathBuf,
+    fill: watch::Sender<Fill>,
+}
+
+/// Register a stream for `url` (or join the one already running) and return
+/// its path on the video server, e.g. `stream/3f2a...c1.webm`
+pub fn open(url: &str, md5: Option<&str>, ext: &str) -> Result<String> {
+    let id = format!("{:x}", Md5::digest(url.as_bytes()));
+    let path = format!("stream/{}{}", id, ext);
+
+    let mut streams = STREAMS.lock().map_err(|e| anyhow!("Lock error: {}", e))?;
+    let reusable = streams.get(&id).is_some_and(|s| s.reusable());
+    if !reusable {
+        let dir = media_store::media_root().join("stream");
+        std::fs::create_dir_all(&dir)?;
+
+        let stream = Arc::new(VideoStream {
+            url: url.to_string(),
+            md5: md5.map(str::to_string),
+            ext: ext.to_string(),
+            part: dir.join(format!("{}{}.part", id, ext)),
+            fill: watch::Sender::new(Fill::default()),
+        });
+        streams.insert(id, stream.clone());
+
+        tauri::async_runtime::spawn(async move {
+            let result = stream.download().await;
+            if let Err(e) = &result {
+                eprintln!("Video stream {} failed: {}", stream.url, e);
+                tokio::fs::remove_file(&stream.part).await.ok();
+            }
+            stream.fill.send_modify(|fill| fill.done = Some(result.map_err(|e| e.to_string())));
+        });
+    }
+
+    Ok(path)
+}
+
+/// Look up a stream by the last segment of its server path (`{id}{ext}`)
+pub fn get(name: &str) -> Option<Arc<VideoStream>> {
+    let id = name.split('.').next().unwrap_or(name);
+    STREAMS.lock().ok()?.get(id).cloned()
+}
+
+impl VideoStream {
+    /// Still downloading, or finished and the file is still in the store
+    /// (cache clears and eviction can delete it)
+    fn reusable(&self) -> bool {
+        match &self.fill.borrow().done {
+            None => true,
+            Some(Ok(path)) => path.exists(),
+            Some(Err(_)) => false,
+        }
+    }
+
+    /// Fetch the whole file into the part file, then move it into the store
+    async fn download(&self) -> Result<PathBuf> {
+        // Create the part file before readers can be let in
+        let mut file = File::create(&self.part).await?;
+
+        let mut response = CLIENT
+            .get(&self.url)
+            .header(REFERER, CDN_REFERER)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let total = response.content_length();
+        self.fill.send_modify(|fill| {
+            fill.total = total;
+            fill.headers_received = true;
+        });
+
+        let mut hasher = Md5::new();
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+            file.flush().await?;
+            hasher.update(&chunk);
+            let len = chunk.len() as u64;
+            self.fill.send_modify(|fill| fill.written += len);
+        }
+        drop(file);
+
+        let md5 = media_store::md5_from_digest(&hasher.finalize());
+        if let Some(expected) = &self.md5 {
+            if *expected != md5 {
+                bail!("MD5 mismatch: expected {}, got {}", expected, md5);
+            }
+        }
+
+        let stored = media_store::adopt(&self.part, &md5, &self.ext)?;
+        media_store::add_ref(&self.url, None, None, &md5, &self.ext)?;
+        Ok(stored.path)
+    }
+
+    /// Wait until upstream headers arrive and return the total size, if known
+    pub async fn wait_for_length(&self) -> Result<Option<u64>> {
+        let mut rx = self.fill.subscribe();
+        let fill = tokio::time::timeout(
+            STALL_TIMEOUT,
+            rx.wait_for(|f| f.headers_received || f.done.is_some()),
+        )
+        .await
+        .map_err(|_| anyhow!("Timed out waiting for upstream"))??;
+
+        match &fill.done {
+            Some(Err(e)) => bail!("{}", e),
+            Some(Ok(_)) => Ok(Some(fill.written)),
+            None => Ok(fill.total),
+        }
+    }
+
+    /// Whether a read starting at `pos` should be served from the part file
+    pub fn serves_from_disk(&self, pos: u64) -> bool {
+        let fill = self.fill.borrow();
+        matches!(fill.done, Some(Ok(_))) || pos <= fill.written + LOOKAHEAD
+    }
+
+    /// Open a reader positioned at `pos` that follows the download
+    pub async fn reader(self: &Arc<Self>, pos: u64) -> Result<StreamReader> {
+        let path = || -> Result<PathBuf> {
+            match &self.fill.borrow().done {
+                Some(Ok(path)) => Ok(path.clone()),
+                Some(Err(e)) => bail!("{}", e),
+                None => Ok(self.part.clone()),
+            }
+        };
+        // The part file may have just been moved into the store
+        let mut file = match File::open(path()?).await {
+            Ok(file) => file,
+            Err(_) => File::open(path()?).await?,
+        };
+        file.seek(SeekFrom::Start(pos)).await?;
+        Ok(StreamReader { fill: self.fill.subscribe(), file, pos })
+    }
+
+    /// Forward a byte range (inclusive) to the CDN
+    pub async fn fetch_upstream(&self, start: u64, end: u64) -> Result<Response> {
+        let response = CLIENT
+            .get(&self.url)
+            .header(REFERER, CDN_REFERER)
+            .header(RANGE, format!("bytes={}-{}", start, end))
+            .send()
+            .await?;
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            bail!("Upstream ignored range request ({})", response.status());
+        }
+        Ok(response)
+    }
+}
+
+/// Reads a stream from disk, waiting for the download when it catches up
+pub struct StreamReader {
+    fill: watch::Receiver<Fill>,
+    file: File,
+    pos: u64,
+}
+
+impl StreamReader {
+    /// Next chunk of at most `max` bytes, or `None` once the file is complete
+    pub async fn next_chunk(&mut self, max: usize) -> Result<Option<Vec<u8>>> {
+        let pos = self.pos;
+        let available = {
+            let fill = tokio::time::timeout(
+                STALL_TIMEOUT,
+                self.fill.wait_for(|f| f.written > pos || f.done.is_some()),
+            )
+            .await
+            .map_err(|_| anyhow!("Video stream stalled"))??;
+
+            if let Some(Err(e)) = &fill.done {
+                bail!("{}", e);
+            }
+            fill.written.saturating_sub(pos)
+        };
+        if available == 0 {
+            return Ok(None);
+        }
+
+        let mut buf = vec![0; max.min(available as usize)];
+        let read = self.file.read(&mut buf).await?;
+        if read == 0 {
+            bail!("Video stream file truncated");
+        }
+        buf.truncate(read);
+        self.pos += read as u64;
+        Ok(Some(buf))
+    }
+}
@@ -0,0 +1,389 @@
+/**
+ * Thread Watcher Module
+ *
+ * Background task that polls watched threads and reports changes to the UI.
+ *
+ * Features:
+ * - Per-thread backoff: active threads are polled often, idle ones less
+ * - `thread-watch-update` events with new post counts
+ * - 404 / archived detection (polling stops for those threads)
+ * - Desktop notification when a new reply quotes one of our own posts
+ * - State persisted in the cache database so it survives restarts
+ */
+
+use rusqlite::{params, Result, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use chrono::Utc;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::api::{ApiError, Conditional, Post, Thread};
+use crate::cache;
+use crate::sources::{Site, Sources};
+
+/// Event emitted after every poll that found something new
+pub const WATCH_UPDATE_EVENT: &str = "thread-watch-update";
+
+/// How often the scheduler looks for due threads
+const TICK_INTERVAL: Duration = Duration::from_secs(5);
+/// Poll interval bounds (seconds). 4chan asks for at most one request per 10s per thread.
+const MIN_INTERVAL_SECS: i64 = 15;
+const MAX_INTERVAL_SECS: i64 = 600;
+/// Interval growth for every poll without new posts
+const BACKOFF_FACTOR: f64 = 1.5;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchStatus {
+    Active,
+    Archived,
+    Dead, // 404
+}
+
+impl WatchStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Archived => "archived",
+            Self::Dead => "dead",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "archived" => Self::Archived,
+            "dead" => Self::Dead,
+            _ => Self::Active,
+        }
+    }
+}
+
+/// A watched thread and its polling state
+#[derive(Debug, Serialize, Clone)]
+pub struct WatchedThread {
+    pub site: Site,
+    pub board: String,
+    pub thread_id: u64,
+    pub subject: Option<String>,
+    pub last_post_no: u64,
+    pub reply_count: u32,
+    pub unread_count: u32,
+    pub you_count: u32,
+    pub status: WatchStatus,
+    pub interval_secs: i64,
+    pub next_check_at: i64,
+    pub last_checked_at: Option<i64>,
+    pub added_at: i64,
+    #[serde(skip)]
+    last_modified: Option<String>,
+}
+
+/// Payload of `thread-watch-update`
+#[derive(Debug, Serialize, Clone)]
+pub struct WatchUpdate {
+    pub site: Site,
+    pub board: String,
+    pub thread_id: u64,
+    pub new_posts: u32,
+    pub reply_count: u32,
+    pub unread_count: u32,
+    /// New replies that quote one of our posts
+    pub you_posts: Vec<u64>,
+    pub status: WatchStatus,
+}
+
+const WATCH_COLUMNS: &str = "site, board, thread_id, subject, last_post_no, reply_count, unread_count, \
+    you_count, status, interval_secs, next_check_at, last_checked_at, added_at, last_modified";
+
+/// Rows of sites this build doesn't know fail to convert and are skipped
+fn row_to_watch(row: &Row) -> Result<WatchedThread> {
+    let site: String = row.get(0)?;
+    let site = Site::from_id(&site).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            0,
+            rusqlite::types::Type::Text,
+            format!("Unknown site '{}'", site).into(),
+        )
+    })?;
+    let status: String = row.get(8)?;
+    Ok(WatchedThread {
+        site,
+        board: row.get(1)?,
+        thread_id: row.get::<_, i64>(2)? as u64,
+        subject: row.get(3)?,
+        last_post_no: row.get::<_, i64>(4)? as u64,
+        reply_count: row.get(5)?,
+        unread_count: row.get(6)?,
+        you_count: row.get(7)?,
+        status: WatchStatus::parse(&status),
+        interval_secs: row.get(9)?,
+        next_check_at: row.get(10)?,
+        last_checked_at: row.get(11)?,
+        added_at: row.get(12)?,
+        last_modified: row.get(13)?,
+    })
+}
+
+fn get_watch(site: Site, board: &str, thread_id: u64) -> Result<Option<WatchedThread>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    let watch = conn.query_row(
+        &format!("SELECT {} FROM watched_threads WHERE site = ?1 AND board = ?2 AND thread_id = ?3", WATCH_COLUMNS),
+        params![site.id(), board, thread_id as i64],
+        row_to_watch,
+    ).ok();
+
+    Ok(watch)
+}
+
+/// Start watching a thread (no-op if already watched). It is polled on the next tick.
+pub fn add_watch(site: Site, board: &str, thread_id: u64, subject: Option<&str>) -> Result<WatchedThread> {
+    {
+        let guard = cache::get_connection()?;
+        let conn = guard.as_ref().unwrap();
+        let now = Utc::now().timestamp();
+
+        conn.execute(
+            r#"INSERT OR IGNORE INTO watched_threads
+               (site, board, thread_id, subject, interval_secs, next_check_at, added_at)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)"#,
+            params![site.id(), board, thread_id as i64, subject, MIN_INTERVAL_SECS, now],
+        )?;
+    }
+
+    get_watch(site, board, thread_id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)
+}
+
+/// Stop watching a thread. Returns whether it was watched.
+pub fn remove_watch(site: Site, board: &str, thread_id: u64) -> Result<bool> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    let removed = conn.execute(
+        "DELETE FROM watched_threads WHERE site = ?1 AND board = ?2 AND thread_id = ?3",
+        params![site.id(), board, thread_id as i64],
+    )?;
+
+    Ok(removed > 0)
+}
+
+/// All watched threads, most recently added first
+pub fn list_watches() -> Result<Vec<WatchedThread>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM watched_threads ORDER BY added_at DESC",
+        WATCH_COLUMNS
+    ))?;
+    let watches = stmt.query_map([], row_to_watch)?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(watches)
+}
+
+/// Reset unread and (You) counters after the user opened the thread
+pub fn mark_read(site: Site, board: &str, thread_id: u64) -> Result<()> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    conn.execute(
+        "UPDATE watched_threads SET unread_count = 0, you_count = 0 WHERE site = ?1 AND board = ?2 AND thread_id = ?3",
+        params![site.id(), board, thread_id as i64],
+    )?;
+
+    Ok(())
+}
+
+fn due_watches(now: i64) -> Result<Vec<WatchedThread>> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM watched_threads WHERE status = 'active' AND next_check_at <= ?1 ORDER BY next_check_at ASC",
+        WATCH_COLUMNS
+    ))?;
+    let watches = stmt.query_map(params![now], row_to_watch)?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(watches)
+}
+
+fn save_watch(watch: &WatchedThread) -> Result<()> {
+    let guard = cache::get_connection()?;
+    let conn = guard.as_ref().unwrap();
+
+    conn.execute(
+        r#"UPDATE watched_threads SET
+               subject = ?4, last_post_no = ?5, reply_count = ?6, unread_count = ?7, you_count = ?8,
+               status = ?9, interval_secs = ?10, next_check_at = ?11, last_checked_at = ?12, last_modified = ?13
+           WHERE site = ?1 AND board = ?2 AND thread_id = ?3"#,
+        params![
+            watch.site.id(),
+            watch.board,
+            watch.thread_id as i64,
+            watch.subject,
+            watch.last_post_no as i64,
+            watch.reply_count,
+            watch.unread_count,
+            watch.you_count,
+            watch.status.as_str(),
+            watch.interval_secs,
+            watch.next_check_at,
+            watch.last_checked_at,
+            watch.last_modified,
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Start the background polling task
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = poll_due(&app).await {
+                eprintln!("Thread watcher error: {}", e);
+            }
+            tokio::time::sleep(TICK_INTERVAL).await;
+        }
+    });
+}
+
+async fn poll_due(app: &AppHandle) -> Result<()> {
+    let sources = app.state::<Sources>();
+
+    for watch in due_watches(Utc::now().timestamp())? {
+        let fetched = sources
+            .get(watch.site)
+            .thread_since(&watch.board, watch.thread_id, watch.last_modified.as_deref())
+            .await;
+
+        let (watch, update) = apply_poll(watch, fetched);
+        save_watch(&watch)?;
+
+        if let Some(update) = update {
+            notify(app, &watch, &update);
+            if let Err(e) = app.emit(WATCH_UPDATE_EVENT, update) {
+                eprintln!("Failed to emit watch update: {}", e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Fold a poll result into the watch state, returning an update if anything changed
+fn apply_poll(
+    mut watch: WatchedThread,
+    fetched: anyhow::Result<Conditional<(Thread, Option<String>)>>,
+) -> (WatchedThread, Option<WatchUpdate>) {
+    let now = Utc::now().timestamp();
+    watch.last_checked_at = Some(now);
+
+    let mut new_posts: Vec<Post> = Vec::new();
+    let mut status_changed = false;
+
+    match fetched {
+        Ok(Conditional::NotModified) => {}
+        Ok(Conditional::Modified((thread, last_modified))) => {
+            watch.last_modified = last_modified;
+
+            if let Some(op) = thread.posts.first() {
+                if watch.subject.is_none() {
+                    watch.subject = op.sub.clone();
+                }
+                if op.archived == Some(1) {
+                    watch.status = WatchStatus::Archived;
+                    status_changed = true;
+                }
+            }
+
+            watch.reply_count = thread.posts.len().saturating_sub(1) as u32;
+
+            // The first poll only establishes the baseline
+            let baseline = watch.last_post_no == 0;
+            let last_post_no = watch.last_post_no;
+            new_posts = thread.posts.into_iter().filter(|p| p.no > last_post_no).collect();
+            if let Some(last) = new_posts.iter().map(|p| p.no).max() {
+                watch.last_post_no = last;
+            }
+            if baseline {
+                new_posts.clear();
+            }
+        }
+        Err(e) if ApiError::is_not_found(&e) => {
+            watch.status = WatchStatus::Dead;
+            status_changed = true;
+        }
+        Err(e) => {
+            eprintln!("Failed to poll /{}/{}: {}", watch.board, watch.thread_id, e);
+        }
+    }
+
+    // Back off while the thread is idle, snap back once it moves
+    watch.interval_secs = if new_posts.is_empty() {
+        ((watch.interval_secs as f64 * BACKOFF_FACTOR) as i64).min(MAX_INTERVAL_SECS)
+    } else {
+        MIN_INTERVAL_SECS
+    };
+    watch.next_check_at = now + watch.interval_secs;
+
+    if new_posts.is_empty() && !status_changed {
+        return (watch, None);
+    }
+
+    let own: HashSet<u64> = cache::get_own_post_numbers(watch.site, &watch.board, watch.thread_id as i64)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|n| n as u64)
+        .collect();
+
+    let you_posts: Vec<u64> = new_posts
+        .iter()
+        .filter(|p| p.quoted_posts().iter().any(|q| own.contains(q)))
+        .map(|p| p.no)
+        .collect();
+
+    watch.unread_count += new_posts.len() as u32;
+    watch.you_count += you_posts.len() as u32;
+
+    let update = WatchUpdate {
+        site: watch.site,
+        board: watch.board.clone(),
+        thread_id: watch.thread_id,
+        new_posts: new_posts.len() as u32,
+        reply_count: watch.reply_count,
+        unread_count: watch.unread_count,
+        you_posts,
+        status: watch.status,
+    };
+
+    (watch, Some(update))
+}
+
+/// Desktop notification for replies to our posts
+fn notify(app: &AppHandle, watch: &WatchedThread, update: &WatchUpdate) {
+    if update.you_posts.is_empty() {
+        return;
+    }
+
+    let title = match &watch.subject {
+        Some(subject) if !subject.is_empty() => format!("/{}/ - {}", watch.board, subject),
+        _ => format!("/{}/{}", watch.board, watch.thread_id),
+    };
+    let body = if update.you_posts.len() == 1 {
+        "1 new reply to your post".to_string()
+    } else {
+        format!("{} new replies to your posts", update.you_posts.len())
+    };
+
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("Failed to show notification: {}", e);
+    }
+}
@@ -1,6 +1,6 @@
 import { useState, useEffect, useMemo } from 'react'
 import { QueryClient, QueryClientProvider, useQuery } from '@tanstack/react-query'
-import { fetchCatalog, fetchThread } from './utils/apiClient'
+import { fetchCatalog, fetchThread, getThreadUrl } from './utils/apiClient'
 import { exportThread } from './utils/exportThread'
 import type { CatalogThread, Post as PostType, Thread } from './types/api'
 import EnhancedSidebar from './components/layout/EnhancedSidebar'
@@ -19,6 +19,7 @@ import { useFavoritesStore } from './stores/favoritesStore'
 import { useWatchlistStore } from './stores/watchlistStore'
 import { useLastSeenStore } from './stores/lastSeenStore'
 import { useLightboxStore } from './stores/lightboxStore'
+import { useBrowserTabsStore } from './stores/browserTabsStore'
 import { useAutoRefresh } from './hooks/useAutoRefresh'
 import { useKeyboardShortcuts } from './hooks/useKeyboardShortcuts'
 import type { KeyboardShortcut } from './hooks/useKeyboardShortcuts'
@@ -446,6 +447,7 @@ function AppContent() {
     const [board, setBoard] = useState<string | null>(null)
     const [currentThread, setCurrentThread] = useState<number | null>(null)
     const { favorites, toggleFavorite } = useFavoritesStore()
+    const { openTab } = useBrowserTabsStore()
 
     // Auto-refresh hook
     useAutoRefresh({ board, threadId: currentThread })
@@ -532,7 +534,12 @@ function AppContent() {
                 />
 
                 {!board ? (
-                    <HomeView onThreadClick={(b: string, t: number) => {
+                    <HomeView onThreadClick={(site: string, b: string, t: number) => {
+                        // Only 4chan threads have an in-app view; open the rest on their site
+                        if (site !== '4chan') {
+                            openTab(getThreadUrl(b, t, site), `/${b}/ - Thread #${t}`)
+                            return
+                        }
                         setBoard(b)
                         setCurrentThread(t)
                     }} />
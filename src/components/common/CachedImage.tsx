@@ -1,5 +1,5 @@
 import { useState, useEffect } from 'react'
-import { fetchImageWithProxy } from '../../utils/apiClient'
+import { mediaUrl } from '../../utils/apiClient'
 
 interface CachedImageProps {
     src: string
@@ -20,54 +20,17 @@ export default function CachedImage({
     onLoad,
     onError
 }: CachedImageProps) {
-    const [cachedSrc, setCachedSrc] = useState<string>('')
     const [loading, setLoading] = useState(true)
     const [error, setError] = useState(false)
 
-    useEffect(() => {
-        let mounted = true
-
-        async function loadImage() {
-            try {
-                setLoading(true)
-                setError(false)
-
-                // Fetch through Rust backend to bypass CORS
-                const localPath = await fetchImageWithProxy(src)
-
-                if (mounted) {
-                    setCachedSrc(localPath)
-                    setLoading(false)
-                    onLoad?.()
-                }
-            } catch (err) {
-                console.error('Failed to load image:', src, err)
-                if (mounted) {
-                    setError(true)
-                    setLoading(false)
-                    onError?.()
-                }
-            }
-        }
+    // Served through the chanmedia:// protocol (cached on disk by the backend)
+    const cachedSrc = src ? mediaUrl(src) : ''
 
-        if (src) {
-            loadImage()
-        }
-
-        return () => {
-            mounted = false
-        }
+    useEffect(() => {
+        setLoading(true)
+        setError(false)
     }, [src])
 
-    if (loading) {
-        return (
-            <div
-                className={`bg-gray-800 animate-pulse ${className}`}
-                style={style}
-            />
-        )
-    }
-
     if (error || !cachedSrc) {
         return (
             <div
@@ -80,13 +43,31 @@ export default function CachedImage({
     }
 
     return (
-        <img
-            src={cachedSrc}
-            alt={alt}
-            className={className}
-            style={style}
-            onClick={onClick}
-            draggable={false}
-        />
+        <>
+            {loading && (
+                <div
+                    className={`bg-gray-800 animate-pulse ${className}`}
+                    style={style}
+                />
+            )}
+            <img
+                src={cachedSrc}
+                alt={alt}
+                className={className}
+                style={loading ? { ...style, display: 'none' } : style}
+                onClick={onClick}
+                onLoad={() => {
+                    setLoading(false)
+                    onLoad?.()
+                }}
+                onError={() => {
+                    console.error('Failed to load image:', src)
+                    setError(true)
+                    setLoading(false)
+                    onError?.()
+                }}
+                draggable={false}
+            />
+        </>
     )
 }
@@ -1,8 +1,7 @@
 import { useState, useEffect } from 'react'
-import { getImageUrl, getThumbnailUrl } from '../../utils/apiClient'
+import { fetchPopularThreads } from '../../utils/apiClient'
 import CachedImage from '../common/CachedImage'
 import { useQuery } from '@tanstack/react-query'
-import { invoke } from '@tauri-apps/api/core'
 import { useProviderStore } from '../../stores/providerStore'
 import { providerRegistry } from '../../providers'
 import { Skeleton } from '@/components/ui/skeleton'
@@ -11,11 +10,10 @@ import { Card, CardContent, CardHeader } from '@/components/ui/card'
 interface PopularThread {
     no: number
     board: string
-    provider?: string  // Added provider field
+    provider: string  // Site the thread is on (provider id)
     title?: string
     com: string
-    tim: number
-    ext: string
+    thumbnailUrl?: string
     replies: number
     images: number
 }
@@ -48,12 +46,7 @@ const PROVIDER_COLORS: Record<string, string> = {
     '7chan': '#7C3AED',
 }
 
-// Function to fetch popular threads from 4chan backend
-async function fetchPopularThreads(): Promise<PopularThread[]> {
-    return await invoke('fetch_popular_threads')
-}
-
-export default function HomeView({ onThreadClick }: { onThreadClick: (board: string, threadId: number) => void }) {
+export default function HomeView({ onThreadClick }: { onThreadClick: (site: string, board: string, threadId: number) => void }) {
     const { activeProviderId } = useProviderStore()
     const provider = providerRegistry.get(activeProviderId)
 
@@ -63,9 +56,18 @@ export default function HomeView({ onThreadClick }: { onThreadClick: (board: str
         queryKey: ['popular-threads', activeProviderId],
         queryFn: async () => {
             if (activeProviderId === '4chan') {
-                // Use existing 4chan popular threads
-                const result = await fetchPopularThreads()
-                return result.map(t => ({ ...t, provider: '4chan' }))
+                // Home feed built in the backend from the configured boards
+                const feed = await fetchPopularThreads()
+                return feed.threads.map((t): PopularThread => ({
+                    no: t.no,
+                    board: t.board,
+                    provider: t.site,
+                    title: t.title,
+                    com: t.com || '',
+                    thumbnailUrl: t.thumbnail_url,
+                    replies: t.replies,
+                    images: t.images,
+                }))
             } else if (provider) {
                 // For other providers, fetch from a popular board's catalog
                 const boards = await provider.fetchBoards()
@@ -84,8 +86,7 @@ export default function HomeView({ onThreadClick }: { onThreadClick: (board: str
                                 provider: activeProviderId,
                                 title: t.sub,
                                 com: t.com || '',
-                                tim: t.tim || 0,
-                                ext: t.ext || '.jpg',
+                                thumbnailUrl: t.tim ? provider.getThumbnailUrl(board.id, t.tim) : undefined,
                                 replies: t.replies || 0,
                                 images: t.images || 0,
                             }))
@@ -169,24 +170,24 @@ export default function HomeView({ onThreadClick }: { onThreadClick: (board: str
                             <div
                                 key={`${thread.provider}-${thread.board}-${thread.no}`}
                                 className="bg-dark-elevated border border-dark-border rounded-lg overflow-hidden hover:border-primary-500/50 transition-all cursor-pointer flex flex-col group hover-rise click-scale"
-                                onClick={() => onThreadClick(thread.board, thread.no)}
+                                onClick={() => onThreadClick(thread.provider, thread.board, thread.no)}
                             >
                                 {/* Board header with provider badge */}
                                 <div
                                     className="px-3 py-2 text-xs font-bold text-white flex items-center justify-between"
-                                    style={{ backgroundColor: PROVIDER_COLORS[thread.provider || '4chan'] || '#666' }}
+                                    style={{ backgroundColor: PROVIDER_COLORS[thread.provider] || '#666' }}
                                 >
                                     <span>/{thread.board}/ - {BOARD_NAMES[thread.board] || thread.board}</span>
                                     <span className="opacity-70 text-[10px] uppercase">
-                                        {thread.provider || '4chan'}
+                                        {thread.provider}
                                     </span>
                                 </div>
 
                                 {/* Thumbnail */}
-                                {thread.tim > 0 && (
+                                {thread.thumbnailUrl && (
                                     <div className="aspect-video overflow-hidden bg-dark-bg">
                                         <CachedImage
-                                            src={getThumbnailUrl(thread.board, thread.tim)}
+                                            src={thread.thumbnailUrl}
                                             alt="Thread thumbnail"
                                             className="w-full h-full object-cover group-hover:scale-105 transition-transform duration-300"
                                         />
@@ -6,6 +6,7 @@ import {
 } from 'lucide-react'
 import { invoke } from '@tauri-apps/api/core'
 import { useSettingsStore } from '../../stores/settingsStore'
+import { getBoardCapabilities, BoardCapabilities } from '../../utils/apiClient'
 
 interface QuickReplyProps {
     board: string
@@ -55,15 +56,22 @@ export default function QuickReply({ board, threadId, replyTo, onClose, onSucces
     const [captchaLoading, setCaptchaLoading] = useState(false)
     const [captchaExpiry, setCaptchaExpiry] = useState(0)
 
+    const [capabilities, setCapabilities] = useState<BoardCapabilities | null>(null)
+
     const textareaRef = useRef<HTMLTextAreaElement>(null)
     const fileInputRef = useRef<HTMLInputElement>(null)
 
-    // Check cooldown on mount
+    // Board limits for the comment box; posting still works without them
+    useEffect(() => {
+        getBoardCapabilities(board).then(setCapabilities).catch(() => setCapabilities(null))
+    }, [board])
+
+    // Check the cooldown for this board and kind of post
     useEffect(() => {
         checkCooldown()
         const interval = setInterval(checkCooldown, 1000)
         return () => clearInterval(interval)
-    }, [])
+    }, [board, threadId, selectedFile])
 
     // Focus textarea on mount
     useEffect(() => {
@@ -86,7 +94,8 @@ export default function QuickReply({ board, threadId, replyTo, onClose, onSucces
 
     const checkCooldown = async () => {
         try {
-            const seconds = await invoke<number>('get_post_cooldown')
+            const kind = !threadId ? 'thread' : selectedFile ? 'reply_with_image' : 'reply'
+            const seconds = await invoke<number>('get_post_cooldown', { board, kind })
             setCooldown(seconds)
         } catch {
             setCooldown(0)
@@ -312,6 +321,7 @@ export default function QuickReply({ board, threadId, replyTo, onClose, onSucces
                                     placeholder="Enter your reply..."
                                     value={formData.comment}
                                     onChange={(e) => setFormData({ ...formData, comment: e.target.value })}
+                                    maxLength={capabilities?.max_comment_chars}
                                     className="w-full bg-zinc-800 border border-zinc-700 rounded-lg px-3 py-2 text-sm text-white placeholder-zinc-500 focus:outline-none focus:border-purple-500 resize-none"
                                     rows={4}
                                 />
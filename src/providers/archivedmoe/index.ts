@@ -33,7 +33,7 @@ class ArchivedMoeProvider implements ImageboardProvider {
 
     async fetchBoards(): Promise<Board[]> {
         try {
-            const result = await invoke<any[]>('fetch_archivedmoe_boards')
+            const result = await invoke<any[]>('fetch_boards', { site: this.id })
             return (result || []).map(b => ({
                 id: b.id,
                 name: b.name,
@@ -49,7 +49,7 @@ class ArchivedMoeProvider implements ImageboardProvider {
 
     async fetchCatalog(board: string): Promise<CatalogThread[]> {
         try {
-            const result = await invoke<any[]>('fetch_archivedmoe_catalog', { board, page: 1 })
+            const result = await invoke<any[]>('fetch_catalog', { site: this.id, board })
 
             return (result || []).map(thread => ({
                 no: thread.no,
@@ -71,7 +71,7 @@ class ArchivedMoeProvider implements ImageboardProvider {
 
     async fetchThread(board: string, threadId: number): Promise<Thread> {
         try {
-            const result = await invoke<any>('fetch_archivedmoe_thread', { board, threadId })
+            const result = await invoke<any>('fetch_thread', { site: this.id, board, threadId })
 
             if (!result || !result.posts) {
                 return { posts: [] }
@@ -61,17 +61,11 @@ class FourChanProvider implements ImageboardProvider {
 
     async fetchCatalog(board: string): Promise<CatalogThread[]> {
         try {
-            const catalog = await invoke<any>('fetch_catalog', { board })
-            // Flatten catalog pages into thread array
-            const threads: CatalogThread[] = []
-            if (Array.isArray(catalog)) {
-                for (const page of catalog) {
-                    if (page.threads) {
-                        threads.push(...page.threads)
-                    }
-                }
-            }
-            return threads
+            const threads = await invoke<any[]>('fetch_catalog', { site: this.id, board })
+            return (threads || []).map(thread => ({
+                ...thread,
+                tim: thread.tim ? Number(thread.tim) : undefined,
+            }))
         } catch (error) {
             console.error(`Failed to fetch /${board}/ catalog:`, error)
             return []
@@ -80,8 +74,13 @@ class FourChanProvider implements ImageboardProvider {
 
     async fetchThread(board: string, threadId: number): Promise<Thread> {
         try {
-            const thread = await invoke<Thread>('fetch_thread', { board, threadId })
-            return thread
+            const thread = await invoke<any>('fetch_thread', { site: this.id, board, threadId })
+            return {
+                posts: (thread?.posts || []).map((post: any) => ({
+                    ...post,
+                    tim: post.tim ? Number(post.tim) : undefined,
+                })),
+            }
         } catch (error) {
             console.error(`Failed to fetch thread ${threadId}:`, error)
             return { posts: [] }
@@ -55,7 +55,7 @@ class FourPlebsProvider implements ImageboardProvider {
 
     async fetchCatalog(board: string): Promise<CatalogThread[]> {
         try {
-            const result = await invoke<any[]>('fetch_fourplebs_catalog', { board, page: 1 })
+            const result = await invoke<any[]>('fetch_catalog', { site: this.id, board })
 
             // Convert FoolFuuka format to our standard format
             return (result || []).map(thread => ({
@@ -79,7 +79,7 @@ class FourPlebsProvider implements ImageboardProvider {
 
     async fetchThread(board: string, threadId: number): Promise<Thread> {
         try {
-            const result = await invoke<any>('fetch_fourplebs_thread', { board, threadId })
+            const result = await invoke<any>('fetch_thread', { site: this.id, board, threadId })
 
             if (!result || !result.posts) {
                 return { posts: [] }
@@ -74,7 +74,7 @@ class SevenChanProvider implements ImageboardProvider {
     async fetchCatalog(board: string): Promise<CatalogThread[]> {
         try {
             // 7chan uses a different format - we'll need backend support
-            const result = await invoke<any>('fetch_sevenchan_catalog', { board })
+            const result = await invoke<any>('fetch_catalog', { site: this.id, board })
             return result || []
         } catch (error) {
             // 7chan scraping often fails - fail silently
@@ -85,7 +85,7 @@ class SevenChanProvider implements ImageboardProvider {
 
     async fetchThread(board: string, threadId: number): Promise<Thread> {
         try {
-            const result = await invoke<any>('fetch_sevenchan_thread', { board, threadId })
+            const result = await invoke<any>('fetch_thread', { site: this.id, board, threadId })
             return result || { posts: [] }
         } catch (error) {
             console.warn(`7chan thread ${threadId}: not accessible`)
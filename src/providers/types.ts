@@ -5,6 +5,8 @@
  * This allows ChanDesk Pro to support multiple imageboards with a unified API.
  */
 
+import type { CommentNode } from '../types/api'
+
 // Common types used across all providers
 export interface Board {
     id: string
@@ -48,6 +50,7 @@ export interface Post {
     tn_h?: number
     replies?: number
     images?: number
+    com_ast?: CommentNode[]
 }
 
 export interface Thread {
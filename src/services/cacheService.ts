@@ -32,6 +32,7 @@ export interface CachedPost {
     fsize: number | null;
     w: number | null;
     h: number | null;
+    md5?: string | null;
 }
 
 export interface CachedThread {
@@ -45,6 +46,16 @@ export interface CachedThread {
     posts: CachedPost[];
 }
 
+export interface PostSearchHit {
+    board: string;
+    thread_id: number;
+    post_no: number;
+    time: number;
+    subject: string | null;
+    snippet: string; // HTML-escaped, matches wrapped in <mark>
+    rank: number;
+}
+
 export interface CleanupResult {
     deleted_by_age: number;
     deleted_by_size: number;
@@ -123,6 +134,97 @@ export async function clearThreadCache(): Promise<void> {
     return invoke('clear_thread_cache');
 }
 
+/**
+ * Find cached posts on any board carrying the same file
+ * @param md5 Base64 MD5 as reported by the API
+ */
+export async function findReposts(md5: string): Promise<CachedPost[]> {
+    return invoke<CachedPost[]>('find_reposts', { md5 });
+}
+
+/**
+ * Full-text search over cached posts (FTS5 syntax: words, "phrases", prefix*, AND/OR/NOT)
+ * @param since Only posts made at or after this Unix time
+ */
+export async function searchCachedPosts(
+    query: string,
+    board?: string,
+    since?: number,
+    limit?: number
+): Promise<PostSearchHit[]> {
+    return invoke<PostSearchHit[]>('search_cached_posts', {
+        query,
+        board,
+        since,
+        limit
+    });
+}
+
+export type MediaKind = 'thumbnail' | 'image' | 'video' | 'other';
+
+export interface CacheLimits {
+    max_bytes: number;
+    thumbnail_bytes: number | null;
+    image_bytes: number | null;
+    video_bytes: number | null;
+    other_bytes: number | null;
+}
+
+export interface StorageReport {
+    media_bytes: number;
+    media_files: number;
+    pinned_bytes: number;
+    limits: CacheLimits;
+    by_kind: { kind: MediaKind; files: number; bytes: number; quota: number | null }[];
+    by_board: { board: string | null; files: number; bytes: number }[];
+    thread_db_bytes: number;
+}
+
+export interface Eviction {
+    files: number;
+    bytes: number;
+}
+
+export interface PinnedThread {
+    board: string;
+    thread_id: number;
+    reason: string;
+    created_at: number;
+}
+
+/**
+ * Media and thread cache usage by kind and board
+ */
+export async function getStorageReport(): Promise<StorageReport> {
+    return invoke<StorageReport>('get_storage_report');
+}
+
+export async function getMediaCacheLimits(): Promise<CacheLimits> {
+    return invoke<CacheLimits>('get_media_cache_limits');
+}
+
+/**
+ * Save media cache limits; evicts immediately if they are exceeded
+ */
+export async function setMediaCacheLimits(limits: CacheLimits): Promise<Eviction> {
+    return invoke<Eviction>('set_media_cache_limits', { limits });
+}
+
+/**
+ * Pin a thread so its media is never evicted (watched threads are pinned automatically)
+ */
+export async function pinThreadMedia(board: string, threadId: number): Promise<void> {
+    return invoke('pin_thread_media', { board, threadId });
+}
+
+export async function unpinThreadMedia(board: string, threadId: number): Promise<boolean> {
+    return invoke<boolean>('unpin_thread_media', { board, threadId });
+}
+
+export async function listPinnedThreads(): Promise<PinnedThread[]> {
+    return invoke<PinnedThread[]>('list_pinned_threads');
+}
+
 /**
  * Format bytes to human readable string
  */
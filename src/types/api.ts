@@ -83,8 +83,23 @@ export interface Post {
     m_img?: number; // mobile optimized image
     archived?: number;
     archived_on?: number;
+    com_ast?: CommentNode[]; // parsed comment
 }
 
+// Parsed comment tree (see src-tauri/src/comment.rs)
+export type CommentNode =
+    | { type: 'text'; text: string }
+    | { type: 'line_break' }
+    | { type: 'quote'; no: number; thread?: number }
+    | { type: 'cross_quote'; board: string; thread?: number; no?: number }
+    | { type: 'dead_link'; board?: string; no: number }
+    | { type: 'greentext'; children: CommentNode[] }
+    | { type: 'spoiler'; children: CommentNode[] }
+    | { type: 'styled'; style: 'bold' | 'italic' | 'underline'; children: CommentNode[] }
+    | { type: 'code'; text: string }
+    | { type: 'math'; text: string; display: boolean }
+    | { type: 'url'; url: string };
+
 export interface Thread {
     posts: Post[];
 }
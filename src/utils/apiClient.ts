@@ -1,4 +1,4 @@
-import { invoke } from '@tauri-apps/api/core'
+import { invoke, convertFileSrc } from '@tauri-apps/api/core'
 import type { BoardInfo } from '../types/boards'
 import type { Thread, Catalog } from '../types/api'
 
@@ -10,17 +10,455 @@ export async function searchBoards(query: string): Promise<BoardInfo[]> {
     return await invoke<BoardInfo[]>('search_boards', { query })
 }
 
+export interface SiteBoard {
+    id: string
+    name: string
+    description?: string
+    nsfw: boolean
+    category: string
+}
+
+// Payload of 'board-list-changed' events, sent when a refresh adds or removes boards
+export interface BoardListDiff {
+    site: string
+    added: SiteBoard[]
+    removed: SiteBoard[]
+}
+
+// Board lists are cached for a day; this refetches one immediately
+export async function refreshBoardList(site: string): Promise<SiteBoard[]> {
+    return await invoke<SiteBoard[]>('refresh_board_list', { site })
+}
+
 export async function fetchCatalog(board: string): Promise<Catalog> {
-    return await invoke<Catalog>('fetch_catalog', { board })
+    // The backend returns a flat thread list; regroup it into catalog pages
+    const threads = await invoke<any[]>('fetch_catalog', { site: '4chan', board })
+    const pages = new Map<number, any[]>()
+    for (const thread of threads) {
+        const page = thread.page ?? 1
+        pages.set(page, [...(pages.get(page) || []), thread])
+    }
+    return [...pages.entries()]
+        .sort(([a], [b]) => a - b)
+        .map(([page, threads]) => ({ page, threads })) as Catalog
+}
+
+export type CatalogSort = 'bump' | 'replies' | 'images' | 'created' | 'last_reply'
+
+// Filtered catalog, e.g. `"general" replies:>100 -is:sticky` or `/rust|zig/i OR sub:code`
+export async function searchCatalog(board: string, query: string, sort?: CatalogSort, site = '4chan'): Promise<any[]> {
+    return await invoke<any[]>('search_catalog', { site, board, query, sort })
 }
 
 export async function fetchThread(board: string, threadId: number): Promise<Thread> {
-    return await invoke<Thread>('fetch_thread', { board, threadId })
+    return await invoke<Thread>('fetch_thread', { site: '4chan', board, threadId })
+}
+
+export interface ThreadUpdate {
+    modified: boolean
+    new_posts: Thread['posts']
+    deleted_posts: number[]
+    // Pass back as `since` on the next refresh of this view
+    last_modified: string | null
+}
+
+// Conditional refresh: only posts newer than afterPost, plus which of knownPosts were deleted
+export async function refreshThread(
+    board: string,
+    threadId: number,
+    afterPost: number,
+    since?: string | null,
+    knownPosts?: number[],
+): Promise<ThreadUpdate> {
+    return await invoke<ThreadUpdate>('refresh_thread', { board, threadId, afterPost, since, knownPosts })
+}
+
+// Reply graph: quote links between the posts of a thread
+export interface ExternalQuote {
+    board?: string
+    thread?: number
+    no?: number
+}
+
+export interface PostLinks {
+    replies_to: number[]
+    replied_by: number[]
+    cross_thread?: ExternalQuote[]
+    cross_board?: ExternalQuote[]
+    dead_links?: number[]
+}
+
+export interface ReplyGraph {
+    thread_id: number
+    posts: Record<number, PostLinks>
+}
+
+export async function getReplyGraph(site: string, board: string, threadId: number): Promise<ReplyGraph> {
+    return await invoke<ReplyGraph>('get_reply_graph', { site, board, threadId })
+}
+
+// A post with everything it answers and everything answering it
+export async function getReplyChain(site: string, board: string, threadId: number, postNo: number): Promise<Thread['posts']> {
+    return await invoke<Thread['posts']>('get_reply_chain', { site, board, threadId, postNo })
+}
+
+// Posts on the reply paths connecting two posts
+export async function getConversation(site: string, board: string, threadId: number, postA: number, postB: number): Promise<Thread['posts']> {
+    return await invoke<Thread['posts']>('get_conversation', { site, board, threadId, postA, postB })
+}
+
+// Thread statistics; `cached` reads the thread cache instead of fetching
+export interface ThreadStats {
+    thread_id: number
+    posts: number
+    first_post_at: number
+    last_post_at: number
+    posts_per_minute: number
+    bucket_secs: number
+    timeline: { start: number; posts: number; posts_per_minute: number }[]
+    unique_posters: number
+    posters: { id: string; posts: number }[]
+    countries: { code: string; name?: string; posts: number }[]
+    most_quoted: { no: number; replies: number }[]
+    media: { images: number; videos: number; file_ratio: number; video_ratio: number }
+    average_comment_length: number
+    bump_limit?: {
+        bump_limit: number
+        replies: number
+        reached: boolean
+        replies_per_minute: number
+        eta_secs?: number
+        projected_at?: number
+    }
+}
+
+export async function getThreadStats(site: string, board: string, threadId: number, cached?: boolean): Promise<ThreadStats> {
+    return await invoke<ThreadStats>('get_thread_stats', { site, board, threadId, cached })
+}
+
+// Board activity sampler: periodic catalog snapshots of the tracked boards
+export interface TrackedBoard {
+    site: string
+    board: string
+}
+
+export interface ThreadVelocity {
+    site: string
+    board: string
+    thread_id: number
+    subject?: string
+    replies: number
+    images: number
+    created_at: number
+    velocity: number // replies per minute
+}
+
+export interface BoardSnapshot {
+    site: string
+    board: string
+    taken_at: number
+    threads: number
+    replies: number
+    images: number
+    max_post_no: number
+    interval_secs?: number
+    posts_per_minute?: number
+    new_threads?: number
+    top_threads: ThreadVelocity[]
+}
+
+export interface NewThreadRate {
+    site: string
+    board: string
+    hours: number
+    new_threads: number
+    covered_secs: number
+    per_hour: number
+}
+
+export async function getActivityBoards(): Promise<TrackedBoard[]> {
+    return await invoke<TrackedBoard[]>('get_activity_boards')
+}
+
+export async function setActivityBoards(boards: TrackedBoard[]): Promise<void> {
+    return await invoke('set_activity_boards', { boards })
+}
+
+export async function sampleBoardActivity(site: string, board: string): Promise<BoardSnapshot> {
+    return await invoke<BoardSnapshot>('sample_board_activity', { site, board })
+}
+
+export async function getBoardActivity(site: string, board: string, hours?: number): Promise<BoardSnapshot[]> {
+    return await invoke<BoardSnapshot[]>('get_board_activity', { site, board, hours })
+}
+
+export async function getFastestThreads(site?: string, board?: string, limit?: number): Promise<ThreadVelocity[]> {
+    return await invoke<ThreadVelocity[]>('get_fastest_threads', { site, board, limit })
+}
+
+export async function getNewThreadRate(site: string, board: string, hours?: number): Promise<NewThreadRate> {
+    return await invoke<NewThreadRate>('get_new_thread_rate', { site, board, hours })
+}
+
+// Home feed: popular threads from the configured boards of any site
+export type FeedRanking = 'replies' | 'velocity' | 'images' | 'newest'
+
+export interface FeedConfig {
+    boards: TrackedBoard[]
+    ranking: FeedRanking
+    exclude_nsfw: boolean
+    per_board: number
+    cache_secs: number
+}
+
+export interface FeedThread {
+    site: string
+    board: string
+    no: number
+    title?: string
+    com?: string
+    tim?: string
+    ext?: string
+    thumbnail_url?: string
+    replies: number
+    images: number
+    time: number
+    velocity: number
+}
+
+export interface FeedPage {
+    threads: FeedThread[]
+    page: number
+    page_size: number
+    total: number
+    ranking: FeedRanking
+    fetched_at: number
+}
+
+export interface FeedOptions {
+    ranking?: FeedRanking
+    page?: number
+    pageSize?: number
+    refresh?: boolean
+}
+
+export async function fetchPopularThreads(options: FeedOptions = {}): Promise<FeedPage> {
+    return await invoke<FeedPage>('fetch_popular_threads', { ...options })
+}
+
+export async function getHomeFeedConfig(): Promise<FeedConfig> {
+    return await invoke<FeedConfig>('get_home_feed_config')
+}
+
+export async function setHomeFeedConfig(config: FeedConfig): Promise<void> {
+    return await invoke('set_home_feed_config', { config })
+}
+
+// Background thread watcher (updates arrive as 'thread-watch-update' events)
+export type WatchStatus = 'active' | 'archived' | 'dead'
+
+export interface WatchedThread {
+    site: string
+    board: string
+    thread_id: number
+    subject: string | null
+    last_post_no: number
+    reply_count: number
+    unread_count: number
+    you_count: number
+    status: WatchStatus
+    interval_secs: number
+    next_check_at: number
+    last_checked_at: number | null
+    added_at: number
+}
+
+export interface WatchUpdate {
+    site: string
+    board: string
+    thread_id: number
+    new_posts: number
+    reply_count: number
+    unread_count: number
+    you_posts: number[]
+    status: WatchStatus
+}
+
+export async function watchThread(site: string, board: string, threadId: number, subject?: string): Promise<WatchedThread> {
+    return await invoke<WatchedThread>('watch_thread', { site, board, threadId, subject })
+}
+
+export async function unwatchThread(site: string, board: string, threadId: number): Promise<boolean> {
+    return await invoke<boolean>('unwatch_thread', { site, board, threadId })
+}
+
+export async function listWatchedThreads(): Promise<WatchedThread[]> {
+    return await invoke<WatchedThread[]>('list_watched_threads')
+}
+
+export async function markWatchedThreadRead(site: string, board: string, threadId: number): Promise<void> {
+    return await invoke('mark_watched_thread_read', { site, board, threadId })
+}
+
+export async function markOwnPost(site: string, board: string, threadId: number, postNo: number): Promise<void> {
+    return await invoke('mark_own_post', { site, board, threadId, postNo })
+}
+
+// Filter rules (applied by the backend to threads and catalogs)
+export type FilterField = 'name' | 'tripcode' | 'poster_id' | 'country' | 'subject' | 'comment' | 'filename' | 'md5'
+export type FilterAction = 'hide' | 'highlight' | 'hide_with_replies' | 'auto_watch'
+// Which posts of a thread a rule looks at
+export type FilterPosts = 'all' | 'op_only' | 'replies_only'
+
+export interface FilterRuleInput {
+    field: FilterField
+    pattern: string
+    boards?: string[]
+    exclude_boards?: string[]
+    posts?: FilterPosts
+    action: FilterAction
+    enabled?: boolean
+}
+
+export interface FilterRule extends Required<FilterRuleInput> {
+    id: number
+    created_at: number
+}
+
+export async function listFilterRules(): Promise<FilterRule[]> {
+    return await invoke<FilterRule[]>('list_filter_rules')
+}
+
+export async function addFilterRule(rule: FilterRuleInput): Promise<FilterRule> {
+    return await invoke<FilterRule>('add_filter_rule', { rule })
+}
+
+export async function updateFilterRule(id: number, rule: FilterRuleInput): Promise<FilterRule> {
+    return await invoke<FilterRule>('update_filter_rule', { id, rule })
+}
+
+export async function deleteFilterRule(id: number): Promise<boolean> {
+    return await invoke<boolean>('delete_filter_rule', { id })
+}
+
+// Import from a 4chan X settings export (JSON); returns the number of rules added
+export async function import4chanXFilters(data: string): Promise<number> {
+    return await invoke<number>('import_4chanx_filters', { data })
+}
+
+// Offline thread archive (progress arrives as 'archive-progress' events)
+export interface ArchiveSummary {
+    path: string
+    posts: number
+    new_posts: number
+    files_downloaded: number
+    files_failed: number
+}
+
+export interface ArchiveProgress {
+    site: string
+    board: string
+    thread_id: number
+    stage: 'thread' | 'media' | 'done'
+    done: number
+    total: number
+    current: string | null
+}
+
+export async function archiveThread(site: string, board: string, threadId: number, destDir: string): Promise<ArchiveSummary> {
+    return await invoke<ArchiveSummary>('archive_thread', { site, board, threadId, destDir })
+}
+
+// Bulk download queue (events: 'download-progress' per job, 'download-queue' aggregate)
+export type DownloadState = 'queued' | 'downloading' | 'paused' | 'completed' | 'failed' | 'cancelled'
+
+export interface MediaFilter {
+    extensions?: string[]
+    min_size?: number
+    max_size?: number
+    min_width?: number
+    min_height?: number
+}
+
+export interface DownloadJob {
+    id: number
+    board: string
+    thread_id: number
+    post_no: number
+    url: string
+    path: string
+    md5: string | null
+    state: DownloadState
+    downloaded: number
+    total: number | null
+    verified: boolean
+    error: string | null
+}
+
+export interface QueueProgress {
+    queued: number
+    active: number
+    paused: number
+    completed: number
+    failed: number
+    cancelled: number
+    bytes_done: number
+    bytes_total: number
+}
+
+export async function enqueueThreadMedia(site: string, board: string, threadId: number, destDir: string, filter?: MediaFilter): Promise<DownloadJob[]> {
+    return await invoke<DownloadJob[]>('enqueue_thread_media', { site, board, threadId, destDir, filter })
+}
+
+export async function listDownloads(): Promise<DownloadJob[]> {
+    return await invoke<DownloadJob[]>('list_downloads')
+}
+
+export async function pauseDownload(id: number): Promise<void> {
+    return await invoke('pause_download', { id })
+}
+
+export async function resumeDownload(id: number): Promise<void> {
+    return await invoke('resume_download', { id })
+}
+
+export async function cancelDownload(id: number): Promise<void> {
+    return await invoke('cancel_download', { id })
+}
+
+export async function clearFinishedDownloads(): Promise<void> {
+    return await invoke('clear_finished_downloads')
+}
+
+export async function setDownloadConcurrency(concurrency: number): Promise<void> {
+    return await invoke('set_download_concurrency', { concurrency })
+}
+
+// chanmedia:// URL for a thumbnail, image or video; served from the media
+// cache and fetched upstream on a miss (verified against md5 when given).
+// board/threadId tie the file to its thread so pinned threads keep it.
+export function mediaUrl(
+    url: string,
+    options: { md5?: string; board?: string; threadId?: number; thumbnail?: boolean | number } = {}
+): string {
+    const params = new URLSearchParams()
+    if (options.md5) params.set('md5', options.md5)
+    // Generated thumbnail: a size in pixels, or the configured size
+    if (options.thumbnail) {
+        params.set('thumb', options.thumbnail === true ? '' : String(options.thumbnail))
+    }
+    if (options.board && options.threadId) {
+        params.set('board', options.board)
+        params.set('thread', String(options.threadId))
+    }
+    const src = convertFileSrc(url, 'chanmedia')
+    const query = params.toString()
+    return query ? `${src}?${query}` : src
 }
 
 // Image proxy to bypass CORS
-export async function fetchImageWithProxy(url: string): Promise<string> {
-    return await invoke<string>('fetch_image_with_cors_bypass', { url })
+export async function fetchImageWithProxy(url: string, md5?: string): Promise<string> {
+    return await invoke<string>('fetch_image_with_cors_bypass', { url, md5 })
 }
 
 export async function clearImageCache(): Promise<void> {
@@ -28,14 +466,38 @@ export async function clearImageCache(): Promise<void> {
 }
 
 // Media caching
-export async function downloadMedia(url: string, board: string, threadId: number): Promise<string> {
-    return await invoke<string>('download_media', { url, board, threadId })
+// Verified against the post's base64 MD5 when given; reposts are stored once
+export async function downloadMedia(url: string, board: string, threadId: number, md5?: string): Promise<string> {
+    return await invoke<string>('download_media', { url, board, threadId, md5 })
 }
 
 export async function getCachedMediaPath(board: string, threadId: number, filename: string): Promise<string | null> {
     return await invoke<string | null>('get_cached_media_path', { board, threadId, filename })
 }
 
+export interface LocalMediaInfo {
+    width: number
+    height: number
+    size: number
+    md5: string
+    thumbnail: { width: number; height: number; source_width: number; source_height: number }
+    // chanmedia:// URL of the thumbnail
+    thumbnail_url: string
+}
+
+// Dimensions and a generated thumbnail of a file picked for posting
+export async function getLocalMediaInfo(path: string, thumbnailSize?: number): Promise<LocalMediaInfo> {
+    return await invoke<LocalMediaInfo>('get_local_media_info', { path, thumbnailSize })
+}
+
+export async function getThumbnailSize(): Promise<number> {
+    return await invoke<number>('get_thumbnail_size')
+}
+
+export async function setThumbnailSize(size: number): Promise<number> {
+    return await invoke<number>('set_thumbnail_size', { size })
+}
+
 export async function clearMediaCache(): Promise<void> {
     return await invoke('clear_media_cache')
 }
@@ -54,21 +516,164 @@ export interface PostRequest {
     comment: string
     file_path?: string
     file_name?: string
+    captcha_challenge?: string
+    captcha_response?: string
+    sanitize?: SanitizeOptions
+}
+
+export interface BoardCapabilities {
+    id: string
+    name: string
+    category: string
+    category_name: string
+    nsfw: boolean
+    description?: string
+    pages: number
+    per_page: number
+    max_filesize: number
+    max_webm_filesize: number
+    max_comment_chars: number
+    max_webm_duration: number
+    min_image_width: number
+    min_image_height: number
+    bump_limit: number
+    image_limit: number
+    cooldowns: { threads: number; replies: number; images: number }
+    spoilers: boolean
+    custom_spoilers?: number
+    is_archived: boolean
+    country_flags: boolean
+    board_flags?: Record<string, string>
+    user_ids: boolean
+    oekaki: boolean
+    sjis_tags: boolean
+    code_tags: boolean
+    math_tags: boolean
+    text_only: boolean
+    forced_anon: boolean
+    webm_audio: boolean
+    require_subject: boolean
+}
+
+// Full boards.json record of a 4chan board, merged with our categories
+export async function getBoardCapabilities(board: string): Promise<BoardCapabilities> {
+    return await invoke<BoardCapabilities>('get_board_capabilities', { board })
+}
+
+export async function listBoardCapabilities(): Promise<BoardCapabilities[]> {
+    return await invoke<BoardCapabilities[]>('list_board_capabilities')
+}
+
+export interface UploadLimits {
+    max_filesize: number
+    max_webm_filesize: number
+    extensions: string[]
+    max_width: number
+    max_height: number
+    min_width?: number
+    min_height?: number
 }
 
+// Applied to the attached file before it is uploaded
+export interface SanitizeOptions {
+    strip_metadata?: boolean
+    randomize_filename?: boolean
+    noise_bytes?: number
+    limits?: UploadLimits
+}
+
+export type UploadError =
+    | { kind: 'too_large'; size: number; max: number }
+    | { kind: 'extension_not_allowed'; ext: string; board: string; allowed: string[] }
+    | { kind: 'dimensions_too_large'; width: number; height: number; max_width: number; max_height: number }
+    | { kind: 'dimensions_too_small'; width: number; height: number; min_width: number; min_height: number }
+    | { kind: 'unreadable'; message: string }
+
 export interface PostResponse {
     success: boolean
     thread_id?: number
     post_id?: number
     error?: string
+    validation_errors?: UploadError[]
+}
+
+export interface UploadCheck {
+    filename: string
+    size: number
+    width?: number
+    height?: number
+    errors: UploadError[]
+}
+
+// Check a file against the board's limits without posting it
+export async function validateUpload(board: string, filePath: string, sanitize?: SanitizeOptions): Promise<UploadCheck> {
+    return await invoke<UploadCheck>('validate_upload', { board, filePath, sanitize })
+}
+
+export async function getUploadLimits(board: string): Promise<UploadLimits> {
+    return await invoke<UploadLimits>('get_upload_limits', { board })
 }
 
 export async function submitPost(request: PostRequest, passToken?: string | null): Promise<PostResponse> {
     return await invoke<PostResponse>('submit_post', { request, passToken })
 }
 
-export async function getPostCooldown(): Promise<number> {
-    return await invoke<number>('get_post_cooldown')
+export interface OwnPost {
+    site: string
+    board: string
+    thread_id: number
+    post_no: number
+    posted_at: number
+}
+
+// Posts recorded as ours ("(You)") after a successful submit
+export async function getOwnPosts(site: string, board: string, threadId: number): Promise<OwnPost[]> {
+    return await invoke<OwnPost[]>('get_own_posts', { site, board, threadId })
+}
+
+export type PostKind = 'thread' | 'reply' | 'reply_with_image'
+
+// Seconds until the next post of this kind on the board; without a board, the longest active cooldown
+export async function getPostCooldown(board?: string, kind?: PostKind): Promise<number> {
+    return await invoke<number>('get_post_cooldown', { board, kind })
+}
+
+// Every active cooldown, by board and kind of post
+export async function getPostCooldowns(): Promise<Record<string, Partial<Record<PostKind, number>>>> {
+    return await invoke('get_post_cooldowns')
+}
+
+export type QueueStatus = 'queued' | 'posting' | 'sent' | 'failed'
+
+export interface QueuedPost {
+    id: number
+    request: PostRequest
+    status: QueueStatus
+    attempts: number
+    next_attempt_at: number
+    last_error?: string
+    thread_id?: number
+    post_id?: number
+    created_at: number
+    updated_at: number
+}
+
+// Post queue: drafts are sent in the background once their cooldown expires.
+// Changes arrive as `post-queue` events, outcomes as `queued-post-sent` / `queued-post-failed`.
+export async function queuePost(request: PostRequest, passToken?: string): Promise<QueuedPost> {
+    return await invoke<QueuedPost>('queue_post', { request, passToken })
+}
+
+export async function listQueuedPosts(): Promise<QueuedPost[]> {
+    return await invoke<QueuedPost[]>('list_queued_posts')
+}
+
+export async function updateQueuedPost(id: number, request: PostRequest, passToken?: string): Promise<QueuedPost> {
+    return await invoke<QueuedPost>('update_queued_post', { id, request, passToken })
+}
+
+export async function cancelQueuedPost(id: number): Promise<boolean> {
+    return await invoke<boolean>('cancel_queued_post', { id })
 }
 
 export async function validatePassToken(token: string): Promise<boolean> {
@@ -84,7 +689,18 @@ export function getThumbnailUrl(board: string, tim: number): string {
     return `https://i.4cdn.org/${board}/${tim}s.jpg`
 }
 
-export function getThreadUrl(board: string, threadId: number): string {
-    return `https://boards.4channel.org/${board}/thread/${threadId}`
+export function getThreadUrl(board: string, threadId: number, site = '4chan'): string {
+    switch (site) {
+        case '7chan':
+            return `https://7chan.org/${board}/res/${threadId}.html`
+        case '4plebs':
+            return `https://archive.4plebs.org/${board}/thread/${threadId}/`
+        case 'archivedmoe':
+            return `https://archived.moe/${board}/thread/${threadId}/`
+        case '22chan':
+            return `https://22chan.org/${board}/${threadId}/`
+        default:
+            return `https://boards.4channel.org/${board}/thread/${threadId}`
+    }
 }
 